@@ -1,4 +1,4 @@
-use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion, Throughput};
 use std::time::Duration;
 use ttl_queue::TtlQueue;
 
@@ -39,6 +39,25 @@ pub fn criterion_benchmark(c: &mut Criterion) {
         });
     }
     group.finish();
+
+    let mut group = c.benchmark_group("refresh, all expired (Duration::ZERO)");
+    for elements in [100_000u64, 1_000_000] {
+        group.throughput(Throughput::Elements(elements));
+        group.bench_with_input(format!("{elements} elements"), &elements, |b, &elems| {
+            b.iter_batched(
+                || {
+                    let mut queue = TtlQueue::new(Duration::ZERO);
+                    for i in 0..elems {
+                        queue.push_back(i);
+                    }
+                    queue
+                },
+                |mut queue| queue.refresh(),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
 }
 
 criterion_group!(benches, criterion_benchmark);
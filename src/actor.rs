@@ -0,0 +1,135 @@
+use crate::{Instant, TtlQueue};
+use tokio::sync::{mpsc, oneshot};
+
+/// The number of commands that can be queued for the actor before senders have to wait.
+const COMMAND_BUFFER: usize = 1024;
+
+enum Command<T> {
+    Push(T),
+    Count(oneshot::Sender<usize>),
+    Drain(oneshot::Sender<Vec<(Instant, T)>>),
+}
+
+/// A cloneable handle to a [`TtlQueue`] owned by a spawned task.
+///
+/// See [`TtlQueue::into_actor`]. The task stops once all handles are dropped.
+#[derive(Debug)]
+pub struct TtlQueueHandle<T> {
+    commands: mpsc::Sender<Command<T>>,
+}
+
+impl<T> Clone for TtlQueueHandle<T> {
+    fn clone(&self) -> Self {
+        Self {
+            commands: self.commands.clone(),
+        }
+    }
+}
+
+impl<T: Send + 'static> TtlQueue<T> {
+    /// Spawns a task on the current tokio runtime that owns the queue and returns
+    /// a cloneable handle to communicate with it.
+    ///
+    /// This removes the need for locking when sharing a queue between tasks.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if called outside of a tokio runtime.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use ttl_queue::TtlQueue;
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() {
+    /// let handle = TtlQueue::new(Duration::from_secs(60)).into_actor();
+    ///
+    /// handle.push_back(42).await.unwrap();
+    /// assert_eq!(handle.count().await, Some(1));
+    /// # }
+    /// ```
+    pub fn into_actor(mut self) -> TtlQueueHandle<T> {
+        let (commands, mut receiver) = mpsc::channel(COMMAND_BUFFER);
+
+        tokio::spawn(async move {
+            while let Some(command) = receiver.recv().await {
+                match command {
+                    Command::Push(element) => self.push_back(element),
+                    Command::Count(reply) => {
+                        let _ = reply.send(self.refresh());
+                    }
+                    Command::Drain(reply) => {
+                        self.refresh();
+                        let mut entries = Vec::with_capacity(self.len());
+                        entries.extend(std::iter::from_fn(|| self.pop_front()));
+                        let _ = reply.send(entries);
+                    }
+                }
+            }
+        });
+
+        TtlQueueHandle { commands }
+    }
+}
+
+impl<T> TtlQueueHandle<T> {
+    /// Pushes an element to the end of the queue.
+    ///
+    /// Returns the element as an error if the owning task has stopped.
+    pub async fn push_back(&self, element: T) -> Result<(), T> {
+        self.commands.send(Command::Push(element)).await.map_err(
+            |mpsc::error::SendError(command)| match command {
+                Command::Push(element) => element,
+                _ => unreachable!("the rejected command is the one that was sent"),
+            },
+        )
+    }
+
+    /// Refreshes the queue and returns the number of currently contained elements,
+    /// or `None` if the owning task has stopped.
+    pub async fn count(&self) -> Option<usize> {
+        let (reply, response) = oneshot::channel();
+        self.commands.send(Command::Count(reply)).await.ok()?;
+        response.await.ok()
+    }
+
+    /// Refreshes the queue, removes all live elements and returns them oldest first,
+    /// or `None` if the owning task has stopped.
+    pub async fn drain(&self) -> Option<Vec<(Instant, T)>> {
+        let (reply, response) = oneshot::channel();
+        self.commands.send(Command::Drain(reply)).await.ok()?;
+        response.await.ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn handles_share_one_queue() {
+        let handle = TtlQueue::new(Duration::MAX).into_actor();
+        let other = handle.clone();
+
+        let producers: Vec<_> = [handle.clone(), other.clone()]
+            .into_iter()
+            .map(|handle| {
+                tokio::spawn(async move {
+                    for i in 0..10 {
+                        handle.push_back(i).await.unwrap();
+                    }
+                })
+            })
+            .collect();
+
+        for producer in producers {
+            producer.await.unwrap();
+        }
+
+        assert_eq!(handle.count().await, Some(20));
+        assert_eq!(other.drain().await.map(|entries| entries.len()), Some(20));
+        assert_eq!(handle.count().await, Some(0));
+    }
+}
@@ -0,0 +1,168 @@
+use crate::TtlQueue;
+use arrow_array::{
+    ArrayRef, BooleanArray, DurationNanosecondArray, Float32Array, Float64Array, Int32Array,
+    Int64Array, RecordBatch, StringArray, UInt32Array, UInt64Array,
+};
+use arrow_schema::{ArrowError, DataType, Field, Schema, TimeUnit};
+use std::sync::Arc;
+
+/// The name of the column holding the age of each element.
+pub const AGE_COLUMN: &str = "age";
+
+/// The name of the column holding the payload of each element.
+pub const VALUE_COLUMN: &str = "value";
+
+/// A payload type that can be converted into an Arrow column.
+///
+/// See [`TtlQueue::to_record_batch`].
+pub trait ArrowPayload: Sized {
+    /// Gets the Arrow data type of the column.
+    fn data_type() -> DataType;
+
+    /// Builds an Arrow column from the values.
+    fn to_array<'a, I>(values: I) -> ArrayRef
+    where
+        I: Iterator<Item = &'a Self>,
+        Self: 'a;
+}
+
+macro_rules! impl_arrow_payload {
+    ($type:ty, $array:ty, $data_type:expr) => {
+        impl ArrowPayload for $type {
+            fn data_type() -> DataType {
+                $data_type
+            }
+
+            fn to_array<'a, I>(values: I) -> ArrayRef
+            where
+                I: Iterator<Item = &'a Self>,
+            {
+                Arc::new(values.cloned().collect::<$array>())
+            }
+        }
+    };
+}
+
+impl_arrow_payload!(i32, Int32Array, DataType::Int32);
+impl_arrow_payload!(i64, Int64Array, DataType::Int64);
+impl_arrow_payload!(u32, UInt32Array, DataType::UInt32);
+impl_arrow_payload!(u64, UInt64Array, DataType::UInt64);
+impl_arrow_payload!(f32, Float32Array, DataType::Float32);
+impl_arrow_payload!(f64, Float64Array, DataType::Float64);
+
+impl ArrowPayload for bool {
+    fn data_type() -> DataType {
+        DataType::Boolean
+    }
+
+    fn to_array<'a, I>(values: I) -> ArrayRef
+    where
+        I: Iterator<Item = &'a Self>,
+    {
+        Arc::new(values.map(|value| Some(*value)).collect::<BooleanArray>())
+    }
+}
+
+impl ArrowPayload for String {
+    fn data_type() -> DataType {
+        DataType::Utf8
+    }
+
+    fn to_array<'a, I>(values: I) -> ArrayRef
+    where
+        I: Iterator<Item = &'a Self>,
+    {
+        Arc::new(values.map(Some).collect::<StringArray>())
+    }
+}
+
+impl ArrowPayload for &str {
+    fn data_type() -> DataType {
+        DataType::Utf8
+    }
+
+    fn to_array<'a, I>(values: I) -> ArrayRef
+    where
+        I: Iterator<Item = &'a Self>,
+        Self: 'a,
+    {
+        Arc::new(values.map(|value| Some(*value)).collect::<StringArray>())
+    }
+}
+
+impl<T: ArrowPayload> TtlQueue<T> {
+    /// Converts the contents of the queue into an Arrow [`RecordBatch`], oldest element first.
+    ///
+    /// The batch has two columns: [`AGE_COLUMN`] holds the age of each element relative to
+    /// the time of the call as a nanosecond duration, [`VALUE_COLUMN`] holds the payload.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use ttl_queue::TtlQueue;
+    /// let mut queue = TtlQueue::new(Duration::from_secs(60));
+    /// queue.push_back(1.0_f64);
+    /// queue.push_back(2.0_f64);
+    ///
+    /// let batch = queue.to_record_batch().unwrap();
+    /// assert_eq!(batch.num_rows(), 2);
+    /// assert_eq!(batch.num_columns(), 2);
+    /// ```
+    pub fn to_record_batch(&self) -> Result<RecordBatch, ArrowError> {
+        let now = self.now();
+        let ages: DurationNanosecondArray = self
+            .iter()
+            .map(|(instant, _)| {
+                let age = now.saturating_duration_since(*instant).as_nanos();
+                i64::try_from(age).unwrap_or(i64::MAX)
+            })
+            .collect::<Vec<_>>()
+            .into();
+        let values = T::to_array(self.iter().map(|(_, value)| value));
+
+        let schema = Schema::new(vec![
+            Field::new(AGE_COLUMN, DataType::Duration(TimeUnit::Nanosecond), false),
+            Field::new(VALUE_COLUMN, T::data_type(), false),
+        ]);
+        RecordBatch::try_new(Arc::new(schema), vec![Arc::new(ages), values])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Instant;
+    use arrow_array::Array;
+    use std::time::Duration;
+
+    #[test]
+    fn record_batch_contains_ages_and_values() {
+        let now = Instant::now();
+        let mut queue = TtlQueue::new(Duration::MAX);
+        queue.push_back_entry(now - Duration::from_secs(2), String::from("a"));
+        queue.push_back_entry(now - Duration::from_secs(1), String::from("b"));
+
+        let batch = queue.to_record_batch().unwrap();
+        assert_eq!(batch.num_rows(), 2);
+
+        let ages = batch
+            .column_by_name(AGE_COLUMN)
+            .unwrap()
+            .as_any()
+            .downcast_ref::<DurationNanosecondArray>()
+            .unwrap();
+        assert!(ages.value(0) >= 2_000_000_000);
+        assert!(ages.value(1) >= 1_000_000_000 && ages.value(1) < ages.value(0));
+
+        let values = batch
+            .column_by_name(VALUE_COLUMN)
+            .unwrap()
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        assert_eq!(values.value(0), "a");
+        assert_eq!(values.value(1), "b");
+        assert_eq!(values.null_count(), 0);
+    }
+}
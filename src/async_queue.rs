@@ -0,0 +1,365 @@
+use crate::{Instant, TtlQueue};
+use std::time::Duration;
+use tokio::sync::{Mutex, MutexGuard, Notify};
+
+/// The shortest time a waiting task sleeps before checking for expired elements again,
+/// so that it does not spin while the next expiry is due or cannot be predicted.
+const MIN_EXPIRY_WAIT: Duration = Duration::from_millis(1);
+
+/// A [`TtlQueue`] guarded by a [`tokio::sync::Mutex`] that can be shared across tasks
+/// without blocking the runtime.
+///
+/// ## Example
+///
+/// ```
+/// # use std::sync::Arc;
+/// # use std::time::Duration;
+/// # use ttl_queue::AsyncTtlQueue;
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// let queue = Arc::new(AsyncTtlQueue::new(Duration::from_secs(60)));
+///
+/// let consumer = {
+///     let queue = queue.clone();
+///     tokio::spawn(async move { queue.pop_front_wait().await })
+/// };
+///
+/// queue.push_back(42).await;
+/// let (_instant, value) = consumer.await.unwrap();
+/// assert_eq!(value, 42);
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct AsyncTtlQueue<T> {
+    queue: Mutex<TtlQueue<T>>,
+    pushed: Notify,
+    /// Signaled when a push makes a queue without unexpired elements non-empty.
+    nonempty: Notify,
+    /// Signaled when elements are removed from the queue.
+    removed: Notify,
+}
+
+impl<T> AsyncTtlQueue<T> {
+    /// Creates an empty [`AsyncTtlQueue`] with default capacity.
+    pub fn new(ttl: Duration) -> Self {
+        Self::from(TtlQueue::new(ttl))
+    }
+
+    /// Creates an empty [`AsyncTtlQueue`] for at least `capacity` elements.
+    pub fn with_capacity(ttl: Duration, capacity: usize) -> Self {
+        Self::from(TtlQueue::with_capacity(ttl, capacity))
+    }
+
+    /// Locks the queue for exclusive access.
+    ///
+    /// Tasks waiting on the queue are not woken by changes made through the guard;
+    /// use [`with_lock`](Self::with_lock) to modify the queue.
+    pub async fn lock(&self) -> MutexGuard<'_, TtlQueue<T>> {
+        self.queue.lock().await
+    }
+
+    /// Calls the function with exclusive access to the queue and returns its result,
+    /// waking waiting tasks afterwards.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use ttl_queue::AsyncTtlQueue;
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() {
+    /// let queue = AsyncTtlQueue::new(Duration::from_secs(60));
+    /// queue
+    ///     .with_lock(|queue| queue.extend([(Duration::ZERO, 1), (Duration::ZERO, 2)]))
+    ///     .await;
+    ///
+    /// assert_eq!(queue.pop_front_wait().await.1, 1);
+    /// # }
+    /// ```
+    pub async fn with_lock<R>(&self, f: impl FnOnce(&mut TtlQueue<T>) -> R) -> R {
+        let (result, was_empty, is_empty) = {
+            let mut queue = self.lock().await;
+            let was_empty = queue.valid_len() == 0;
+            let result = f(&mut queue);
+            (result, was_empty, queue.valid_len() == 0)
+        };
+        if !is_empty {
+            self.notify_pushed(was_empty);
+        }
+        self.removed.notify_waiters();
+        result
+    }
+
+    /// Consumes the wrapper and returns the underlying queue.
+    pub fn into_inner(self) -> TtlQueue<T> {
+        self.queue.into_inner()
+    }
+
+    /// Pushes an element to the end of the queue.
+    pub async fn push_back(&self, element: T) {
+        let was_empty = {
+            let mut queue = self.lock().await;
+            let was_empty = queue.valid_len() == 0;
+            queue.push_back(element);
+            was_empty
+        };
+        self.notify_pushed(was_empty);
+    }
+
+    /// Pushes an element to the end of the queue and returns the number of items
+    /// currently in the queue. See [`TtlQueue::refresh_and_push_back`].
+    pub async fn refresh_and_push_back(&self, element: T) -> usize {
+        let (count, was_empty) = {
+            let mut queue = self.lock().await;
+            let was_empty = queue.valid_len() == 0;
+            (queue.refresh_and_push_back(element), was_empty)
+        };
+        self.notify_pushed(was_empty);
+        count
+    }
+
+    fn notify_pushed(&self, was_empty: bool) {
+        self.pushed.notify_one();
+        if was_empty {
+            self.nonempty.notify_waiters();
+        }
+    }
+
+    /// Waits until the queue holds an unexpired element, e.g. to sleep while there is
+    /// nothing to process instead of polling [`refresh`](Self::refresh) in a loop.
+    ///
+    /// Resolves once a push makes an empty queue non-empty, or immediately if the queue
+    /// already holds unexpired elements. Unlike [`pop_front_wait`](Self::pop_front_wait),
+    /// this wakes all waiting tasks and leaves the elements in the queue.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use std::sync::Arc;
+    /// # use std::time::Duration;
+    /// # use ttl_queue::AsyncTtlQueue;
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() {
+    /// let queue = Arc::new(AsyncTtlQueue::new(Duration::from_secs(60)));
+    ///
+    /// let consumer = {
+    ///     let queue = queue.clone();
+    ///     tokio::spawn(async move {
+    ///         queue.notified_nonempty().await;
+    ///         queue.len().await
+    ///     })
+    /// };
+    ///
+    /// tokio::task::yield_now().await;
+    /// queue.push_back("job").await;
+    /// assert_eq!(consumer.await.unwrap(), 1);
+    /// # }
+    /// ```
+    pub async fn notified_nonempty(&self) {
+        let notified = self.nonempty.notified();
+        tokio::pin!(notified);
+        notified.as_mut().enable();
+
+        if self.lock().await.valid_len() > 0 {
+            return;
+        }
+        notified.await;
+    }
+
+    /// Refreshes the queue and returns the number of currently contained elements.
+    pub async fn refresh(&self) -> usize {
+        let count = self.lock().await.refresh();
+        self.removed.notify_waiters();
+        count
+    }
+
+    /// Gets the element from the front of the queue if it exists, as well as the
+    /// time instant at which it was added.
+    pub async fn pop_front(&self) -> Option<(Instant, T)> {
+        let entry = self.lock().await.pop_front();
+        self.removed.notify_waiters();
+        entry
+    }
+
+    /// Waits until fewer than `n` unexpired elements are in the queue, either because
+    /// elements expire or because they are removed, e.g. to stop admitting new jobs
+    /// while too many were accepted within the TTL.
+    ///
+    /// Resolves immediately if the queue already holds fewer than `n` unexpired elements,
+    /// and never if `n` is zero. Expired elements are removed while waiting.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use ttl_queue::AsyncTtlQueue;
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() {
+    /// let admitted = AsyncTtlQueue::new(Duration::from_millis(50));
+    /// for job in 0..3 {
+    ///     admitted.push_back(job).await;
+    /// }
+    ///
+    /// admitted.wait_below(3).await;
+    /// assert_eq!(admitted.len().await, 0);
+    /// # }
+    /// ```
+    pub async fn wait_below(&self, n: usize) {
+        loop {
+            let removed = self.removed.notified();
+            tokio::pin!(removed);
+            removed.as_mut().enable();
+
+            let timeout = {
+                let mut queue = self.lock().await;
+                queue.refresh();
+                if queue.valid_len() < n {
+                    return;
+                }
+                queue.time_until_next_expiry()
+            };
+
+            match timeout {
+                Some(timeout) => {
+                    let _ = tokio::time::timeout(timeout.max(MIN_EXPIRY_WAIT), removed).await;
+                }
+                None => removed.await,
+            }
+        }
+    }
+
+    /// Waits until a live element is available, then removes it from the front of
+    /// the queue and returns it, as well as the time instant at which it was added.
+    ///
+    /// Expired elements are dropped rather than returned.
+    pub async fn pop_front_wait(&self) -> (Instant, T) {
+        loop {
+            {
+                let mut queue = self.lock().await;
+                queue.refresh();
+                if let Some(entry) = queue.pop_front() {
+                    // Pass the wake-up on to another consumer while elements remain,
+                    // e.g. after several were added via `with_lock`.
+                    let remaining = !queue.is_empty();
+                    drop(queue);
+                    if remaining {
+                        self.pushed.notify_one();
+                    }
+                    self.removed.notify_waiters();
+                    return entry;
+                }
+            }
+
+            self.pushed.notified().await;
+        }
+    }
+
+    /// Gets the number elements currently in the queue, including potentially expired elements.
+    pub async fn len(&self) -> usize {
+        self.lock().await.len()
+    }
+
+    /// Returns `true` if the queue is definitely empty or `false` if the queue is
+    /// possibly empty.
+    pub async fn is_empty(&self) -> bool {
+        self.lock().await.is_empty()
+    }
+}
+
+impl<T> From<TtlQueue<T>> for AsyncTtlQueue<T> {
+    fn from(queue: TtlQueue<T>) -> Self {
+        Self {
+            queue: Mutex::new(queue),
+            pushed: Notify::new(),
+            nonempty: Notify::new(),
+            removed: Notify::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn pop_front_wait_waits_for_push() {
+        let queue = Arc::new(AsyncTtlQueue::new(Duration::MAX));
+
+        let consumer = {
+            let queue = queue.clone();
+            tokio::spawn(async move {
+                let mut values = Vec::new();
+                for _ in 0..3 {
+                    values.push(queue.pop_front_wait().await.1);
+                }
+                values
+            })
+        };
+
+        for i in 0..3 {
+            tokio::task::yield_now().await;
+            queue.push_back(i).await;
+        }
+
+        assert_eq!(consumer.await.unwrap(), [0, 1, 2]);
+        assert!(queue.is_empty().await);
+    }
+
+    #[tokio::test]
+    async fn wait_below_does_not_spin_on_unpredictable_expiry() {
+        // Elements past the TTL that the policy keeps alive have no predictable expiry.
+        let checks = Arc::new(AtomicUsize::new(0));
+        let queue = Arc::new(AsyncTtlQueue::new(Duration::ZERO));
+        queue.lock().await.set_expiry_policy({
+            let checks = checks.clone();
+            move |_inserted: Instant, _now: Instant, _value: &i32| {
+                checks.fetch_add(1, Ordering::Relaxed);
+                false
+            }
+        });
+        queue.push_back(1).await;
+        assert_eq!(
+            queue.lock().await.time_until_next_expiry(),
+            Some(Duration::ZERO)
+        );
+
+        let waiter = {
+            let queue = queue.clone();
+            tokio::spawn(async move { queue.wait_below(1).await })
+        };
+
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(queue.pop_front().await.is_some());
+        waiter.await.unwrap();
+        assert!(checks.load(Ordering::Relaxed) < 100);
+    }
+
+    #[tokio::test]
+    async fn changes_through_with_lock_wake_waiting_tasks() {
+        let queue = Arc::new(AsyncTtlQueue::new(Duration::MAX));
+
+        let waiters: Vec<_> = (0..2)
+            .map(|_| {
+                let queue = queue.clone();
+                tokio::spawn(async move {
+                    queue.notified_nonempty().await;
+                    queue.pop_front_wait().await.1
+                })
+            })
+            .collect();
+
+        tokio::task::yield_now().await;
+        queue
+            .with_lock(|queue| queue.extend([(Duration::ZERO, 1), (Duration::ZERO, 2)]))
+            .await;
+
+        let mut values = Vec::new();
+        for waiter in waiters {
+            values.push(waiter.await.unwrap());
+        }
+        values.sort_unstable();
+        assert_eq!(values, [1, 2]);
+    }
+}
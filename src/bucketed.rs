@@ -0,0 +1,149 @@
+use crate::Instant;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// A queue with approximate expiry that groups elements into time buckets.
+///
+/// Instead of stamping every element, the TTL is divided into a fixed number of
+/// buckets and each bucket carries a single timestamp for all elements pushed
+/// during its span. Whole buckets are dropped at once, so a [`refresh`](Self::refresh)
+/// is O(K) in the number of buckets regardless of the number of elements.
+///
+/// Elements never expire early, but may be kept up to [`max_error`](Self::max_error),
+/// i.e. `ttl / K`, longer than the TTL.
+///
+/// ## Example
+///
+/// ```
+/// # use std::time::Duration;
+/// # use ttl_queue::BucketedTtlQueue;
+/// let mut queue = BucketedTtlQueue::new(Duration::from_secs(60), 12);
+/// queue.push_back(1);
+/// queue.push_back(2);
+///
+/// assert_eq!(queue.len(), 2);
+/// assert_eq!(queue.max_error(), Duration::from_secs(5));
+/// assert_eq!(queue.refresh(), 2);
+/// ```
+#[derive(Debug)]
+pub struct BucketedTtlQueue<T> {
+    ttl: Duration,
+    bucket_width: Duration,
+    /// The buckets, ordered by their start time.
+    buckets: VecDeque<(Instant, VecDeque<T>)>,
+    len: usize,
+}
+
+impl<T> BucketedTtlQueue<T> {
+    /// Creates an empty queue dividing the TTL into the specified number of buckets.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `buckets` is zero.
+    pub fn new(ttl: Duration, buckets: u32) -> Self {
+        assert_ne!(buckets, 0, "At least one bucket is required");
+        Self {
+            ttl,
+            bucket_width: ttl / buckets,
+            buckets: VecDeque::with_capacity(buckets as usize + 1),
+            len: 0,
+        }
+    }
+
+    /// Gets the time-to-live of the elements.
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Gets the maximum time an element may be kept beyond its TTL.
+    pub fn max_error(&self) -> Duration {
+        self.bucket_width
+    }
+
+    /// Gets the number of elements in the queue.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the queue contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Pushes an element to the end of the queue.
+    pub fn push_back(&mut self, element: T) {
+        self.push_back_at(Instant::now(), element)
+    }
+
+    fn push_back_at(&mut self, now: Instant, element: T) {
+        match self.buckets.back_mut() {
+            Some((start, bucket)) if now.saturating_duration_since(*start) < self.bucket_width => {
+                bucket.push_back(element)
+            }
+            _ => self.buckets.push_back((now, VecDeque::from([element]))),
+        }
+        self.len += 1;
+    }
+
+    /// Removes the oldest element from the queue.
+    ///
+    /// The returned instant is the start of the bucket the element was pushed into.
+    pub fn pop_front(&mut self) -> Option<(Instant, T)> {
+        let (start, bucket) = self.buckets.front_mut()?;
+        let start = *start;
+        let element = bucket.pop_front()?;
+        if bucket.is_empty() {
+            self.buckets.pop_front();
+        }
+
+        self.len -= 1;
+        Some((start, element))
+    }
+
+    /// Drops all buckets whose TTL elapsed and returns the number of elements remaining.
+    pub fn refresh(&mut self) -> usize {
+        self.refresh_at(Instant::now())
+    }
+
+    fn refresh_at(&mut self, now: Instant) -> usize {
+        let lifetime = self.ttl.saturating_add(self.bucket_width);
+        while let Some((start, bucket)) = self.buckets.front() {
+            if now.saturating_duration_since(*start) < lifetime {
+                break;
+            }
+
+            self.len -= bucket.len();
+            self.buckets.pop_front();
+        }
+
+        self.len
+    }
+
+    /// Returns an iterator over the elements, each paired with the start of its bucket.
+    pub fn iter(&self) -> impl Iterator<Item = (Instant, &T)> {
+        self.buckets
+            .iter()
+            .flat_map(|(start, bucket)| bucket.iter().map(move |element| (*start, element)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expires_whole_buckets() {
+        let mut queue = BucketedTtlQueue::new(Duration::from_secs(10), 10);
+        let start = Instant::now();
+        for second in 0..20u64 {
+            queue.push_back_at(start + Duration::from_millis(second * 500), second);
+        }
+
+        assert_eq!(queue.buckets.len(), 10);
+        assert_eq!(queue.refresh_at(start + Duration::from_millis(10_999)), 20);
+        assert_eq!(queue.refresh_at(start + Duration::from_secs(11)), 18);
+        assert_eq!(queue.pop_front().map(|(_, value)| value), Some(2));
+        assert_eq!(queue.iter().count(), 17);
+        assert_eq!(queue.refresh_at(start + Duration::from_secs(21)), 0);
+    }
+}
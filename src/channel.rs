@@ -0,0 +1,259 @@
+use crate::{Clock, Instant, TtlQueue};
+use std::fmt::{Debug, Formatter};
+use std::sync::mpsc::{self, Receiver, SendError, Sender, TryRecvError};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Creates a [`TtlQueue`] that is fed through a lock-free multi-producer channel.
+///
+/// Producers push through the returned [`TtlSender`], which stamps each element with
+/// the time of the push and never blocks on the consumer. The single [`TtlReceiver`]
+/// moves pending elements into its timed window whenever it is refreshed.
+///
+/// See [`TtlQueue::into_channel`] to feed a queue with a configured clock, listeners
+/// or hooks.
+///
+/// ## Example
+///
+/// ```
+/// # use std::thread;
+/// # use std::time::Duration;
+/// let (sender, mut receiver) = ttl_queue::channel(Duration::from_secs(60));
+///
+/// let producer = thread::spawn(move || {
+///     for i in 0..10 {
+///         sender.push_back(i).unwrap();
+///     }
+/// });
+///
+/// producer.join().unwrap();
+/// assert_eq!(receiver.refresh(), 10);
+/// ```
+pub fn channel<T>(ttl: Duration) -> (TtlSender<T>, TtlReceiver<T>) {
+    TtlQueue::new(ttl).into_channel()
+}
+
+/// The producing half of a [`channel`]. Can be cloned to obtain multiple producers.
+pub struct TtlSender<T> {
+    sender: Sender<(Instant, T)>,
+    clock: Arc<dyn Clock>,
+}
+
+/// The consuming half of a [`channel`], owning the timed window.
+#[derive(Debug)]
+pub struct TtlReceiver<T> {
+    receiver: Receiver<(Instant, T)>,
+    queue: TtlQueue<T>,
+    senders_dropped: bool,
+}
+
+impl<T> TtlQueue<T> {
+    /// Turns the queue into the consuming half of a [`channel`] and returns it along
+    /// with a [`TtlSender`] feeding it.
+    ///
+    /// Senders stamp elements with the [clock](Self::set_clock) of this queue at the
+    /// time of the call; a clock set on the receiver's queue later is not picked up.
+    /// The receiver keeps the elements and the configuration of this queue.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use ttl_queue::{TickClock, TtlQueue};
+    /// let clock = TickClock::new();
+    /// let mut queue = TtlQueue::new(TickClock::ttl(10));
+    /// queue.set_clock(clock.clone());
+    ///
+    /// let (sender, mut receiver) = queue.into_channel();
+    /// sender.push_back("first").unwrap();
+    /// clock.advance(5);
+    /// sender.push_back("second").unwrap();
+    ///
+    /// clock.advance(5);
+    /// assert_eq!(receiver.refresh(), 1);
+    /// ```
+    pub fn into_channel(self) -> (TtlSender<T>, TtlReceiver<T>) {
+        let (sender, receiver) = mpsc::channel();
+        let sender = TtlSender {
+            sender,
+            clock: self.clock.clone(),
+        };
+        let receiver = TtlReceiver {
+            receiver,
+            queue: self,
+            senders_dropped: false,
+        };
+        (sender, receiver)
+    }
+}
+
+impl<T> Clone for TtlSender<T> {
+    fn clone(&self) -> Self {
+        Self {
+            sender: self.sender.clone(),
+            clock: self.clock.clone(),
+        }
+    }
+}
+
+impl<T> Debug for TtlSender<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TtlSender").finish_non_exhaustive()
+    }
+}
+
+impl<T> TtlSender<T> {
+    /// Pushes an element to the end of the queue, stamped with the current time of
+    /// the queue's clock.
+    ///
+    /// Returns the element as an error if the [`TtlReceiver`] was dropped.
+    pub fn push_back(&self, element: T) -> Result<(), T> {
+        self.sender
+            .send((self.clock.now(), element))
+            .map_err(|SendError((_instant, element))| element)
+    }
+}
+
+impl<T> TtlReceiver<T> {
+    /// Moves all pending elements into the timed window and returns the number of
+    /// elements actually inserted.
+    ///
+    /// Elements are inserted as by [`TtlQueue::push_back`], so the push hooks, the
+    /// maximum length and the refresh policy of the queue apply. Concurrent producers
+    /// may deliver timestamps slightly out of order; to retain the ordering of the
+    /// window, such elements are stamped with the timestamp of the preceding element
+    /// instead. While expiry is [paused](TtlQueue::pause), elements are stamped with
+    /// the time of the pause at the latest.
+    pub fn drain_pending(&mut self) -> usize {
+        let mut count = 0;
+        loop {
+            let (instant, element) = match self.receiver.try_recv() {
+                Ok(entry) => entry,
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.senders_dropped = true;
+                    break;
+                }
+            };
+
+            if self.queue.push_back_stamped(instant, element) {
+                count += 1;
+            }
+        }
+        count
+    }
+
+    /// Returns `true` if all senders were dropped and all of their elements were
+    /// moved into the timed window, i.e. no further elements will arrive.
+    pub fn is_closed(&mut self) -> bool {
+        if !self.senders_dropped {
+            self.drain_pending();
+        }
+        self.senders_dropped
+    }
+
+    /// Drains pending elements, refreshes the queue and returns the number of
+    /// currently contained elements.
+    pub fn refresh(&mut self) -> usize {
+        self.drain_pending();
+        self.queue.refresh()
+    }
+
+    /// Drains pending elements and gets the element from the front of the queue if
+    /// it exists, as well as the time instant at which it was added.
+    pub fn pop_front(&mut self) -> Option<(Instant, T)> {
+        self.drain_pending();
+        self.queue.pop_front()
+    }
+
+    /// Gets the timed window. Pending elements are only visible after a call to
+    /// [`drain_pending`](Self::drain_pending) or [`refresh`](Self::refresh).
+    pub fn queue(&self) -> &TtlQueue<T> {
+        &self.queue
+    }
+
+    /// Gets the timed window mutably.
+    pub fn queue_mut(&mut self) -> &mut TtlQueue<T> {
+        &mut self.queue
+    }
+
+    /// Consumes the receiver and returns the timed window, including all pending elements.
+    pub fn into_queue(mut self) -> TtlQueue<T> {
+        self.drain_pending();
+        self.queue
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TickClock;
+    use std::thread;
+
+    #[test]
+    fn producers_do_not_block() {
+        let (sender, mut receiver) = channel(Duration::MAX);
+
+        let producers: Vec<_> = (0..4)
+            .map(|_| {
+                let sender = sender.clone();
+                thread::spawn(move || {
+                    for i in 0..100 {
+                        sender.push_back(i).unwrap();
+                    }
+                })
+            })
+            .collect();
+
+        for producer in producers {
+            producer.join().unwrap();
+        }
+
+        assert!(receiver.queue().is_empty());
+        assert_eq!(receiver.refresh(), 400);
+
+        let queue = receiver.into_queue();
+        assert!(queue
+            .iter()
+            .zip(queue.iter().skip(1))
+            .all(|(a, b)| a.0 <= b.0));
+    }
+
+    #[test]
+    fn senders_use_the_queue_clock_and_hooks() {
+        let clock = TickClock::new();
+        let mut queue = TtlQueue::with_max_len(TickClock::ttl(10), 2);
+        queue.set_clock(clock.clone());
+        queue.on_push(|value: i32| (value >= 0).then_some(value));
+
+        let (sender, mut receiver) = queue.into_channel();
+        for value in [-1, 1, 2, 3] {
+            sender.push_back(value).unwrap();
+            clock.tick();
+        }
+        assert_eq!(receiver.drain_pending(), 2);
+
+        let stamps: Vec<_> = receiver
+            .queue()
+            .iter()
+            .map(|(instant, _)| clock.ticks_at(*instant))
+            .collect();
+        assert_eq!(stamps, [1, 2]);
+
+        receiver.queue_mut().pause();
+        clock.advance(20);
+        assert_eq!(receiver.pop_front().map(|(_, value)| value), Some(1));
+        sender.push_back(4).unwrap();
+        assert_eq!(receiver.refresh(), 2);
+        let (instant, value) = receiver.pop_front().unwrap();
+        assert_eq!((clock.ticks_at(instant), value), (2, 2));
+        let (instant, value) = receiver.pop_front().unwrap();
+        assert_eq!((clock.ticks_at(instant), value), (4, 4));
+    }
+
+    #[test]
+    fn push_fails_without_receiver() {
+        let (sender, receiver) = channel(Duration::MAX);
+        drop(receiver);
+        assert_eq!(sender.push_back(42), Err(42));
+    }
+}
@@ -0,0 +1,421 @@
+use crate::Instant;
+use std::fmt::{Debug, Formatter};
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, PoisonError, Weak};
+use std::thread;
+use std::time::Duration;
+
+/// A source of the current time for a [`TtlQueue`](crate::TtlQueue).
+///
+/// By default, queues use the [`SystemClock`]. A different clock can be configured
+/// via [`TtlQueue::set_clock`](crate::TtlQueue::set_clock), e.g. a [`CachedClock`]
+/// for workloads in which reading the system clock dominates.
+pub trait Clock: Send + Sync {
+    /// Gets the current time instant.
+    fn now(&self) -> Instant;
+}
+
+impl<C: Clock + ?Sized> Clock for Arc<C> {
+    fn now(&self) -> Instant {
+        (**self).now()
+    }
+}
+
+/// A [`Clock`] reading the system's monotonic clock on every call.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// A [`Clock`] that caches the time and only re-reads the system clock occasionally,
+/// trading timestamp precision for cheaper time queries.
+///
+/// The clock either re-reads the system clock every N queries (see [`every`](Self::every))
+/// or is updated by a background thread at a fixed resolution
+/// (see [`with_resolution`](Self::with_resolution)). The returned time never decreases.
+///
+/// ## Example
+///
+/// ```
+/// # use std::time::Duration;
+/// # use ttl_queue::{CachedClock, TtlQueue};
+/// let mut queue = TtlQueue::new(Duration::from_secs(1));
+/// queue.set_clock(CachedClock::every(64));
+///
+/// for i in 0..1000 {
+///     queue.push_back(i);
+/// }
+///
+/// assert_eq!(queue.refresh(), 1000);
+/// ```
+pub struct CachedClock {
+    inner: Arc<CachedTime>,
+    /// The number of queries after which the system clock is read again,
+    /// or zero if the time is updated by a background thread.
+    reads_per_update: u32,
+    reads: AtomicU32,
+}
+
+/// The cached time, shared with the updating thread.
+struct CachedTime {
+    origin: Instant,
+    /// The cached time as nanoseconds since `origin`.
+    nanos: AtomicU64,
+}
+
+impl CachedTime {
+    fn new() -> Self {
+        Self {
+            origin: Instant::now(),
+            nanos: AtomicU64::new(0),
+        }
+    }
+
+    fn update(&self) {
+        let nanos = u64::try_from(self.origin.elapsed().as_nanos()).unwrap_or(u64::MAX);
+        self.nanos.fetch_max(nanos, Ordering::Relaxed);
+    }
+
+    fn get(&self) -> Instant {
+        self.origin + Duration::from_nanos(self.nanos.load(Ordering::Relaxed))
+    }
+}
+
+impl CachedClock {
+    /// Creates a clock that reads the system clock once every `reads` queries.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `reads` is zero.
+    pub fn every(reads: u32) -> Self {
+        assert_ne!(reads, 0, "The number of reads must not be zero");
+        Self {
+            inner: Arc::new(CachedTime::new()),
+            reads_per_update: reads,
+            reads: AtomicU32::new(0),
+        }
+    }
+
+    /// Creates a clock that is updated by a background thread with the specified resolution.
+    ///
+    /// Queries never read the system clock themselves. The thread ends once the clock
+    /// is dropped.
+    pub fn with_resolution(resolution: Duration) -> Self {
+        let inner = Arc::new(CachedTime::new());
+        let time = Arc::downgrade(&inner);
+        thread::spawn(move || Self::update_periodically(&time, resolution));
+
+        Self {
+            inner,
+            reads_per_update: 0,
+            reads: AtomicU32::new(0),
+        }
+    }
+
+    fn update_periodically(time: &Weak<CachedTime>, resolution: Duration) {
+        loop {
+            thread::sleep(resolution);
+            match time.upgrade() {
+                Some(time) => time.update(),
+                None => break,
+            }
+        }
+    }
+}
+
+impl Clock for CachedClock {
+    fn now(&self) -> Instant {
+        if self.reads_per_update != 0 {
+            let reads = self.reads.fetch_add(1, Ordering::Relaxed);
+            if reads % self.reads_per_update == 0 {
+                self.inner.update();
+            }
+        }
+
+        self.inner.get()
+    }
+}
+
+impl Debug for CachedClock {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CachedClock")
+            .field("reads_per_update", &self.reads_per_update)
+            .field("now", &self.inner.get())
+            .finish_non_exhaustive()
+    }
+}
+
+/// A [`Clock`] driven by logical ticks instead of wall time, e.g. frames of a game
+/// loop or steps of a deterministic simulation.
+///
+/// The clock only advances when [`tick`](Self::tick) or [`advance`](Self::advance)
+/// is called. Each tick corresponds to one nanosecond past the creation of the clock,
+/// so a TTL of N ticks is expressed as [`TickClock::ttl(N)`](Self::ttl). Clones share
+/// the same tick counter, so one clone can be handed to a queue while another drives it.
+///
+/// ## Example
+///
+/// ```
+/// # use ttl_queue::{TickClock, TtlQueue};
+/// let clock = TickClock::new();
+/// let mut queue = TtlQueue::new(TickClock::ttl(3));
+/// queue.set_clock(clock.clone());
+///
+/// queue.push_back("frame 0");
+/// clock.advance(2);
+/// queue.push_back("frame 2");
+/// clock.tick();
+///
+/// assert_eq!(queue.refresh(), 1);
+/// ```
+#[derive(Debug, Clone)]
+pub struct TickClock {
+    origin: Instant,
+    ticks: Arc<AtomicU64>,
+}
+
+impl TickClock {
+    /// Creates a clock starting at tick zero.
+    pub fn new() -> Self {
+        Self {
+            origin: Instant::now(),
+            ticks: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Gets the time-to-live corresponding to the specified number of ticks.
+    pub const fn ttl(ticks: u64) -> Duration {
+        Duration::from_nanos(ticks)
+    }
+
+    /// Advances the clock by a single tick and returns the new tick.
+    pub fn tick(&self) -> u64 {
+        self.advance(1)
+    }
+
+    /// Advances the clock by the specified number of ticks and returns the new tick.
+    pub fn advance(&self, ticks: u64) -> u64 {
+        self.ticks.fetch_add(ticks, Ordering::Relaxed) + ticks
+    }
+
+    /// Gets the current tick.
+    pub fn ticks(&self) -> u64 {
+        self.ticks.load(Ordering::Relaxed)
+    }
+
+    /// Gets the tick at which an element with the specified timestamp was pushed.
+    pub fn ticks_at(&self, instant: Instant) -> u64 {
+        let elapsed = instant.saturating_duration_since(self.origin);
+        u64::try_from(elapsed.as_nanos()).unwrap_or(u64::MAX)
+    }
+}
+
+impl Default for TickClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for TickClock {
+    fn now(&self) -> Instant {
+        self.origin + Self::ttl(self.ticks())
+    }
+}
+
+/// A [`Clock`] reading a hardware timer or other monotonic tick counter, e.g. the
+/// free-running counter of a timer peripheral exposed by a board support crate.
+///
+/// The counter is read through a user-provided function and its ticks are converted
+/// to time using the counter frequency. Ticks are measured relative to the first read
+/// at construction. Counters narrower than 64 bits are supported via
+/// [`with_width`](Self::with_width); wrap-arounds are detected by comparing each read
+/// with the previous one, so the clock must be read at least once per wrap-around period.
+///
+/// This is a `std`-only counter clock: its state is guarded by a [`std::sync::Mutex`]
+/// and timestamps are still [`Instant`]s, anchored at the time of construction. The
+/// crate does not support `no_std` targets yet.
+///
+/// ## Example
+///
+/// ```
+/// # use std::sync::atomic::{AtomicU64, Ordering};
+/// # use std::time::Duration;
+/// # use ttl_queue::{TimerClock, TtlQueue};
+/// static COUNTER: AtomicU64 = AtomicU64::new(0);
+///
+/// let mut queue = TtlQueue::new(Duration::from_millis(10));
+/// queue.set_clock(TimerClock::new(1_000, || COUNTER.load(Ordering::Relaxed)));
+///
+/// queue.push_back("sensor event");
+/// COUNTER.fetch_add(10, Ordering::Relaxed);
+/// assert_eq!(queue.refresh(), 0);
+/// ```
+pub struct TimerClock<F> {
+    origin: Instant,
+    frequency: u64,
+    /// The bits of the counter, e.g. `0xFFFF` for a 16-bit counter.
+    mask: u64,
+    /// The previous reading of the counter, along with the ticks elapsed until then.
+    state: Mutex<(u64, u64)>,
+    ticks: F,
+}
+
+impl<F: Fn() -> u64> TimerClock<F> {
+    /// Creates a clock from a 64-bit tick counter running at the specified frequency in Hz.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `frequency` is zero.
+    pub fn new(frequency: u64, ticks: F) -> Self {
+        Self::with_width(frequency, 64, ticks)
+    }
+
+    /// Creates a clock from a tick counter of the specified width in bits, which wraps
+    /// around to zero after `2^bits` ticks, running at the specified frequency in Hz.
+    ///
+    /// Bits of the read value above the width are ignored. The clock must be read at
+    /// least once every `2^bits / frequency` seconds, e.g. by refreshing the queue,
+    /// since longer gaps cannot be told apart from shorter ones.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `frequency` is zero or `bits` is not within `1..=64`.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use std::sync::atomic::{AtomicU64, Ordering};
+    /// # use std::time::Duration;
+    /// # use ttl_queue::{Clock, TimerClock};
+    /// static COUNTER: AtomicU64 = AtomicU64::new(0xFFF0);
+    ///
+    /// let clock = TimerClock::with_width(1_000, 16, || COUNTER.load(Ordering::Relaxed));
+    /// let start = clock.now();
+    ///
+    /// // The 16-bit counter wraps around from 0xFFFF to 0x0000.
+    /// COUNTER.store(0x0010, Ordering::Relaxed);
+    /// assert_eq!(clock.now() - start, Duration::from_millis(32));
+    /// ```
+    pub fn with_width(frequency: u64, bits: u32, ticks: F) -> Self {
+        assert_ne!(frequency, 0, "The timer frequency must not be zero");
+        assert!(
+            (1..=64).contains(&bits),
+            "The counter width must be between 1 and 64 bits"
+        );
+        let mask = u64::MAX >> (64 - bits);
+        Self {
+            origin: Instant::now(),
+            frequency,
+            mask,
+            state: Mutex::new((ticks() & mask, 0)),
+            ticks,
+        }
+    }
+
+    /// Gets the frequency of the tick counter in Hz.
+    pub fn frequency(&self) -> u64 {
+        self.frequency
+    }
+
+    /// Gets the width of the tick counter in bits.
+    pub fn bits(&self) -> u32 {
+        self.mask.count_ones()
+    }
+
+    fn elapsed(&self) -> Duration {
+        let reading = (self.ticks)() & self.mask;
+        let ticks = {
+            let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+            let (previous, elapsed) = &mut *state;
+            *elapsed = elapsed.saturating_add(reading.wrapping_sub(*previous) & self.mask);
+            *previous = reading;
+            *elapsed
+        };
+
+        let secs = ticks / self.frequency;
+        let nanos = (ticks % self.frequency) as u128 * 1_000_000_000 / self.frequency as u128;
+        Duration::new(secs, nanos as u32)
+    }
+}
+
+impl<F: Fn() -> u64 + Send + Sync> Clock for TimerClock<F> {
+    fn now(&self) -> Instant {
+        self.origin + self.elapsed()
+    }
+}
+
+impl<F> Debug for TimerClock<F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TimerClock")
+            .field("frequency", &self.frequency)
+            .field("bits", &self.mask.count_ones())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(not(feature = "mock_instant"))]
+    fn cached_clock_updates_every_n_reads() {
+        let clock = CachedClock::every(3);
+        let first = clock.now();
+        thread::sleep(Duration::from_millis(2));
+        assert_eq!(clock.now(), first);
+        assert_eq!(clock.now(), first);
+        assert!(clock.now() > first);
+    }
+
+    #[test]
+    #[cfg(not(feature = "mock_instant"))]
+    fn cached_clock_updates_in_background() {
+        let clock = CachedClock::with_resolution(Duration::from_millis(1));
+        let first = clock.now();
+        thread::sleep(Duration::from_millis(20));
+        assert!(clock.now() > first);
+    }
+
+    #[test]
+    fn tick_clock_only_advances_on_ticks() {
+        let clock = TickClock::new();
+        let first = clock.now();
+        thread::sleep(Duration::from_millis(2));
+        assert_eq!(clock.now(), first);
+
+        assert_eq!(clock.clone().advance(5), 5);
+        assert_eq!(clock.ticks_at(clock.now()), 5);
+        assert_eq!(clock.now() - first, TickClock::ttl(5));
+    }
+
+    #[test]
+    fn timer_clock_converts_ticks() {
+        let ticks = Arc::new(AtomicU64::new(u64::MAX - 5));
+        let counter = ticks.clone();
+        let clock = TimerClock::new(4, move || counter.load(Ordering::Relaxed));
+        let first = clock.now();
+
+        ticks.fetch_add(11, Ordering::Relaxed);
+        assert_eq!(clock.now() - first, Duration::from_millis(2750));
+    }
+
+    #[test]
+    fn narrow_timer_clock_accumulates_wrap_arounds() {
+        let ticks = Arc::new(AtomicU64::new(0xF0));
+        let counter = ticks.clone();
+        let clock = TimerClock::with_width(1_000, 8, move || counter.load(Ordering::Relaxed));
+        assert_eq!(clock.bits(), 8);
+        let first = clock.now();
+
+        for _ in 0..10 {
+            ticks.fetch_add(0xC8, Ordering::Relaxed);
+            clock.now();
+        }
+        assert_eq!(clock.now() - first, Duration::from_secs(2));
+    }
+}
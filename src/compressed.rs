@@ -0,0 +1,317 @@
+use crate::{Instant, TtlQueue};
+use std::fmt::{Debug, Formatter};
+use std::time::Duration;
+
+/// The default number of payloads compressed together in one block.
+const DEFAULT_BLOCK_LEN: usize = 64;
+
+/// A TTL queue of byte payloads that are stored LZ4-compressed in blocks and
+/// decompressed on iteration, e.g. for windows of large log lines or JSON documents
+/// where memory rather than CPU is the constraint.
+///
+/// The newest payloads are kept uncompressed until a block is full. Blocks are dropped
+/// as a whole once all of their payloads expired; partially expired blocks are retained,
+/// but their expired payloads are skipped.
+///
+/// ## Example
+///
+/// ```
+/// # use std::time::Duration;
+/// # use ttl_queue::CompressedTtlQueue;
+/// let mut queue = CompressedTtlQueue::with_block_len(Duration::from_secs(60), 16);
+/// for i in 0..100 {
+///     queue.push_back(format!("GET /api/items/{i} HTTP/1.1 200 OK"));
+/// }
+///
+/// assert_eq!(queue.refresh(), 100);
+/// assert!(queue.stored_bytes() < queue.payload_bytes());
+///
+/// let (_instant, first) = queue.iter().next().unwrap();
+/// assert_eq!(first, b"GET /api/items/0 HTTP/1.1 200 OK");
+/// ```
+pub struct CompressedTtlQueue {
+    /// The full blocks, each stamped with the timestamp of its newest payload.
+    blocks: TtlQueue<CompressedBlock>,
+    /// The number of leading payloads of the front block that were removed.
+    skip: usize,
+    /// The payloads not compressed yet.
+    tail: Vec<(Instant, Vec<u8>)>,
+    block_len: usize,
+    len: usize,
+    payload_bytes: usize,
+}
+
+/// A block of compressed payloads along with their timestamps.
+struct CompressedBlock {
+    instants: Vec<Instant>,
+    /// The end offset of each payload within the decompressed data.
+    ends: Vec<usize>,
+    data: Vec<u8>,
+}
+
+impl CompressedBlock {
+    fn compress(entries: Vec<(Instant, Vec<u8>)>) -> Self {
+        let mut instants = Vec::with_capacity(entries.len());
+        let mut ends = Vec::with_capacity(entries.len());
+        let mut raw = Vec::new();
+        for (instant, payload) in entries {
+            raw.extend_from_slice(&payload);
+            instants.push(instant);
+            ends.push(raw.len());
+        }
+
+        Self {
+            instants,
+            ends,
+            data: lz4_flex::compress_prepend_size(&raw),
+        }
+    }
+
+    fn decompress(&self) -> Vec<(Instant, Vec<u8>)> {
+        let raw = lz4_flex::decompress_size_prepended(&self.data)
+            .expect("blocks are compressed by the queue");
+
+        let mut start = 0;
+        self.instants
+            .iter()
+            .zip(&self.ends)
+            .map(|(instant, &end)| {
+                let payload = raw[start..end].to_vec();
+                start = end;
+                (*instant, payload)
+            })
+            .collect()
+    }
+
+    fn len(&self) -> usize {
+        self.instants.len()
+    }
+
+    /// Gets the number of bytes of the payload at the specified index.
+    fn payload_len(&self, index: usize) -> usize {
+        let start = index
+            .checked_sub(1)
+            .map_or(0, |previous| self.ends[previous]);
+        self.ends[index] - start
+    }
+}
+
+impl CompressedTtlQueue {
+    /// Creates an empty [`CompressedTtlQueue`] compressing 64 payloads per block.
+    pub fn new(ttl: Duration) -> Self {
+        Self::with_block_len(ttl, DEFAULT_BLOCK_LEN)
+    }
+
+    /// Creates an empty [`CompressedTtlQueue`] compressing `block_len` payloads per block.
+    ///
+    /// Larger blocks usually compress better, but retain expired payloads longer and
+    /// make [`pop_front`](Self::pop_front) decompress more data.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `block_len` is zero.
+    pub fn with_block_len(ttl: Duration, block_len: usize) -> Self {
+        assert!(block_len > 0, "The block length must not be zero");
+        Self {
+            blocks: TtlQueue::new(ttl),
+            skip: 0,
+            tail: Vec::with_capacity(block_len),
+            block_len,
+            len: 0,
+            payload_bytes: 0,
+        }
+    }
+
+    /// Gets the time-to-live of the payloads.
+    pub fn ttl(&self) -> Duration {
+        self.blocks.ttl()
+    }
+
+    /// Gets the number of payloads compressed per block.
+    pub fn block_len(&self) -> usize {
+        self.block_len
+    }
+
+    /// Gets the number of payloads, including potentially expired payloads.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the queue is definitely empty or `false` if it is possibly empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Gets the total size of the payloads in bytes, i.e. without compression.
+    pub fn payload_bytes(&self) -> usize {
+        self.payload_bytes
+    }
+
+    /// Gets the number of bytes used to store the payloads, i.e. the compressed size
+    /// of the blocks plus the size of the payloads not compressed yet.
+    pub fn stored_bytes(&self) -> usize {
+        let compressed: usize = self.blocks.iter().map(|(_, block)| block.data.len()).sum();
+        let uncompressed: usize = self.tail.iter().map(|(_, payload)| payload.len()).sum();
+        compressed + uncompressed
+    }
+
+    /// Pushes a payload to the end of the queue, compressing the newest payloads once
+    /// they fill a block.
+    pub fn push_back(&mut self, payload: impl Into<Vec<u8>>) {
+        let payload = payload.into();
+        self.len += 1;
+        self.payload_bytes += payload.len();
+        self.tail.push((self.blocks.now(), payload));
+
+        if self.tail.len() >= self.block_len {
+            let entries = std::mem::replace(&mut self.tail, Vec::with_capacity(self.block_len));
+            let block = CompressedBlock::compress(entries);
+            let newest = *block.instants.last().expect("blocks are never empty");
+            self.blocks.push_back_entry(newest, block);
+        }
+    }
+
+    /// Removes the payload from the front of the queue and returns it, as well as the
+    /// time instant at which it was added.
+    ///
+    /// This decompresses the front block.
+    pub fn pop_front(&mut self) -> Option<(Instant, Vec<u8>)> {
+        let Some((_, block)) = self.blocks.front_entry() else {
+            if self.tail.is_empty() {
+                return None;
+            }
+            let entry = self.tail.remove(0);
+            self.len -= 1;
+            self.payload_bytes -= entry.1.len();
+            return Some(entry);
+        };
+
+        let entry = block.decompress().swap_remove(self.skip);
+        self.remove_front(1);
+        Some(entry)
+    }
+
+    /// Refreshes the queue and returns the number of currently contained payloads.
+    pub fn refresh(&mut self) -> usize {
+        let now = self.blocks.now();
+        let ttl = self.blocks.ttl();
+        let expired = |instant: &Instant| now.saturating_duration_since(*instant) >= ttl;
+
+        while let Some((newest, block)) = self.blocks.front_entry() {
+            let count = if expired(newest) {
+                block.len() - self.skip
+            } else {
+                block.instants[self.skip..]
+                    .iter()
+                    .take_while(|instant| expired(instant))
+                    .count()
+            };
+            if count == 0 {
+                return self.len;
+            }
+            self.remove_front(count);
+        }
+
+        let count = self
+            .tail
+            .iter()
+            .take_while(|(instant, _)| expired(instant))
+            .count();
+        for (_, payload) in self.tail.drain(..count) {
+            self.len -= 1;
+            self.payload_bytes -= payload.len();
+        }
+        self.len
+    }
+
+    /// Removes `count` payloads from the front block, dropping the block if none are left.
+    fn remove_front(&mut self, count: usize) {
+        let Some((_, block)) = self.blocks.front_entry() else {
+            return;
+        };
+        let removed_bytes: usize = (self.skip..self.skip + count)
+            .map(|index| block.payload_len(index))
+            .sum();
+        let exhausted = self.skip + count >= block.len();
+
+        self.len -= count;
+        self.payload_bytes -= removed_bytes;
+        self.skip += count;
+        if exhausted {
+            self.blocks.pop_front_entry();
+            self.skip = 0;
+        }
+    }
+
+    /// Returns an iterator over the payloads, oldest first, as well as the time instants
+    /// at which they were added.
+    ///
+    /// Blocks are decompressed one at a time as the iterator advances.
+    pub fn iter(&self) -> impl Iterator<Item = (Instant, Vec<u8>)> + '_ {
+        self.blocks
+            .iter()
+            .enumerate()
+            .flat_map(move |(index, (_, block))| {
+                let skip = if index == 0 { self.skip } else { 0 };
+                block.decompress().into_iter().skip(skip)
+            })
+            .chain(self.tail.iter().cloned())
+    }
+}
+
+impl Debug for CompressedTtlQueue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CompressedTtlQueue")
+            .field("ttl", &self.ttl())
+            .field("block_len", &self.block_len)
+            .field("len", &self.len)
+            .field("blocks", &self.blocks.len())
+            .field("payload_bytes", &self.payload_bytes)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TickClock;
+
+    #[test]
+    fn partially_expired_blocks_are_skipped() {
+        let clock = TickClock::new();
+        let mut queue = CompressedTtlQueue::with_block_len(TickClock::ttl(10), 4);
+        queue.blocks.set_clock(clock.clone());
+
+        for i in 0..10u8 {
+            queue.push_back(vec![i; 3]);
+            clock.tick();
+        }
+        assert_eq!(queue.blocks.len(), 2);
+        assert_eq!(queue.payload_bytes(), 30);
+
+        clock.advance(1);
+        assert_eq!(queue.refresh(), 8);
+        let first: Vec<u8> = queue.iter().map(|(_, payload)| payload[0]).collect();
+        assert_eq!(first, [2, 3, 4, 5, 6, 7, 8, 9]);
+
+        assert_eq!(
+            queue.pop_front().map(|(_, payload)| payload),
+            Some(vec![2; 3])
+        );
+        assert_eq!(
+            queue.pop_front().map(|(_, payload)| payload),
+            Some(vec![3; 3])
+        );
+        assert_eq!(queue.blocks.len(), 1);
+
+        clock.advance(7);
+        assert_eq!(queue.refresh(), 1);
+        assert_eq!(queue.blocks.len(), 0);
+        assert_eq!(queue.payload_bytes(), 3);
+        assert_eq!(
+            queue.pop_front().map(|(_, payload)| payload),
+            Some(vec![9; 3])
+        );
+        assert!(queue.is_empty());
+    }
+}
@@ -0,0 +1,125 @@
+use crate::{Instant, TtlQueue};
+use std::iter::FusedIterator;
+use std::time::Duration;
+
+/// An iterator over pairs of entries from two queues whose timestamps are within a
+/// tolerance of each other.
+///
+/// See [`TtlQueue::correlate`].
+#[derive(Debug)]
+pub struct Correlate<'a, T, U> {
+    left: &'a TtlQueue<T>,
+    right: &'a TtlQueue<U>,
+    tolerance: Duration,
+    /// The position of the current entry in the left queue.
+    left_index: usize,
+    /// The first position in the right queue that may still match the current left entry.
+    window_start: usize,
+    /// The position of the next candidate in the right queue.
+    right_index: usize,
+}
+
+impl<T> TtlQueue<T> {
+    /// Returns an iterator over all pairs of entries from this and another queue whose
+    /// timestamps differ by at most `tolerance`.
+    ///
+    /// Pairs are yielded in the order of this queue's entries, then in the order of
+    /// the other queue's entries. Since both queues are ordered by time, this operation
+    /// is O(N + M + P) for P matching pairs.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use ttl_queue::TtlQueue;
+    /// let mut requests = TtlQueue::new(Duration::from_secs(60));
+    /// let mut responses = TtlQueue::new(Duration::from_secs(60));
+    /// requests.push_back("request");
+    /// responses.push_back("response");
+    ///
+    /// let pairs: Vec<_> = requests
+    ///     .correlate(&responses, Duration::from_secs(1))
+    ///     .map(|(request, response)| (request.1, response.1))
+    ///     .collect();
+    /// assert_eq!(pairs, [("request", "response")]);
+    /// ```
+    pub fn correlate<'a, U>(
+        &'a self,
+        other: &'a TtlQueue<U>,
+        tolerance: Duration,
+    ) -> Correlate<'a, T, U> {
+        Correlate {
+            left: self,
+            right: other,
+            tolerance,
+            left_index: 0,
+            window_start: 0,
+            right_index: 0,
+        }
+    }
+}
+
+impl<'a, T, U> Iterator for Correlate<'a, T, U> {
+    type Item = (&'a (Instant, T), &'a (Instant, U));
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let left = self.left.get(self.left_index)?;
+
+            // Skip right entries that are too old for this and all subsequent left entries.
+            while let Some((instant, _)) = self.right.get(self.window_start) {
+                if *instant >= left.0 || left.0.duration_since(*instant) <= self.tolerance {
+                    break;
+                }
+                self.window_start += 1;
+            }
+            self.right_index = self.right_index.max(self.window_start);
+
+            match self.right.get(self.right_index) {
+                Some(right) if right.0.saturating_duration_since(left.0) <= self.tolerance => {
+                    self.right_index += 1;
+                    return Some((left, right));
+                }
+                _ => {
+                    self.left_index += 1;
+                    self.right_index = self.window_start;
+                }
+            }
+        }
+    }
+}
+
+impl<T, U> FusedIterator for Correlate<'_, T, U> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn correlate_matches_within_tolerance() {
+        let now = Instant::now();
+        let at = |secs: u64| now + Duration::from_secs(secs);
+
+        let mut left = TtlQueue::new(Duration::MAX);
+        for (secs, value) in [(0, 'a'), (10, 'b'), (11, 'c'), (30, 'd')] {
+            left.push_back_entry(at(secs), value);
+        }
+
+        let mut right = TtlQueue::new(Duration::MAX);
+        for (secs, value) in [(1, 1), (9, 2), (12, 3), (20, 4)] {
+            right.push_back_entry(at(secs), value);
+        }
+
+        let pairs: Vec<_> = left
+            .correlate(&right, Duration::from_secs(1))
+            .map(|(l, r)| (l.1, r.1))
+            .collect();
+        assert_eq!(pairs, [('a', 1), ('b', 2), ('c', 3)]);
+
+        let pairs: Vec<_> = left
+            .correlate(&right, Duration::from_secs(2))
+            .map(|(l, r)| (l.1, r.1))
+            .collect();
+        assert_eq!(pairs, [('a', 1), ('b', 2), ('b', 3), ('c', 2), ('c', 3)]);
+    }
+}
@@ -0,0 +1,62 @@
+use crate::SyncTtlQueue;
+use crossbeam_channel::Receiver;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// Drains a [`crossbeam_channel::Receiver`] into a [`SyncTtlQueue`] on a helper thread
+/// and returns the queue.
+///
+/// Each element is stamped when the helper thread receives it. The thread ends once all
+/// senders are disconnected, or after receiving the next element once the returned
+/// queue was dropped.
+///
+/// ## Example
+///
+/// ```
+/// # use std::time::Duration;
+/// let (sender, receiver) = crossbeam_channel::unbounded();
+/// let queue = ttl_queue::bridge_receiver(receiver, Duration::from_secs(60));
+///
+/// sender.send("event").unwrap();
+/// let received = queue.pop_front_timeout(Duration::from_secs(5));
+/// assert_eq!(received.map(|(_, value)| value), Some("event"));
+/// ```
+pub fn bridge_receiver<T: Send + 'static>(
+    receiver: Receiver<T>,
+    ttl: Duration,
+) -> Arc<SyncTtlQueue<T>> {
+    let queue = Arc::new(SyncTtlQueue::new(ttl));
+    let target = Arc::downgrade(&queue);
+    thread::spawn(move || {
+        for element in receiver {
+            let Some(queue) = target.upgrade() else {
+                break;
+            };
+            queue.push_back(element);
+        }
+    });
+    queue
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bridged_elements_arrive_in_order() {
+        let (sender, receiver) = crossbeam_channel::bounded(4);
+        let queue = bridge_receiver(receiver, Duration::MAX);
+        for i in 0..10 {
+            sender.send(i).unwrap();
+        }
+        drop(sender);
+
+        let received: Vec<_> =
+            std::iter::from_fn(|| queue.pop_front_timeout(Duration::from_secs(5)))
+                .take(10)
+                .map(|(_, value)| value)
+                .collect();
+        assert_eq!(received, (0..10).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,266 @@
+use crate::{Instant, TtlQueue};
+
+/// A cursor over a [`TtlQueue`] that can mutate, remove and insert elements in place.
+///
+/// Like the cursors of [`LinkedList`](std::collections::LinkedList), the cursor points
+/// either at an element or at a "ghost" position past the back of the queue, from which
+/// it wraps around to the front. Timestamps cannot be modified and insertions are only
+/// accepted if they retain the ordering of the timestamps.
+///
+/// See [`TtlQueue::cursor_front_mut`].
+#[derive(Debug)]
+pub struct CursorMut<'a, T> {
+    queue: &'a mut TtlQueue<T>,
+    index: usize,
+}
+
+impl<T> TtlQueue<T> {
+    /// Returns a cursor pointing at the front (oldest) element of the queue, or at
+    /// the ghost position if the queue is empty.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use ttl_queue::TtlQueue;
+    /// let mut queue = TtlQueue::new(Duration::from_secs(60));
+    /// for i in 0..5 {
+    ///     queue.push_back(i);
+    /// }
+    ///
+    /// let mut cursor = queue.cursor_front_mut();
+    /// while let Some((_instant, value)) = cursor.current() {
+    ///     if *value % 2 == 0 {
+    ///         *value *= 10;
+    ///         cursor.move_next();
+    ///     } else {
+    ///         cursor.remove_current();
+    ///     }
+    /// }
+    ///
+    /// let values: Vec<_> = queue.iter().map(|(_, value)| *value).collect();
+    /// assert_eq!(values, [0, 20, 40]);
+    /// ```
+    pub fn cursor_front_mut(&mut self) -> CursorMut<'_, T> {
+        CursorMut {
+            queue: self,
+            index: 0,
+        }
+    }
+
+    /// Returns a cursor pointing at the back (newest) element of the queue, or at
+    /// the ghost position if the queue is empty.
+    pub fn cursor_back_mut(&mut self) -> CursorMut<'_, T> {
+        let index = self.len().saturating_sub(1);
+        CursorMut { queue: self, index }
+    }
+}
+
+impl<'a, T> CursorMut<'a, T> {
+    /// Gets the position of the cursor, counting from the front of the queue,
+    /// or `None` if the cursor is at the ghost position.
+    pub fn index(&self) -> Option<usize> {
+        (self.index < self.queue.len()).then_some(self.index)
+    }
+
+    /// Moves the cursor to the next (newer) element. If the cursor is at the back
+    /// of the queue, it moves to the ghost position; if it is at the ghost position,
+    /// it moves to the front of the queue.
+    pub fn move_next(&mut self) {
+        self.index = if self.index >= self.queue.len() {
+            0
+        } else {
+            self.index + 1
+        };
+    }
+
+    /// Moves the cursor to the previous (older) element. If the cursor is at the
+    /// front of the queue, it moves to the ghost position; if it is at the ghost
+    /// position, it moves to the back of the queue.
+    pub fn move_prev(&mut self) {
+        let len = self.queue.len();
+        self.index = match self.index {
+            0 => len,
+            index if index >= len => len.saturating_sub(1),
+            index => index - 1,
+        };
+    }
+
+    /// Gets the timestamp and a mutable reference to the element the cursor points at.
+    pub fn current(&mut self) -> Option<(Instant, &mut T)> {
+        // The element may be modified in place, changing its cost.
+        self.queue.invalidate_cost();
+        self.queue
+            .get_mut(self.index)
+            .map(|(instant, element)| (*instant, element))
+    }
+
+    /// Gets the entry after the one the cursor points at.
+    pub fn peek_next(&self) -> Option<&(Instant, T)> {
+        match self.index() {
+            Some(index) => self.queue.get(index + 1),
+            None => self.queue.get(0),
+        }
+    }
+
+    /// Gets the entry before the one the cursor points at.
+    pub fn peek_prev(&self) -> Option<&(Instant, T)> {
+        match self.index() {
+            Some(0) => None,
+            Some(index) => self.queue.get(index - 1),
+            None => self.queue.back_entry(),
+        }
+    }
+
+    /// Removes the element the cursor points at and returns it, as well as the
+    /// time instant at which it was added. The cursor then points at the next element.
+    pub fn remove_current(&mut self) -> Option<(Instant, T)> {
+        self.index()?;
+        let entry = self.queue.remove(self.index);
+        self.queue.update_thresholds(self.queue.len());
+        entry
+    }
+
+    /// Inserts an element before the one the cursor points at, or at the back of the
+    /// queue if the cursor is at the ghost position. The cursor keeps pointing at the
+    /// same element.
+    ///
+    /// Returns the entry as an error if its timestamp would violate the ordering of
+    /// the queue.
+    pub fn insert_before(&mut self, instant: Instant, element: T) -> Result<(), (Instant, T)> {
+        let index = self.index.min(self.queue.len());
+        if !self.fits_between(index, instant) {
+            return Err((instant, element));
+        }
+
+        self.queue.insert(index, (instant, element));
+        self.index = index + 1;
+        self.queue.update_thresholds(self.queue.len());
+        Ok(())
+    }
+
+    /// Inserts an element after the one the cursor points at, or at the front of the
+    /// queue if the cursor is at the ghost position. The cursor keeps pointing at the
+    /// same element.
+    ///
+    /// Returns the entry as an error if its timestamp would violate the ordering of
+    /// the queue.
+    pub fn insert_after(&mut self, instant: Instant, element: T) -> Result<(), (Instant, T)> {
+        let (index, at_ghost) = match self.index() {
+            Some(index) => (index + 1, false),
+            None => (0, true),
+        };
+
+        if !self.fits_between(index, instant) {
+            return Err((instant, element));
+        }
+
+        self.queue.insert(index, (instant, element));
+        if at_ghost {
+            self.index = self.queue.len();
+        }
+        self.queue.update_thresholds(self.queue.len());
+        Ok(())
+    }
+
+    /// Determines whether an entry with the specified timestamp can be inserted at
+    /// the specified position without violating the ordering of the queue.
+    fn fits_between(&self, index: usize, instant: Instant) -> bool {
+        let after_prev = index == 0
+            || self
+                .queue
+                .get(index - 1)
+                .map_or(true, |(prev, _)| *prev <= instant);
+        let before_next = self
+            .queue
+            .get(index)
+            .map_or(true, |(next, _)| instant <= *next);
+        after_prev && before_next
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn values(queue: &TtlQueue<u32>) -> Vec<u32> {
+        queue.iter().map(|(_, value)| *value).collect()
+    }
+
+    #[test]
+    fn cursor_edits_update_cost() {
+        let mut queue = TtlQueue::with_max_bytes(Duration::MAX, 100, String::len);
+        queue.push_back(String::from("a"));
+
+        let mut cursor = queue.cursor_front_mut();
+        if let Some((_instant, value)) = cursor.current() {
+            value.push_str("bcd");
+        }
+
+        assert_eq!(queue.total_bytes(), 4);
+        queue.push_back(String::from("e"));
+        assert_eq!(queue.total_bytes(), 5);
+    }
+
+    #[test]
+    fn cursor_walks_and_wraps() {
+        let mut queue = TtlQueue::new(Duration::MAX);
+        for i in 0..3 {
+            queue.push_back(i);
+        }
+
+        let mut cursor = queue.cursor_back_mut();
+        assert_eq!(cursor.index(), Some(2));
+        cursor.move_next();
+        assert_eq!(cursor.index(), None);
+        assert_eq!(cursor.peek_next().map(|(_, value)| *value), Some(0));
+        assert_eq!(cursor.peek_prev().map(|(_, value)| *value), Some(2));
+        cursor.move_next();
+        assert_eq!(cursor.index(), Some(0));
+        cursor.move_prev();
+        assert_eq!(cursor.index(), None);
+        cursor.move_prev();
+        assert_eq!(cursor.index(), Some(2));
+    }
+
+    #[test]
+    fn cursor_inserts_in_order() {
+        let mut queue = TtlQueue::new(Duration::MAX);
+        let now = Instant::now();
+        for i in 0..3u32 {
+            queue.push_back_entry(now + Duration::from_secs(i as u64 * 10), i * 10);
+        }
+
+        let mut cursor = queue.cursor_front_mut();
+        cursor.move_next();
+        assert_eq!(cursor.current().map(|(_, value)| *value), Some(10));
+
+        // Timestamps between the neighbours are accepted ...
+        assert!(cursor
+            .insert_before(now + Duration::from_secs(5), 5)
+            .is_ok());
+        assert!(cursor
+            .insert_after(now + Duration::from_secs(15), 15)
+            .is_ok());
+        assert_eq!(cursor.current().map(|(_, value)| *value), Some(10));
+
+        // ... others are rejected.
+        assert!(cursor
+            .insert_before(now + Duration::from_secs(30), 30)
+            .is_err());
+
+        // At the ghost position, insertions happen at the front and back.
+        for _ in 0..3 {
+            cursor.move_prev();
+        }
+        assert_eq!(cursor.index(), None);
+        assert!(cursor.insert_after(now, 1).is_ok());
+        assert!(cursor
+            .insert_before(now + Duration::from_secs(20), 21)
+            .is_ok());
+        assert_eq!(cursor.index(), None);
+
+        assert_eq!(values(&queue), [1, 0, 5, 10, 15, 20, 21]);
+    }
+}
@@ -0,0 +1,95 @@
+use crate::{Instant, TtlQueue};
+use chrono::{DateTime, TimeDelta, Utc};
+
+impl<T> TtlQueue<T> {
+    /// Gets the wall-clock time at which the queue was created, or at which its
+    /// [clock](Self::set_clock) was last set.
+    ///
+    /// This is the anchor for converting timestamps with [`to_datetime`](Self::to_datetime).
+    pub fn created_at(&self) -> DateTime<Utc> {
+        self.anchor.1
+    }
+
+    /// Converts a timestamp of the queue into a wall-clock date.
+    ///
+    /// Timestamps are converted relative to the [creation](Self::created_at) of the queue,
+    /// so later adjustments of the system time are not reflected. Dates outside of the
+    /// range supported by [`DateTime`] saturate.
+    pub fn to_datetime(&self, instant: Instant) -> DateTime<Utc> {
+        let (anchor, created_at) = self.anchor;
+        let (delta, later) = if instant >= anchor {
+            (instant.saturating_duration_since(anchor), true)
+        } else {
+            (anchor.saturating_duration_since(instant), false)
+        };
+
+        let delta = TimeDelta::from_std(delta).unwrap_or(TimeDelta::MAX);
+        let date = if later {
+            created_at.checked_add_signed(delta)
+        } else {
+            created_at.checked_sub_signed(delta)
+        };
+
+        date.unwrap_or(if later {
+            DateTime::<Utc>::MAX_UTC
+        } else {
+            DateTime::<Utc>::MIN_UTC
+        })
+    }
+
+    /// Converts a wall-clock date into a timestamp of the queue, e.g. for use with
+    /// [`binary_search_timestamp`](Self::binary_search_timestamp).
+    ///
+    /// Returns `None` if the date cannot be represented as an [`Instant`].
+    pub fn from_datetime(&self, date: DateTime<Utc>) -> Option<Instant> {
+        let (anchor, created_at) = self.anchor;
+        let delta = date.signed_duration_since(created_at);
+        match delta.to_std() {
+            Ok(delta) => anchor.checked_add(delta),
+            Err(_) => anchor.checked_sub(delta.abs().to_std().ok()?),
+        }
+    }
+
+    /// Returns an iterator over the elements with wall-clock timestamps, oldest first.
+    ///
+    /// See [`to_datetime`](Self::to_datetime) for how timestamps are converted.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use ttl_queue::TtlQueue;
+    /// let mut queue = TtlQueue::new(Duration::from_secs(60));
+    /// queue.push_back("login");
+    ///
+    /// let (date, event) = queue.iter_datetime().next().unwrap();
+    /// assert_eq!(*event, "login");
+    /// assert!(date >= queue.created_at());
+    /// ```
+    pub fn iter_datetime(&self) -> impl Iterator<Item = (DateTime<Utc>, &T)> {
+        self.iter()
+            .map(|(instant, element)| (self.to_datetime(*instant), element))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn datetimes_round_trip() {
+        let queue = TtlQueue::<()>::new(Duration::MAX);
+        let (anchor, created_at) = queue.anchor;
+
+        let later = anchor + Duration::from_secs(90);
+        let date = queue.to_datetime(later);
+        assert_eq!(date - created_at, TimeDelta::seconds(90));
+        assert_eq!(queue.from_datetime(date), Some(later));
+
+        let earlier = created_at - TimeDelta::milliseconds(250);
+        let instant = queue.from_datetime(earlier).unwrap();
+        assert_eq!(anchor - instant, Duration::from_millis(250));
+        assert_eq!(queue.to_datetime(instant), earlier);
+    }
+}
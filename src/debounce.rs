@@ -0,0 +1,150 @@
+use crate::{Instant, TtlQueue};
+use std::time::Duration;
+
+/// Yields the most recently submitted item once no further items were submitted
+/// for a given quiet period.
+///
+/// Every [`submit`](Self::submit) replaces the pending item and restarts the quiet
+/// period, so a burst of submissions results in a single item, e.g. when reacting
+/// to keystrokes or file-system events.
+///
+/// ## Example
+///
+/// ```
+/// # use std::time::Duration;
+/// # use ttl_queue::{Debouncer, Instant};
+/// let mut debouncer = Debouncer::new(Duration::from_millis(100));
+/// let now = Instant::now();
+/// debouncer.submit_at(now, "h");
+/// debouncer.submit_at(now + Duration::from_millis(50), "he");
+///
+/// assert_eq!(debouncer.poll_at(now + Duration::from_millis(120)), None);
+/// assert_eq!(debouncer.poll_at(now + Duration::from_millis(150)), Some("he"));
+/// assert!(!debouncer.is_pending());
+/// ```
+#[derive(Debug)]
+pub struct Debouncer<T> {
+    /// Holds the pending item, stamped with the time of its submission.
+    queue: TtlQueue<T>,
+    /// The number of items replaced by later submissions since the last yield.
+    suppressed: usize,
+}
+
+impl<T> Debouncer<T> {
+    /// Creates a debouncer with the specified quiet period.
+    pub fn new(quiet_period: Duration) -> Self {
+        Self {
+            queue: TtlQueue::with_capacity(quiet_period, 1),
+            suppressed: 0,
+        }
+    }
+
+    /// Gets the quiet period after which the pending item is yielded.
+    pub fn quiet_period(&self) -> Duration {
+        self.queue.ttl()
+    }
+
+    /// Submits an item, replacing any pending item and restarting the quiet period.
+    pub fn submit(&mut self, item: T) {
+        self.submit_at(Instant::now(), item)
+    }
+
+    /// Submits an item at the specified instant, replacing any pending item and
+    /// restarting the quiet period.
+    pub fn submit_at(&mut self, instant: Instant, item: T) {
+        if self.queue.pop_front_entry().is_some() {
+            self.suppressed += 1;
+        }
+        self.queue.push_back_entry(instant, item);
+    }
+
+    /// Returns `true` if an item is waiting for the quiet period to pass.
+    pub fn is_pending(&self) -> bool {
+        !self.queue.is_empty()
+    }
+
+    /// Gets the number of items that were replaced by later submissions since the
+    /// last item was yielded or cancelled.
+    pub fn suppressed(&self) -> usize {
+        self.suppressed
+    }
+
+    /// Gets the remaining time until the pending item is yielded, if any.
+    pub fn time_until_ready(&self) -> Option<Duration> {
+        self.queue.time_until_empty()
+    }
+
+    /// Takes the pending item if the quiet period has passed.
+    pub fn poll(&mut self) -> Option<T> {
+        self.poll_at(Instant::now())
+    }
+
+    /// Takes the pending item if the quiet period has passed at the specified instant.
+    pub fn poll_at(&mut self, now: Instant) -> Option<T> {
+        let (instant, _) = self.queue.front_entry()?;
+        if now.saturating_duration_since(*instant) < self.queue.ttl() {
+            return None;
+        }
+        self.cancel()
+    }
+
+    /// Removes the pending item regardless of the quiet period and returns it.
+    pub fn cancel(&mut self) -> Option<T> {
+        self.suppressed = 0;
+        self.queue.pop_front_entry().map(|(_, item)| item)
+    }
+
+    /// Waits until the quiet period of the pending item has passed and returns it.
+    ///
+    /// If no item is pending, the future never completes. It is cancellation safe,
+    /// so it can be used in a `tokio::select!` loop alongside the source of items.
+    #[cfg(feature = "tokio")]
+    pub async fn debounced(&mut self) -> T {
+        loop {
+            let Some((instant, _)) = self.queue.front_entry() else {
+                return std::future::pending().await;
+            };
+
+            let deadline = *instant + self.queue.ttl();
+            tokio::time::sleep_until(deadline).await;
+            if let Some(item) = self.poll() {
+                return item;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn submissions_restart_the_quiet_period() {
+        let mut debouncer = Debouncer::new(Duration::from_secs(1));
+        let now = Instant::now();
+        for i in 0..5u64 {
+            debouncer.submit_at(now + Duration::from_millis(i * 500), i);
+        }
+
+        assert_eq!(debouncer.suppressed(), 4);
+        assert_eq!(debouncer.poll_at(now + Duration::from_millis(2500)), None);
+        assert_eq!(
+            debouncer.poll_at(now + Duration::from_millis(3000)),
+            Some(4)
+        );
+        assert_eq!(debouncer.suppressed(), 0);
+        assert_eq!(debouncer.poll_at(now + Duration::from_secs(10)), None);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn debounced_waits_for_the_quiet_period() {
+        let mut debouncer = Debouncer::new(Duration::from_millis(20));
+        let start = Instant::now();
+        debouncer.submit("a");
+        debouncer.submit("b");
+
+        assert_eq!(debouncer.debounced().await, "b");
+        assert!(start.elapsed() >= Duration::from_millis(20));
+    }
+}
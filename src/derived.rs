@@ -0,0 +1,29 @@
+use crate::{Instant, TtlQueue};
+
+/// A wrapper around a [`TtlQueue`] maintaining state derived from its elements, e.g.
+/// counts or running statistics, which therefore has to observe every removed element.
+pub(crate) trait DerivedState {
+    type Element;
+
+    /// Gets the wrapped queue.
+    fn queue(&self) -> &TtlQueue<Self::Element>;
+
+    /// Removes the front element from the wrapped queue and updates the derived state.
+    fn remove_front(&mut self);
+
+    /// Removes the elements that expired as of the specified instant and returns the
+    /// number of remaining elements.
+    fn expire_at(&mut self, now: Instant) -> usize {
+        while self.queue().is_expired_at(0, now) {
+            self.remove_front();
+        }
+        self.queue().len()
+    }
+
+    /// Removes the elements that expired according to the clock of the wrapped queue
+    /// and returns the number of remaining elements.
+    fn expire(&mut self) -> usize {
+        let now = self.queue().now();
+        self.expire_at(now)
+    }
+}
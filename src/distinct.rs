@@ -0,0 +1,185 @@
+use crate::Instant;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::VecDeque;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::time::Duration;
+
+/// Estimates the number of distinct values seen within a sliding time window.
+///
+/// This is a sliding HyperLogLog: instead of the largest rank, each of the `2^precision`
+/// registers keeps the timestamps of the ranks that may still become the largest one
+/// as older observations expire. Memory is therefore bounded by the number of registers
+/// and grows only logarithmically with the number of values, independent of how many
+/// distinct values appear. The standard error of the estimate is about
+/// `1.04 / sqrt(2^precision)`, e.g. 1.6% for a precision of 12.
+///
+/// ## Example
+///
+/// ```
+/// # use std::time::Duration;
+/// # use ttl_queue::DistinctWindow;
+/// let mut visitors = DistinctWindow::new(Duration::from_secs(60), 12);
+/// for i in 0..10_000 {
+///     visitors.insert(&(i % 1000));
+/// }
+///
+/// let estimate = visitors.approx_distinct();
+/// assert!((950..=1050).contains(&estimate));
+/// ```
+#[derive(Debug)]
+pub struct DistinctWindow<T: ?Sized> {
+    ttl: Duration,
+    precision: u32,
+    /// Per register, the observations ordered by time with strictly decreasing ranks.
+    registers: Vec<VecDeque<(Instant, u8)>>,
+    _values: PhantomData<fn(&T)>,
+}
+
+impl<T: Hash + ?Sized> DistinctWindow<T> {
+    /// Creates an empty [`DistinctWindow`] with `2^precision` registers.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `precision` is not within `4..=16`.
+    pub fn new(ttl: Duration, precision: u32) -> Self {
+        assert!(
+            (4..=16).contains(&precision),
+            "The precision must be between 4 and 16"
+        );
+        Self {
+            ttl,
+            precision,
+            registers: vec![VecDeque::new(); 1 << precision],
+            _values: PhantomData,
+        }
+    }
+
+    /// Gets the time-to-live of the observations.
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Gets the number of bits selecting a register.
+    pub fn precision(&self) -> u32 {
+        self.precision
+    }
+
+    /// Records an observation of the specified value.
+    pub fn insert(&mut self, value: &T) {
+        self.insert_at(Instant::now(), value)
+    }
+
+    /// Records an observation of the specified value at the specified instant.
+    ///
+    /// The instant must not be earlier than that of the previous observation.
+    pub fn insert_at(&mut self, instant: Instant, value: &T) {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        let hash = hasher.finish();
+
+        let index = (hash >> (64 - self.precision)) as usize;
+        let rank = ((hash << self.precision) | (1 << (self.precision - 1))).leading_zeros() + 1;
+        let rank = rank as u8;
+
+        let ttl = self.ttl;
+        let register = &mut self.registers[index];
+        while register
+            .front()
+            .is_some_and(|(observed, _)| instant.saturating_duration_since(*observed) >= ttl)
+        {
+            register.pop_front();
+        }
+        while register
+            .back()
+            .is_some_and(|(_, previous)| *previous <= rank)
+        {
+            register.pop_back();
+        }
+        register.push_back((instant, rank));
+    }
+
+    /// Removes the observations that have expired.
+    pub fn refresh(&mut self) {
+        self.refresh_at(Instant::now())
+    }
+
+    /// Removes the observations that have expired at the specified instant.
+    pub fn refresh_at(&mut self, now: Instant) {
+        for register in &mut self.registers {
+            while register
+                .front()
+                .is_some_and(|(observed, _)| now.saturating_duration_since(*observed) >= self.ttl)
+            {
+                register.pop_front();
+            }
+        }
+    }
+
+    /// Estimates the number of distinct values observed within the TTL.
+    pub fn approx_distinct(&self) -> usize {
+        self.approx_distinct_at(Instant::now())
+    }
+
+    /// Estimates the number of distinct values observed within the TTL before the
+    /// specified instant.
+    pub fn approx_distinct_at(&self, now: Instant) -> usize {
+        let (sum, zeros) = self
+            .registers
+            .iter()
+            .map(|register| {
+                register
+                    .iter()
+                    .find(|(observed, _)| now.saturating_duration_since(*observed) < self.ttl)
+                    .map_or(0, |(_, rank)| *rank)
+            })
+            .fold((0.0, 0usize), |(sum, zeros), rank| {
+                (
+                    sum + (-f64::from(rank)).exp2(),
+                    zeros + usize::from(rank == 0),
+                )
+            });
+
+        let m = self.registers.len() as f64;
+        let alpha = match self.registers.len() {
+            16 => 0.673,
+            32 => 0.697,
+            64 => 0.709,
+            _ => 0.7213 / (1.0 + 1.079 / m),
+        };
+
+        let estimate = alpha * m * m / sum;
+        let estimate = if estimate <= 2.5 * m && zeros > 0 {
+            m * (m / zeros as f64).ln()
+        } else {
+            estimate
+        };
+        estimate.round() as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn old_values_are_forgotten() {
+        let mut window = DistinctWindow::new(Duration::from_secs(10), 10);
+        let now = Instant::now();
+        for i in 0..500u32 {
+            window.insert_at(now, &i);
+        }
+        for i in 500..600u32 {
+            window.insert_at(now + Duration::from_secs(5), &i);
+        }
+
+        let estimate = window.approx_distinct_at(now + Duration::from_secs(5));
+        assert!((550..=650).contains(&estimate), "{estimate}");
+
+        let estimate = window.approx_distinct_at(now + Duration::from_secs(10));
+        assert!((90..=110).contains(&estimate), "{estimate}");
+
+        window.refresh_at(now + Duration::from_secs(15));
+        assert_eq!(window.approx_distinct_at(now + Duration::from_secs(15)), 0);
+    }
+}
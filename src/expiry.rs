@@ -0,0 +1,115 @@
+use crate::{Instant, TtlQueue};
+use std::time::Duration;
+
+/// Decides whether an element of a [`TtlQueue`] has expired, e.g. to let error entries
+/// expire faster than regular ones.
+///
+/// Without a policy, elements expire after the TTL of the queue. This corresponds to
+/// the policy implemented for [`Duration`]. Policies are also implemented for closures
+/// taking the instant the element was added at, the current instant and the element.
+///
+/// An element that expired must remain expired as time advances.
+pub trait ExpiryPolicy<T> {
+    /// Determines whether the element added at `inserted` has expired at `now`.
+    fn expired(&self, inserted: Instant, now: Instant, value: &T) -> bool;
+}
+
+impl<T> ExpiryPolicy<T> for Duration {
+    fn expired(&self, inserted: Instant, now: Instant, _value: &T) -> bool {
+        now.saturating_duration_since(inserted) >= *self
+    }
+}
+
+impl<T, F> ExpiryPolicy<T> for F
+where
+    F: Fn(Instant, Instant, &T) -> bool,
+{
+    fn expired(&self, inserted: Instant, now: Instant, value: &T) -> bool {
+        self(inserted, now, value)
+    }
+}
+
+impl<T> TtlQueue<T> {
+    /// Sets the policy that decides whether an element has expired, replacing the TTL.
+    ///
+    /// Elements are removed as soon as the policy considers them expired; the
+    /// [grace period](Self::set_grace_period) and [TTL jitter](Self::set_ttl_jitter)
+    /// do not apply. Since elements may then expire out of order, each refresh
+    /// checks all elements. Estimates such as [`time_until_empty`](Self::time_until_empty)
+    /// are still based on the TTL.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use ttl_queue::{Instant, TickClock, TtlQueue};
+    /// let clock = TickClock::new();
+    /// let mut responses = TtlQueue::new(TickClock::ttl(60));
+    /// responses.set_clock(clock.clone());
+    /// responses.set_expiry_policy(|inserted: Instant, now: Instant, status: &u16| {
+    ///     let ttl = if *status >= 500 { TickClock::ttl(10) } else { TickClock::ttl(60) };
+    ///     now.saturating_duration_since(inserted) >= ttl
+    /// });
+    ///
+    /// responses.push_back(503);
+    /// responses.push_back(200);
+    /// clock.advance(30);
+    ///
+    /// assert_eq!(responses.refresh(), 1);
+    /// assert_eq!(responses.front(), Some(&200));
+    /// ```
+    pub fn set_expiry_policy<P>(&mut self, policy: P)
+    where
+        P: ExpiryPolicy<T> + Send + Sync + 'static,
+    {
+        self.expiry_policy = Some(Box::new(policy));
+        *self.expired_hint.get_mut() = 0;
+    }
+
+    /// Removes the policy set via [`set_expiry_policy`](Self::set_expiry_policy), so that
+    /// elements expire after the TTL again.
+    pub fn clear_expiry_policy(&mut self) {
+        self.expiry_policy = None;
+        *self.expired_hint.get_mut() = 0;
+    }
+
+    /// Returns `true` if an [expiry policy](Self::set_expiry_policy) is set.
+    pub fn has_expiry_policy(&self) -> bool {
+        self.expiry_policy.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TickClock;
+
+    #[test]
+    fn policy_expires_out_of_order() {
+        let clock = TickClock::new();
+        let mut queue = TtlQueue::new(TickClock::ttl(10));
+        queue.set_clock(clock.clone());
+        queue.set_grace_period(TickClock::ttl(100));
+        queue.set_expiry_policy(|inserted: Instant, now: Instant, value: &u32| {
+            now.saturating_duration_since(inserted) >= TickClock::ttl(u64::from(*value))
+        });
+
+        for value in [5, 1, 3, 20] {
+            queue.push_back(value);
+        }
+        clock.advance(2);
+        assert_eq!(queue.valid_len(), 3);
+        assert_eq!(queue.peek_nth(1).map(|(_, value)| *value), Some(3));
+        assert_eq!(queue.stale_len(), 0);
+
+        clock.advance(2);
+        assert_eq!(queue.refresh(), 2);
+        let values: Vec<_> = queue.iter().map(|(_, value)| *value).collect();
+        assert_eq!(values, [5, 20]);
+
+        queue.clear_expiry_policy();
+        clock.advance(10);
+        assert_eq!(queue.valid_len(), 0);
+        assert_eq!(queue.refresh(), 0);
+        assert_eq!(queue.stale_len(), 2);
+    }
+}
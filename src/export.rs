@@ -0,0 +1,124 @@
+use crate::{Instant, TtlQueue};
+
+#[cfg(feature = "csv")]
+use std::fmt::Display;
+#[cfg(feature = "csv")]
+use std::io::{self, Write};
+
+#[cfg(feature = "json")]
+use serde::Serialize;
+
+/// Gets the age of an entry in whole milliseconds, saturating at [`u64::MAX`].
+fn age_ms(now: Instant, instant: Instant) -> u64 {
+    u64::try_from(now.saturating_duration_since(instant).as_millis()).unwrap_or(u64::MAX)
+}
+
+/// A single row of an exported window.
+#[cfg(feature = "json")]
+#[derive(Serialize)]
+struct Row<'a, T> {
+    age_ms: u64,
+    value: &'a T,
+}
+
+impl<T> TtlQueue<T> {
+    /// Writes the contents of the queue as CSV with an `age_ms,value` header, oldest
+    /// element first. The age is measured relative to the time of the call.
+    ///
+    /// Values are formatted using their [`Display`] implementation and quoted if required.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use ttl_queue::TtlQueue;
+    /// let mut queue = TtlQueue::new(Duration::from_secs(60));
+    /// queue.push_back("hello, world");
+    ///
+    /// let mut csv = Vec::new();
+    /// queue.export_csv(&mut csv).unwrap();
+    /// assert_eq!(String::from_utf8(csv).unwrap(), "age_ms,value\n0,\"hello, world\"\n");
+    /// ```
+    #[cfg(feature = "csv")]
+    pub fn export_csv<W: Write>(&self, mut writer: W) -> io::Result<()>
+    where
+        T: Display,
+    {
+        let now = self.now();
+        writeln!(writer, "age_ms,value")?;
+        for (instant, element) in self.iter() {
+            let value = element.to_string();
+            let age = age_ms(now, *instant);
+            if value.contains([',', '"', '\n', '\r']) {
+                writeln!(writer, "{age},\"{}\"", value.replace('"', "\"\""))?;
+            } else {
+                writeln!(writer, "{age},{value}")?;
+            }
+        }
+        writer.flush()
+    }
+
+    /// Serializes the contents of the queue as a JSON array of `{"age_ms": .., "value": ..}`
+    /// objects, oldest element first. The age is measured relative to the time of the call.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use ttl_queue::TtlQueue;
+    /// let mut queue = TtlQueue::new(Duration::from_secs(60));
+    /// queue.push_back(42);
+    ///
+    /// let json = queue.export_json().unwrap();
+    /// assert_eq!(json, r#"[{"age_ms":0,"value":42}]"#);
+    /// ```
+    #[cfg(feature = "json")]
+    pub fn export_json(&self) -> serde_json::Result<String>
+    where
+        T: Serialize,
+    {
+        let now = self.now();
+        let rows: Vec<_> = self
+            .iter()
+            .map(|(instant, value)| Row {
+                age_ms: age_ms(now, *instant),
+                value,
+            })
+            .collect();
+        serde_json::to_string(&rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn queue() -> TtlQueue<&'static str> {
+        let now = Instant::now();
+        let mut queue = TtlQueue::new(Duration::MAX);
+        queue.push_back_entry(now - Duration::from_secs(2), "a");
+        queue.push_back_entry(now - Duration::from_secs(1), "say \"hi\"");
+        queue
+    }
+
+    #[test]
+    #[cfg(feature = "csv")]
+    fn export_csv_works() {
+        let mut csv = Vec::new();
+        queue().export_csv(&mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "age_ms,value\n2000,a\n1000,\"say \"\"hi\"\"\"\n"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn export_json_works() {
+        assert_eq!(
+            queue().export_json().unwrap(),
+            r#"[{"age_ms":2000,"value":"a"},{"age_ms":1000,"value":"say \"hi\""}]"#
+        );
+    }
+}
@@ -0,0 +1,122 @@
+use crate::{Instant, TtlQueue};
+use std::fmt::{Debug, Formatter};
+
+/// An iterator that removes and yields the elements of a [`TtlQueue`] matching a predicate.
+///
+/// See [`TtlQueue::extract_if`].
+pub struct ExtractIf<'a, T, F>
+where
+    F: FnMut(Instant, &T) -> bool,
+{
+    queue: &'a mut TtlQueue<T>,
+    predicate: F,
+    index: usize,
+    extracted: usize,
+}
+
+impl<T> TtlQueue<T> {
+    /// Returns an iterator that removes and yields the elements, along with the time
+    /// instants at which they were added, for which the predicate returns `true`.
+    ///
+    /// Elements are tested lazily from front to back, so dropping the iterator early
+    /// retains the remaining elements, matching or not. Expired elements are tested as
+    /// well unless the queue was [refreshed](Self::refresh) first.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use ttl_queue::TtlQueue;
+    /// let mut queue = TtlQueue::new(Duration::from_secs(60));
+    /// for i in 0..10 {
+    ///     queue.push_back(i);
+    /// }
+    ///
+    /// let first_evens: Vec<_> = queue
+    ///     .extract_if(|_, value| value % 2 == 0)
+    ///     .map(|(_, value)| value)
+    ///     .take(3)
+    ///     .collect();
+    ///
+    /// assert_eq!(first_evens, [0, 2, 4]);
+    /// assert_eq!(queue.len(), 7);
+    /// ```
+    pub fn extract_if<F>(&mut self, predicate: F) -> ExtractIf<'_, T, F>
+    where
+        F: FnMut(Instant, &T) -> bool,
+    {
+        ExtractIf {
+            queue: self,
+            predicate,
+            index: 0,
+            extracted: 0,
+        }
+    }
+}
+
+impl<T, F> Iterator for ExtractIf<'_, T, F>
+where
+    F: FnMut(Instant, &T) -> bool,
+{
+    type Item = (Instant, T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((instant, element)) = self.queue.get(self.index) {
+            if (self.predicate)(*instant, element) {
+                self.extracted += 1;
+                return self.queue.remove(self.index);
+            }
+            self.index += 1;
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.queue.len() - self.index))
+    }
+}
+
+impl<T, F> Drop for ExtractIf<'_, T, F>
+where
+    F: FnMut(Instant, &T) -> bool,
+{
+    fn drop(&mut self) {
+        if self.extracted > 0 {
+            self.queue.update_thresholds(self.queue.len());
+        }
+    }
+}
+
+impl<T: Debug, F> Debug for ExtractIf<'_, T, F>
+where
+    F: FnMut(Instant, &T) -> bool,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ExtractIf")
+            .field("queue", &self.queue)
+            .field("index", &self.index)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn dropping_early_retains_remaining_elements() {
+        let mut queue = TtlQueue::new(Duration::MAX);
+        let now = Instant::now();
+        for i in 0..6u64 {
+            queue.push_back_entry(now + Duration::from_secs(i), i);
+        }
+
+        let mut extract = queue.extract_if(|instant, _| instant >= now + Duration::from_secs(2));
+        assert_eq!(extract.next(), Some((now + Duration::from_secs(2), 2)));
+        drop(extract);
+
+        let values: Vec<_> = queue.iter().map(|(_, value)| *value).collect();
+        assert_eq!(values, [0, 1, 3, 4, 5]);
+    }
+}
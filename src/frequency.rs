@@ -0,0 +1,229 @@
+use crate::derived::DerivedState;
+use crate::{Clock, Instant, TtlQueue};
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::time::Duration;
+
+/// A [`TtlQueue`] counting the occurrences of each distinct value in the window.
+///
+/// The count of a value is incremented on push and decremented when an occurrence
+/// expires, so [`count`](Self::count) is O(1) and [`top_k`](Self::top_k) only
+/// considers the distinct values, e.g. for "top talkers in the last minute"
+/// dashboards.
+///
+/// ## Example
+///
+/// ```
+/// # use std::time::Duration;
+/// # use ttl_queue::FrequencyWindow;
+/// let mut talkers = FrequencyWindow::new(Duration::from_secs(60));
+/// for host in ["10.0.0.1", "10.0.0.2", "10.0.0.1", "10.0.0.3", "10.0.0.1", "10.0.0.2"] {
+///     talkers.push_back(host);
+/// }
+///
+/// assert_eq!(talkers.refresh(), 6);
+/// assert_eq!(talkers.top_k(2), [(&"10.0.0.1", 3), (&"10.0.0.2", 2)]);
+/// ```
+#[derive(Debug)]
+pub struct FrequencyWindow<T> {
+    queue: TtlQueue<T>,
+    counts: HashMap<T, usize>,
+}
+
+impl<T: Hash + Eq + Clone> FrequencyWindow<T> {
+    /// Creates an empty [`FrequencyWindow`].
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            queue: TtlQueue::new(ttl),
+            counts: HashMap::new(),
+        }
+    }
+
+    /// Gets the values of the window.
+    pub fn queue(&self) -> &TtlQueue<T> {
+        &self.queue
+    }
+
+    /// Gets the number of values, including potentially expired values.
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Returns `true` if the window is definitely empty or `false` if it is possibly empty.
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// Pushes a value to the end of the window.
+    pub fn push_back(&mut self, value: T) {
+        self.push_back_at(self.queue.now(), value)
+    }
+
+    /// Pushes a value stamped with the specified instant to the end of the window.
+    ///
+    /// The instant must not be earlier than that of the previously pushed value.
+    pub fn push_back_at(&mut self, instant: Instant, value: T) {
+        *self.counts.entry(value.clone()).or_default() += 1;
+        self.queue.push_back_entry(instant, value);
+    }
+
+    /// Removes the value from the front of the window and returns it, as well as the
+    /// time instant at which it was added.
+    pub fn pop_front(&mut self) -> Option<(Instant, T)> {
+        let (instant, value) = self.queue.pop_front_entry()?;
+        if let Some(count) = self.counts.get_mut(&value) {
+            *count -= 1;
+            if *count == 0 {
+                self.counts.remove(&value);
+            }
+        }
+        Some((instant, value))
+    }
+
+    /// Sets the clock used to timestamp new values and to determine expiry.
+    ///
+    /// See [`TtlQueue::set_clock`].
+    pub fn set_clock(&mut self, clock: impl Clock + 'static) {
+        self.queue.set_clock(clock);
+    }
+
+    /// Refreshes the window and returns the number of currently contained values.
+    pub fn refresh(&mut self) -> usize {
+        self.expire()
+    }
+
+    /// Refreshes the window as of the specified instant and returns the number of
+    /// currently contained values.
+    pub fn refresh_at(&mut self, now: Instant) -> usize {
+        self.expire_at(now)
+    }
+
+    /// Gets the number of occurrences of the specified value, including potentially
+    /// expired occurrences. This operation is O(1).
+    pub fn count(&self, value: &T) -> usize {
+        self.counts.get(value).copied().unwrap_or(0)
+    }
+
+    /// Refreshes the window and returns `true` if the specified value occurred within
+    /// the TTL, e.g. to suppress duplicates. This operation is amortized O(1).
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use ttl_queue::FrequencyWindow;
+    /// let mut delivered = FrequencyWindow::new(Duration::from_secs(60));
+    /// delivered.push_back("msg-1");
+    ///
+    /// assert!(delivered.seen_recently(&"msg-1"));
+    /// assert!(!delivered.seen_recently(&"msg-2"));
+    /// ```
+    pub fn seen_recently(&mut self, value: &T) -> bool {
+        self.seen_recently_at(self.queue.now(), value)
+    }
+
+    /// Refreshes the window as of the specified instant and returns `true` if the
+    /// specified value occurred within the TTL.
+    pub fn seen_recently_at(&mut self, now: Instant, value: &T) -> bool {
+        self.refresh_at(now);
+        self.counts.contains_key(value)
+    }
+
+    /// Pushes a value to the end of the window unless it occurred within the TTL and
+    /// returns `true` if it was pushed.
+    pub fn push_back_unique(&mut self, value: T) -> bool {
+        let now = self.queue.now();
+        if self.seen_recently_at(now, &value) {
+            return false;
+        }
+        self.push_back_at(now, value);
+        true
+    }
+
+    /// Gets the number of distinct values.
+    pub fn distinct(&self) -> usize {
+        self.counts.len()
+    }
+
+    /// Returns up to `k` of the most frequent values along with their number of
+    /// occurrences, most frequent first. The order of values occurring equally often
+    /// is unspecified.
+    ///
+    /// This operation is O(M + k log k) in the number M of distinct values.
+    pub fn top_k(&self, k: usize) -> Vec<(&T, usize)> {
+        let mut counts: Vec<(&T, usize)> = self
+            .counts
+            .iter()
+            .map(|(value, count)| (value, *count))
+            .collect();
+
+        let by_count = |lhs: &(&T, usize), rhs: &(&T, usize)| rhs.1.cmp(&lhs.1);
+        if k < counts.len() {
+            counts.select_nth_unstable_by(k, by_count);
+            counts.truncate(k);
+        }
+        counts.sort_unstable_by(by_count);
+        counts
+    }
+}
+
+impl<T: Hash + Eq + Clone> DerivedState for FrequencyWindow<T> {
+    type Element = T;
+
+    fn queue(&self) -> &TtlQueue<T> {
+        &self.queue
+    }
+
+    fn remove_front(&mut self) {
+        self.pop_front();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TickClock;
+
+    #[test]
+    fn top_k_follows_expiry() {
+        let mut window = FrequencyWindow::new(Duration::from_secs(10));
+        let now = Instant::now();
+        for (i, value) in ['a', 'a', 'a', 'b', 'b', 'c'].into_iter().enumerate() {
+            window.push_back_at(now + Duration::from_secs(i as u64), value);
+        }
+        assert_eq!(window.top_k(1), [(&'a', 3)]);
+        assert_eq!(window.top_k(10).len(), 3);
+
+        assert_eq!(window.refresh_at(now + Duration::from_secs(12)), 3);
+        assert_eq!(window.top_k(2), [(&'b', 2), (&'c', 1)]);
+        assert_eq!(window.count(&'a'), 0);
+        assert_eq!(window.distinct(), 2);
+        assert!(window.top_k(0).is_empty());
+
+        assert!(window.seen_recently_at(now + Duration::from_secs(14), &'c'));
+        assert!(!window.seen_recently_at(now + Duration::from_secs(15), &'c'));
+    }
+
+    #[test]
+    fn window_follows_the_queue_clock() {
+        let clock = TickClock::new();
+        let mut window = FrequencyWindow::new(TickClock::ttl(10));
+        window.set_clock(clock.clone());
+
+        window.push_back('a');
+        clock.advance(5);
+        assert!(window.push_back_unique('b'));
+        assert!(!window.push_back_unique('a'));
+
+        clock.advance(5);
+        assert_eq!(window.refresh(), 1);
+        assert!(window.push_back_unique('a'));
+        assert_eq!(
+            window
+                .queue()
+                .front_entry()
+                .map(|(instant, _)| clock.ticks_at(*instant)),
+            Some(5)
+        );
+    }
+}
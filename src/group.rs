@@ -0,0 +1,169 @@
+use crate::{Clock, SystemClock, TtlQueue};
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::hash::Hash;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// A set of keyed [`TtlQueue`]s sharing a TTL and a clock.
+///
+/// All queues of the group use the same [`Clock`], and [`refresh_all`](Self::refresh_all)
+/// reads it only once for all queues, e.g. when tracking events for thousands of
+/// connections.
+///
+/// ## Example
+///
+/// ```
+/// # use std::time::Duration;
+/// # use ttl_queue::TtlQueueGroup;
+/// let mut group = TtlQueueGroup::new(Duration::from_secs(60));
+/// group.push_back("alice", 1);
+/// group.push_back("alice", 2);
+/// group.push_back("bob", 3);
+///
+/// let counts = group.refresh_all();
+/// assert_eq!(counts["alice"], 2);
+/// assert_eq!(counts["bob"], 1);
+/// ```
+pub struct TtlQueueGroup<K, T> {
+    ttl: Duration,
+    clock: Arc<dyn Clock>,
+    queues: HashMap<K, TtlQueue<T>>,
+}
+
+impl<K: Hash + Eq, T> TtlQueueGroup<K, T> {
+    /// Creates an empty group whose queues use the specified TTL.
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            clock: Arc::new(SystemClock),
+            queues: HashMap::new(),
+        }
+    }
+
+    /// Gets the time-to-live of the elements.
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Sets the time-to-live of the elements of all queues in the group.
+    pub fn set_ttl(&mut self, ttl: Duration) {
+        self.ttl = ttl;
+        for queue in self.queues.values_mut() {
+            queue.set_ttl(ttl);
+        }
+    }
+
+    /// Sets the clock shared by all queues in the group.
+    ///
+    /// Defaults to the [`SystemClock`]. See [`TtlQueue::set_clock`].
+    pub fn set_clock(&mut self, clock: impl Clock + 'static) {
+        self.clock = Arc::new(clock);
+        for queue in self.queues.values_mut() {
+            queue.set_shared_clock(self.clock.clone());
+        }
+    }
+
+    /// Gets the number of queues in the group.
+    pub fn len(&self) -> usize {
+        self.queues.len()
+    }
+
+    /// Returns `true` if the group contains no queues.
+    pub fn is_empty(&self) -> bool {
+        self.queues.is_empty()
+    }
+
+    /// Pushes an element to the end of the queue for the specified key,
+    /// creating the queue if necessary.
+    pub fn push_back(&mut self, key: K, element: T) {
+        self.queue_mut(key).push_back(element)
+    }
+
+    /// Gets the queue for the specified key, creating it if necessary.
+    pub fn queue_mut(&mut self, key: K) -> &mut TtlQueue<T> {
+        let (ttl, clock) = (self.ttl, &self.clock);
+        self.queues.entry(key).or_insert_with(|| {
+            let mut queue = TtlQueue::new(ttl);
+            queue.set_shared_clock(clock.clone());
+            queue
+        })
+    }
+
+    /// Gets the queue for the specified key, if any.
+    pub fn get<Q>(&self, key: &Q) -> Option<&TtlQueue<T>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.queues.get(key)
+    }
+
+    /// Removes the queue for the specified key from the group and returns it.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<TtlQueue<T>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.queues.remove(key)
+    }
+
+    /// Refreshes all queues using a single clock read and returns the number of
+    /// currently contained elements per queue.
+    pub fn refresh_all(&mut self) -> HashMap<K, usize>
+    where
+        K: Clone,
+    {
+        let now = self.clock.now();
+        self.queues
+            .iter_mut()
+            .map(|(key, queue)| (key.clone(), queue.refresh_at(now)))
+            .collect()
+    }
+
+    /// Removes all queues that are empty, e.g. after a [`refresh_all`](Self::refresh_all).
+    pub fn remove_empty(&mut self) {
+        self.queues.retain(|_, queue| !queue.is_empty());
+    }
+
+    /// Returns an iterator over the keys and queues of the group.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &TtlQueue<T>)> {
+        self.queues.iter()
+    }
+}
+
+impl<K: Debug, T: Debug> Debug for TtlQueueGroup<K, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TtlQueueGroup")
+            .field("ttl", &self.ttl)
+            .field("queues", &self.queues)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(all(test, not(feature = "mock_instant")))]
+mod tests {
+    use super::*;
+    use crate::Instant;
+
+    #[test]
+    fn refresh_all_counts_per_queue() {
+        let mut group = TtlQueueGroup::new(Duration::from_secs(10));
+        let expired = Instant::now() - Duration::from_secs(20);
+        group.queue_mut(1).push_back_entry(expired, "old");
+        group.push_back(1, "new");
+        group.push_back(2, "new");
+        group.queue_mut(3).push_back_entry(expired, "old");
+
+        let counts = group.refresh_all();
+        assert_eq!(counts.len(), 3);
+        assert_eq!(counts[&1], 1);
+        assert_eq!(counts[&2], 1);
+        assert_eq!(counts[&3], 0);
+
+        group.remove_empty();
+        assert_eq!(group.len(), 2);
+        assert!(group.get(&3).is_none());
+    }
+}
@@ -0,0 +1,166 @@
+use crate::{Clock, Instant, TtlQueueGroup};
+use std::borrow::Borrow;
+use std::fmt::{Debug, Formatter};
+use std::hash::Hash;
+use std::time::Duration;
+
+/// Tracks the liveness of multiple peers by their heartbeats.
+///
+/// Each peer is backed by a [`TtlQueue`](crate::TtlQueue) of a [`TtlQueueGroup`]
+/// holding its beats within the timeout. A peer is considered dead once its last
+/// beat is at least the timeout ago. Dead peers are reported until they beat again
+/// or are [removed](Self::remove).
+///
+/// ## Example
+///
+/// ```
+/// # use std::time::Duration;
+/// # use ttl_queue::{HeartbeatMonitor, TickClock};
+/// let clock = TickClock::new();
+/// let mut monitor = HeartbeatMonitor::new(TickClock::ttl(30));
+/// monitor.set_clock(clock.clone());
+///
+/// monitor.beat("node-1");
+/// monitor.beat("node-2");
+/// clock.advance(20);
+/// monitor.beat("node-2");
+/// clock.advance(20);
+///
+/// assert_eq!(monitor.dead_peers().collect::<Vec<_>>(), [&"node-1"]);
+/// assert!(monitor.is_alive("node-2"));
+/// ```
+pub struct HeartbeatMonitor<K> {
+    peers: TtlQueueGroup<K, ()>,
+}
+
+impl<K: Hash + Eq> HeartbeatMonitor<K> {
+    /// Creates a monitor considering peers dead if they did not beat within the timeout.
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            peers: TtlQueueGroup::new(timeout),
+        }
+    }
+
+    /// Gets the duration after the last beat at which a peer is considered dead.
+    pub fn timeout(&self) -> Duration {
+        self.peers.ttl()
+    }
+
+    /// Sets the clock used to timestamp the beats.
+    ///
+    /// Defaults to the [`SystemClock`](crate::SystemClock).
+    pub fn set_clock(&mut self, clock: impl Clock + 'static) {
+        self.peers.set_clock(clock);
+    }
+
+    /// Gets the number of tracked peers, alive or dead.
+    pub fn len(&self) -> usize {
+        self.peers.len()
+    }
+
+    /// Returns `true` if no peers are tracked.
+    pub fn is_empty(&self) -> bool {
+        self.peers.is_empty()
+    }
+
+    /// Records a heartbeat of the specified peer, starting to track it if necessary.
+    pub fn beat(&mut self, peer: K) {
+        self.peers.queue_mut(peer).refresh_and_push_back(());
+    }
+
+    /// Gets the time of the last beat of the specified peer, or `None` if it is not tracked.
+    pub fn last_beat<Q>(&self, peer: &Q) -> Option<Instant>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.peers.get(peer)?.last_push()
+    }
+
+    /// Gets the number of beats of the specified peer within the timeout.
+    pub fn beats<Q>(&self, peer: &Q) -> usize
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.peers.get(peer).map_or(0, |beats| beats.valid_len())
+    }
+
+    /// Returns `true` if the specified peer is tracked and beat within the timeout.
+    pub fn is_alive<Q>(&self, peer: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let timeout = self.timeout();
+        self.peers
+            .get(peer)
+            .is_some_and(|beats| !beats.is_stale(timeout))
+    }
+
+    /// Returns an iterator over the peers whose last beat is at least the timeout ago.
+    pub fn dead_peers(&self) -> impl Iterator<Item = &K> {
+        let timeout = self.timeout();
+        self.peers
+            .iter()
+            .filter(move |(_, beats)| beats.is_stale(timeout))
+            .map(|(peer, _)| peer)
+    }
+
+    /// Returns an iterator over the peers that beat within the timeout.
+    pub fn alive_peers(&self) -> impl Iterator<Item = &K> {
+        let timeout = self.timeout();
+        self.peers
+            .iter()
+            .filter(move |(_, beats)| !beats.is_stale(timeout))
+            .map(|(peer, _)| peer)
+    }
+
+    /// Stops tracking the specified peer and returns `true` if it was tracked.
+    pub fn remove<Q>(&mut self, peer: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.peers.remove(peer).is_some()
+    }
+}
+
+impl<K: Hash + Eq + Debug> Debug for HeartbeatMonitor<K> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HeartbeatMonitor")
+            .field("timeout", &self.peers.ttl())
+            .field(
+                "peers",
+                &self.peers.iter().map(|(peer, _)| peer).collect::<Vec<_>>(),
+            )
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TickClock;
+
+    #[test]
+    fn dead_peers_revive_on_beat() {
+        let clock = TickClock::new();
+        let mut monitor = HeartbeatMonitor::new(TickClock::ttl(10));
+        monitor.set_clock(clock.clone());
+        monitor.beat(1);
+        monitor.beat(1);
+        assert_eq!(monitor.beats(&1), 2);
+
+        clock.advance(10);
+        assert!(!monitor.is_alive(&1));
+        assert_eq!(monitor.alive_peers().count(), 0);
+        assert_eq!(monitor.beats(&1), 0);
+
+        monitor.beat(1);
+        assert!(monitor.is_alive(&1));
+        assert_eq!(monitor.last_beat(&1), Some(clock.now()));
+        assert!(monitor.remove(&1));
+        assert!(monitor.is_empty());
+    }
+}
@@ -0,0 +1,119 @@
+use crate::{Instant, TtlQueue};
+use std::fmt::{Debug, Formatter};
+use std::iter::FusedIterator;
+
+/// A consuming iterator over the elements of a [`TtlQueue`] that had not expired when
+/// the iterator was created.
+///
+/// See [`TtlQueue::into_iter_valid`].
+pub struct IntoIterValid<T> {
+    queue: TtlQueue<T>,
+    now: Instant,
+    notify: bool,
+}
+
+impl<T> TtlQueue<T> {
+    /// Converts the queue into an iterator over the elements that have not expired,
+    /// as well as the time instants at which they were added.
+    ///
+    /// The current time is captured once when this method is called, and expired
+    /// elements are dropped while the iterator is consumed. Unlike the owning
+    /// [`IntoIterator`] implementation, this does not yield expired elements. Use
+    /// [`IntoIterValid::notify_listeners`] to report the dropped elements to the
+    /// expiry listeners.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use ttl_queue::{TickClock, TtlQueue};
+    /// let clock = TickClock::new();
+    /// let mut queue = TtlQueue::new(TickClock::ttl(3));
+    /// queue.set_clock(clock.clone());
+    /// for i in 0..5 {
+    ///     queue.push_back(i);
+    ///     clock.tick();
+    /// }
+    ///
+    /// let values: Vec<_> = queue.into_iter_valid().map(|(_, value)| value).collect();
+    /// assert_eq!(values, [3, 4]);
+    /// ```
+    pub fn into_iter_valid(self) -> IntoIterValid<T> {
+        IntoIterValid {
+            now: self.now(),
+            queue: self,
+            notify: false,
+        }
+    }
+}
+
+impl<T> IntoIterValid<T> {
+    /// Reports the dropped expired elements to the listeners registered via
+    /// [`on_expired`](TtlQueue::on_expired).
+    pub fn notify_listeners(mut self) -> Self {
+        self.notify = true;
+        self
+    }
+}
+
+impl<T> Iterator for IntoIterValid<T> {
+    type Item = (Instant, T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.queue.is_empty() {
+            let expired = self.queue.is_expired_at(0, self.now);
+            let entry = self.queue.pop_front_entry()?;
+            if !expired {
+                return Some(entry);
+            }
+            if self.notify {
+                self.queue.retire(entry);
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.queue.len()))
+    }
+}
+
+impl<T> FusedIterator for IntoIterValid<T> {}
+
+impl<T: Debug> Debug for IntoIterValid<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("IntoIterValid")
+            .field("now", &self.now)
+            .field("remaining", &self.queue.len())
+            .field("notify", &self.notify)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TickClock;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn expired_elements_are_routed_to_listeners() {
+        let clock = TickClock::new();
+        let mut queue = TtlQueue::new(TickClock::ttl(3));
+        queue.set_clock(clock.clone());
+
+        let expired = Arc::new(Mutex::new(Vec::new()));
+        let sink = expired.clone();
+        queue.on_expired(move |_, value: &u32| sink.lock().unwrap().push(*value));
+
+        for i in 0..4 {
+            queue.push_back(i);
+            clock.tick();
+        }
+        let iter = queue.into_iter_valid().notify_listeners();
+        clock.advance(10);
+
+        let values: Vec<_> = iter.map(|(_, value)| value).collect();
+        assert_eq!(values, [2, 3]);
+        assert_eq!(*expired.lock().unwrap(), [0, 1]);
+    }
+}
@@ -0,0 +1,174 @@
+use crate::derived::DerivedState;
+use crate::{Clock, Instant, TtlQueue};
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::hash::Hash;
+use std::time::Duration;
+
+/// A [`TtlQueue`] counting its elements per key extracted from each element.
+///
+/// Unlike [`TtlQueue::counts_by_key`], which recounts all elements on every call, the
+/// count of a key is incremented on push and decremented when an element expires, so
+/// [`count`](Self::count) is O(1). Unlike a [`LabeledTtlQueue`](crate::LabeledTtlQueue),
+/// the key is derived from the element rather than passed along with it. Like
+/// [`TtlQueue::len`], the counts include expired elements until the window is
+/// [refreshed](Self::refresh).
+///
+/// ## Example
+///
+/// ```
+/// # use std::time::Duration;
+/// # use ttl_queue::KeyedCountWindow;
+/// let mut responses = KeyedCountWindow::new(Duration::from_secs(60), |status: &u16| status / 100);
+/// for status in [200, 404, 200, 503, 200] {
+///     responses.push_back(status);
+/// }
+///
+/// assert_eq!(responses.refresh(), 5);
+/// assert_eq!(responses.count(&2), 3);
+/// assert_eq!(responses.count(&4), 1);
+/// assert_eq!(responses.count(&3), 0);
+/// ```
+pub struct KeyedCountWindow<T, K, F> {
+    queue: TtlQueue<T>,
+    counts: HashMap<K, usize>,
+    extract_key: F,
+}
+
+impl<T, K, F> KeyedCountWindow<T, K, F>
+where
+    K: Hash + Eq,
+    F: Fn(&T) -> K,
+{
+    /// Creates an empty [`KeyedCountWindow`] counting the elements per key extracted
+    /// by `extract_key`.
+    pub fn new(ttl: Duration, extract_key: F) -> Self {
+        Self {
+            queue: TtlQueue::new(ttl),
+            counts: HashMap::new(),
+            extract_key,
+        }
+    }
+
+    /// Gets the elements of the window.
+    pub fn queue(&self) -> &TtlQueue<T> {
+        &self.queue
+    }
+
+    /// Gets the number of elements, including potentially expired elements.
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Returns `true` if the window is definitely empty or `false` if it is possibly empty.
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// Pushes an element to the end of the window.
+    pub fn push_back(&mut self, element: T) {
+        self.push_back_at(self.queue.now(), element)
+    }
+
+    /// Pushes an element stamped with the specified instant to the end of the window.
+    ///
+    /// The instant must not be earlier than that of the previously pushed element.
+    pub fn push_back_at(&mut self, instant: Instant, element: T) {
+        *self.counts.entry((self.extract_key)(&element)).or_default() += 1;
+        self.queue.push_back_entry(instant, element);
+    }
+
+    /// Removes the element from the front of the window and returns it, as well as the
+    /// time instant at which it was added.
+    pub fn pop_front(&mut self) -> Option<(Instant, T)> {
+        let (instant, element) = self.queue.pop_front_entry()?;
+        let key = (self.extract_key)(&element);
+        if let Some(count) = self.counts.get_mut(&key) {
+            *count -= 1;
+            if *count == 0 {
+                self.counts.remove(&key);
+            }
+        }
+        Some((instant, element))
+    }
+
+    /// Gets the number of elements with the specified key, including potentially
+    /// expired elements. This operation is O(1).
+    pub fn count(&self, key: &K) -> usize {
+        self.counts.get(key).copied().unwrap_or(0)
+    }
+
+    /// Returns an iterator over the keys and their number of elements.
+    pub fn counts(&self) -> impl Iterator<Item = (&K, usize)> {
+        self.counts.iter().map(|(key, count)| (key, *count))
+    }
+
+    /// Sets the clock used to timestamp new elements and to determine expiry.
+    ///
+    /// See [`TtlQueue::set_clock`].
+    pub fn set_clock(&mut self, clock: impl Clock + 'static) {
+        self.queue.set_clock(clock);
+    }
+
+    /// Refreshes the window and returns the number of currently contained elements.
+    pub fn refresh(&mut self) -> usize {
+        self.expire()
+    }
+
+    /// Refreshes the window as of the specified instant and returns the number of
+    /// currently contained elements.
+    pub fn refresh_at(&mut self, now: Instant) -> usize {
+        self.expire_at(now)
+    }
+}
+
+impl<T, K, F> DerivedState for KeyedCountWindow<T, K, F>
+where
+    K: Hash + Eq,
+    F: Fn(&T) -> K,
+{
+    type Element = T;
+
+    fn queue(&self) -> &TtlQueue<T> {
+        &self.queue
+    }
+
+    fn remove_front(&mut self) {
+        self.pop_front();
+    }
+}
+
+impl<T: Debug, K: Debug, F> Debug for KeyedCountWindow<T, K, F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KeyedCountWindow")
+            .field("queue", &self.queue)
+            .field("counts", &self.counts)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TickClock;
+
+    #[test]
+    fn counts_follow_expiry() {
+        let clock = TickClock::new();
+        let mut window = KeyedCountWindow::new(TickClock::ttl(10), |word: &&str| word.len());
+        window.set_clock(clock.clone());
+        for word in ["a", "bb", "cc", "d"] {
+            window.push_back(word);
+            clock.tick();
+        }
+        assert_eq!((window.count(&1), window.count(&2)), (2, 2));
+
+        clock.advance(7);
+        assert_eq!(window.refresh(), 2);
+        assert_eq!((window.count(&1), window.count(&2)), (1, 1));
+
+        clock.advance(3);
+        assert_eq!(window.refresh(), 0);
+        assert_eq!(window.counts().count(), 0);
+    }
+}
@@ -0,0 +1,157 @@
+use crate::derived::DerivedState;
+use crate::{Clock, Instant, TtlQueue};
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::time::Duration;
+
+/// A [`TtlQueue`] whose elements carry a label, with per-label counts.
+///
+/// The number of elements per label is updated on every push and removal, so
+/// [`count_where`](Self::count_where) is O(1) and a single queue can serve multiple
+/// breakdowns of the same events. Like [`TtlQueue::len`], the counts include
+/// expired elements until the queue is [refreshed](Self::refresh).
+///
+/// ## Example
+///
+/// ```
+/// # use std::time::Duration;
+/// # use ttl_queue::LabeledTtlQueue;
+/// let mut requests = LabeledTtlQueue::new(Duration::from_secs(60));
+/// requests.push_back(200, "/index.html");
+/// requests.push_back(404, "/favicon.ico");
+/// requests.push_back(200, "/style.css");
+///
+/// assert_eq!(requests.refresh(), 3);
+/// assert_eq!(requests.count_where(&200), 2);
+/// assert_eq!(requests.iter_labeled(&404).map(|(_, path)| *path).collect::<Vec<_>>(), ["/favicon.ico"]);
+/// assert_eq!(requests.count_where(&500), 0);
+/// ```
+#[derive(Debug)]
+pub struct LabeledTtlQueue<L, T> {
+    queue: TtlQueue<(L, T)>,
+    counts: HashMap<L, usize>,
+}
+
+impl<L: Hash + Eq + Clone, T> LabeledTtlQueue<L, T> {
+    /// Creates an empty [`LabeledTtlQueue`].
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            queue: TtlQueue::new(ttl),
+            counts: HashMap::new(),
+        }
+    }
+
+    /// Gets the time-to-live of the elements.
+    pub fn ttl(&self) -> Duration {
+        self.queue.ttl()
+    }
+
+    /// Gets the number of elements, including potentially expired elements.
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Returns `true` if the queue is definitely empty or `false` if it is possibly empty.
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// Pushes a labeled element to the end of the queue.
+    pub fn push_back(&mut self, label: L, element: T) {
+        self.push_back_at(self.queue.now(), label, element)
+    }
+
+    /// Pushes a labeled element stamped with the specified instant to the end of the queue.
+    ///
+    /// The instant must not be earlier than that of the previously pushed element.
+    pub fn push_back_at(&mut self, instant: Instant, label: L, element: T) {
+        *self.counts.entry(label.clone()).or_default() += 1;
+        self.queue.push_back_entry(instant, (label, element));
+    }
+
+    /// Removes the element from the front of the queue and returns it, along with its
+    /// label and the time instant at which it was added.
+    pub fn pop_front(&mut self) -> Option<(Instant, L, T)> {
+        let (instant, (label, element)) = self.queue.pop_front_entry()?;
+        self.decrement(&label);
+        Some((instant, label, element))
+    }
+
+    /// Gets the number of elements with the specified label, including potentially
+    /// expired elements. This operation is O(1).
+    pub fn count_where(&self, label: &L) -> usize {
+        self.counts.get(label).copied().unwrap_or(0)
+    }
+
+    /// Returns an iterator over the labels and their number of elements.
+    pub fn counts(&self) -> impl Iterator<Item = (&L, usize)> {
+        self.counts.iter().map(|(label, count)| (label, *count))
+    }
+
+    /// Returns an iterator over the elements with the specified label, oldest first.
+    pub fn iter_labeled<'a>(&'a self, label: &'a L) -> impl Iterator<Item = (Instant, &'a T)> {
+        self.queue
+            .iter()
+            .filter(move |(_, (entry_label, _))| entry_label == label)
+            .map(|(instant, (_, element))| (*instant, element))
+    }
+
+    /// Sets the clock used to timestamp new elements and to determine expiry.
+    ///
+    /// See [`TtlQueue::set_clock`].
+    pub fn set_clock(&mut self, clock: impl Clock + 'static) {
+        self.queue.set_clock(clock);
+    }
+
+    /// Refreshes the queue and returns the number of currently contained elements.
+    pub fn refresh(&mut self) -> usize {
+        self.expire()
+    }
+
+    /// Refreshes the queue as of the specified instant and returns the number of
+    /// currently contained elements.
+    pub fn refresh_at(&mut self, now: Instant) -> usize {
+        self.expire_at(now)
+    }
+
+    fn decrement(&mut self, label: &L) {
+        if let Some(count) = self.counts.get_mut(label) {
+            *count -= 1;
+            if *count == 0 {
+                self.counts.remove(label);
+            }
+        }
+    }
+}
+
+impl<L: Hash + Eq + Clone, T> DerivedState for LabeledTtlQueue<L, T> {
+    type Element = (L, T);
+
+    fn queue(&self) -> &TtlQueue<(L, T)> {
+        &self.queue
+    }
+
+    fn remove_front(&mut self) {
+        self.pop_front();
+    }
+}
+
+#[cfg(all(test, not(feature = "mock_instant")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_follow_expiry() {
+        let mut queue = LabeledTtlQueue::new(Duration::from_secs(10));
+        let now = Instant::now();
+        queue.push_back_at(now - Duration::from_secs(20), "error", 1);
+        queue.push_back_at(now - Duration::from_secs(15), "warning", 2);
+        queue.push_back_at(now, "error", 3);
+        assert_eq!(queue.count_where(&"error"), 2);
+
+        assert_eq!(queue.refresh_at(now), 1);
+        assert_eq!(queue.count_where(&"error"), 1);
+        assert_eq!(queue.count_where(&"warning"), 0);
+        assert_eq!(queue.counts().count(), 1);
+    }
+}
@@ -6,7 +6,24 @@
 //!
 //! * `vecdeque` - Uses a `VecDeque` as the underlying data structure. Enabled by default.
 //! * `doublestack` - Uses two stacks (`Vec`) as the underlying data structure. Mutually exclusive with `vecdeque`.
-//! * `tokio` - Uses [`tokio::time::Instant`] instead of [`std::time::Instant`].
+//! * `tokio` - Uses [`tokio::time::Instant`] instead of [`std::time::Instant`] and enables
+//!   `AsyncTtlQueue` and `TtlQueue::into_actor` for sharing a queue across tasks.
+//! * `arc-swap` - Enables `SnapshotTtlQueue` for wait-free reads of published snapshots.
+//! * `parking_lot` - Enables `SyncTtlQueue`, a thread-safe wrapper using a `parking_lot` mutex.
+//! * `crossbeam` - Enables `bridge_receiver()` to buffer the elements of a `crossbeam-channel`
+//!   receiver in a `SyncTtlQueue`. Implies `parking_lot`.
+//! * `lz4` - Enables `CompressedTtlQueue`, which stores byte payloads LZ4-compressed in blocks.
+//! * `mmap` - Enables `MmapTtlQueue`, which persists byte payloads in a memory-mapped file.
+//! * `governor` - Enables `TtlStateStore` and `GovernorClock` for use with `governor` rate limiters.
+//! * `tower` - Enables `RequestWindowLayer`, a `tower` layer recording per-route request counts and latencies.
+//! * `log` - Enables `log_expired()` to log expired elements via the `log` facade.
+//! * `rayon` - Enables parallel iteration via `par_iter()` and `IntoParallelIterator`.
+//! * `csv` - Enables `export_csv()` to dump the contents of a queue as CSV.
+//! * `json` - Enables `export_json()` to dump the contents of a queue as JSON.
+//! * `arrow` - Enables `to_record_batch()` to convert the contents of a queue into an Arrow `RecordBatch`.
+//! * `mock_instant` - Uses `mock_instant::global::Instant` instead of [`std::time::Instant`], so
+//!   that time is controlled by `mock_instant::global::MockClock`. Mutually exclusive with `tokio`.
+//! * `validate` - Runs `debug_validate()` after every mutating operation to catch ordering violations early.
 //!
 //! ## Example
 //!
@@ -26,6 +43,8 @@
 //!
 //!     // Sleep ~20 ms to achieve a ~50 Hz frequency.
 //!     thread::sleep(Duration::from_millis(19));
+//! #     #[cfg(feature = "mock_instant")]
+//! #     mock_instant::global::MockClock::advance(Duration::from_millis(19));
 //! }
 //!
 //! let fps = fps_counter.refresh();
@@ -35,17 +54,153 @@
 //! debug_assert!(delta >= Duration::from_millis(19) && delta <= Duration::from_millis(21));
 //! ```
 
+use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
+use std::fmt::{self, Debug, Formatter};
+use std::hash::{Hash, Hasher};
+use std::mem::size_of;
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+use std::sync::Arc;
 use std::time::Duration;
 
-#[cfg(not(feature = "tokio"))]
-use std::time::Instant;
+/// The timestamp type used by the queue, depending on the enabled crate features.
+#[cfg(not(any(feature = "tokio", feature = "mock_instant")))]
+pub use std::time::Instant;
 
+/// The timestamp type used by the queue, depending on the enabled crate features.
 #[cfg(feature = "tokio")]
-use tokio::time::Instant;
+pub use tokio::time::Instant;
+
+/// The timestamp type used by the queue, depending on the enabled crate features.
+#[cfg(feature = "mock_instant")]
+pub use mock_instant::global::Instant;
+
+#[cfg(all(feature = "tokio", feature = "mock_instant"))]
+compile_error!("The `tokio` and `mock_instant` features are mutually exclusive");
 
-#[cfg(feature = "vecdeque")]
 use std::collections::VecDeque;
 
+#[cfg(feature = "tokio")]
+mod actor;
+#[cfg(feature = "arrow")]
+pub mod arrow;
+#[cfg(feature = "tokio")]
+mod async_queue;
+mod bucketed;
+mod channel;
+mod clock;
+#[cfg(feature = "lz4")]
+mod compressed;
+mod correlate;
+#[cfg(feature = "crossbeam")]
+mod crossbeam;
+mod cursor;
+#[cfg(feature = "chrono")]
+mod datetime;
+mod debounce;
+mod derived;
+mod distinct;
+mod expiry;
+#[cfg(any(feature = "csv", feature = "json"))]
+mod export;
+mod extract;
+mod frequency;
+mod group;
+mod heartbeat;
+mod into_valid;
+mod keyed;
+mod labeled;
+#[cfg(feature = "log")]
+mod logging;
+mod lru;
+mod median;
+#[cfg(feature = "mmap")]
+mod mmap;
+mod multimap;
+#[cfg(feature = "rayon")]
+mod parallel;
+mod popped;
+mod presence;
+mod priority;
+mod producer;
+#[cfg(feature = "governor")]
+mod rate_limit;
+mod replay;
+mod resample;
+#[cfg(feature = "tower")]
+mod route_stats;
+mod sharded;
+mod signal;
+mod snapshot;
+mod spill;
+#[cfg(feature = "parking_lot")]
+mod sync;
+mod tee;
+mod throttle;
+mod tiered;
+mod tumbling;
+mod wal;
+mod wheel;
+mod window;
+
+#[cfg(feature = "tokio")]
+pub use actor::TtlQueueHandle;
+#[cfg(feature = "tokio")]
+pub use async_queue::AsyncTtlQueue;
+pub use bucketed::BucketedTtlQueue;
+pub use channel::{channel, TtlReceiver, TtlSender};
+pub use clock::{CachedClock, Clock, SystemClock, TickClock, TimerClock};
+#[cfg(feature = "lz4")]
+pub use compressed::CompressedTtlQueue;
+pub use correlate::Correlate;
+#[cfg(feature = "crossbeam")]
+pub use crossbeam::bridge_receiver;
+pub use cursor::CursorMut;
+pub use debounce::Debouncer;
+pub use distinct::DistinctWindow;
+pub use expiry::ExpiryPolicy;
+pub use extract::ExtractIf;
+pub use frequency::FrequencyWindow;
+pub use group::TtlQueueGroup;
+pub use heartbeat::HeartbeatMonitor;
+pub use into_valid::IntoIterValid;
+pub use keyed::KeyedCountWindow;
+pub use labeled::LabeledTtlQueue;
+#[cfg(feature = "log")]
+pub use logging::ExpiryLogger;
+pub use lru::{CacheStats, LruTtlCache};
+pub use median::MedianWindow;
+#[cfg(feature = "mmap")]
+pub use mmap::MmapTtlQueue;
+pub use multimap::TtlMultiMap;
+pub use popped::PoppedGuard;
+pub use presence::{PresenceGuard, PresenceQueue};
+pub use priority::TtlPriorityQueue;
+pub use producer::TtlProducer;
+#[cfg(feature = "governor")]
+pub use rate_limit::{GovernorClock, TtlRateLimiter, TtlStateStore};
+pub use replay::{Recording, RecordingTtlQueue, Replayer};
+pub use resample::Interpolation;
+#[cfg(feature = "tower")]
+pub use route_stats::{RecordingFuture, RequestWindowLayer, RequestWindowService, RouteStats};
+pub use sharded::ShardedTtlQueue;
+pub use signal::SignalWindow;
+use snapshot::FrozenSegments;
+pub use snapshot::{SnapshotIter, TtlSnapshot};
+
+#[cfg(feature = "arc-swap")]
+pub use snapshot::{SnapshotReader, SnapshotTtlQueue};
+pub use spill::SpillingTtlQueue;
+#[cfg(feature = "parking_lot")]
+pub use sync::SyncTtlQueue;
+pub use tee::TtlTee;
+pub use throttle::{Throttle, ThrottleEdge};
+pub use tiered::TieredTtlQueue;
+pub use tumbling::TumblingWindow;
+pub use wal::WalTtlQueue;
+pub use wheel::TimingWheel;
+pub use window::{CountWindow, HoppingWindow, Session};
+
 /// A queue that drops its content after a given amount of time.
 ///
 /// ## Example
@@ -66,6 +221,8 @@ use std::collections::VecDeque;
 ///
 ///     // Sleep ~20 ms to achieve a ~50 Hz frequency.
 ///     thread::sleep(Duration::from_millis(19));
+/// #     #[cfg(feature = "mock_instant")]
+/// #     mock_instant::global::MockClock::advance(Duration::from_millis(19));
 /// }
 ///
 /// let fps = fps_counter.refresh();
@@ -74,201 +231,2374 @@ use std::collections::VecDeque;
 /// let delta = fps_counter.avg_delta();
 /// debug_assert!(delta >= Duration::from_millis(19) && delta <= Duration::from_millis(21));
 /// ```
-#[derive(Debug)]
 pub struct TtlQueue<T> {
     ttl: Duration,
+    grace: Duration,
+    jitter: Duration,
+    /// The random TTL offsets of the elements, front to back, while a jitter is set.
+    jitter_offsets: Option<VecDeque<u64>>,
+    /// The number of elements that were assigned a jitter offset.
+    jitter_sequence: u64,
     #[cfg(feature = "doublestack")]
     stack_1: Vec<(Instant, T)>,
     #[cfg(feature = "doublestack")]
     stack_2: Vec<(Instant, T)>,
     #[cfg(feature = "vecdeque")]
     queue: VecDeque<(Instant, T)>,
+    smoothed_rate: Option<(Instant, f64)>,
+    listeners: Vec<(ListenerId, ExpiryListener<T>)>,
+    late_listeners: Vec<(ListenerId, ExpiryListener<T>)>,
+    push_hooks: Vec<(ListenerId, PushHook<T>)>,
+    thresholds: Vec<ThresholdWatch>,
+    next_listener_id: u64,
+    budget: Option<ByteBudget<T>>,
+    refresh_policy: RefreshPolicy,
+    last_refresh: Option<Instant>,
+    /// The timestamp of the newest element ever pushed, retained after it expires.
+    last_push: Option<Instant>,
+    /// How far behind the newest element out-of-order elements are accepted.
+    allowed_lateness: Duration,
+    /// The number of leading elements known to be expired; advanced lazily
+    /// by [`valid_len`](Self::valid_len) and [`iter_valid`](Self::iter_valid).
+    expired_hint: AtomicUsize,
+    clock: Arc<dyn Clock>,
+    /// The time at which expiry was paused, if it is.
+    paused_at: Option<Instant>,
+    /// The clock of a [simulated](Self::simulated) queue, driven by [`advance`](Self::advance).
+    simulation: Option<TickClock>,
+    /// The maximum number of elements configured via [`with_max_len`](Self::with_max_len).
+    max_len: Option<usize>,
+    overflow_policy: OverflowPolicy,
+    /// The most recently expired elements, retained for [`recently_expired`](Self::recently_expired).
+    tombstones: VecDeque<(Instant, T)>,
+    tombstone_capacity: usize,
+    /// Decides whether an element expired instead of the TTL, if set.
+    expiry_policy: Option<BoxedExpiryPolicy<T>>,
+    /// The segments shared with the snapshots returned by [`freeze`](Self::freeze).
+    frozen: FrozenSegments,
+    /// The time of creation according to both the clock and the wall clock,
+    /// used to convert timestamps to dates.
+    #[cfg(feature = "chrono")]
+    anchor: (Instant, chrono::DateTime<chrono::Utc>),
+}
+
+/// Determines when a [`TtlQueue`] removes expired elements implicitly.
+///
+/// Regardless of the policy, [`TtlQueue::refresh`] can always be called explicitly.
+/// Operations taking `&self`, such as [`TtlQueue::len`] or [`TtlQueue::iter`], never
+/// refresh the queue.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RefreshPolicy {
+    /// Expired elements are only removed by explicit calls to [`TtlQueue::refresh`].
+    #[default]
+    Manual,
+    /// The queue is refreshed before every [`TtlQueue::push_back`].
+    OnPush,
+    /// The queue is refreshed before every [`TtlQueue::pop_front`] and [`TtlQueue::peek_front`].
+    OnRead,
+    /// The queue is refreshed on any push, pop or peek if the last refresh is at least
+    /// the specified duration ago.
+    Every(Duration),
+}
+
+/// Determines what happens when pushing into a [`TtlQueue`] that holds its
+/// [maximum number](TtlQueue::with_max_len) of unexpired elements.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OverflowPolicy {
+    /// The pushed element is discarded and the queue is left unchanged.
+    #[default]
+    DropNewest,
+    /// The oldest element is replaced by the pushed element, like in a ring buffer.
+    /// Replaced elements are reported to [expiry listeners](TtlQueue::on_expired).
+    Overwrite,
+}
+
+/// The kind of operation that may trigger an implicit refresh.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RefreshTrigger {
+    Push,
+    Read,
+}
+
+/// A memory budget configured via [`TtlQueue::with_max_bytes`].
+struct ByteBudget<T> {
+    max_bytes: usize,
+    total_bytes: usize,
+    /// Whether `total_bytes` is outdated because elements were modified in place.
+    stale: bool,
+    cost: Arc<dyn Fn(&T) -> usize + Send + Sync>,
+}
+
+impl<T> ByteBudget<T> {
+    /// Creates an empty budget with the same limit and cost function.
+    fn empty_like(&self) -> Self {
+        Self {
+            max_bytes: self.max_bytes,
+            total_bytes: 0,
+            stale: false,
+            cost: self.cost.clone(),
+        }
+    }
+}
+
+/// A callback that is invoked for every element that expires from a [`TtlQueue`].
+type ExpiryListener<T> = Box<dyn FnMut(Instant, &T) + Send + Sync>;
+
+/// A callback that transforms or rejects every element pushed into a [`TtlQueue`].
+type PushHook<T> = Box<dyn FnMut(T) -> Option<T> + Send + Sync>;
+
+/// A policy deciding whether an element expired.
+type BoxedExpiryPolicy<T> = Box<dyn ExpiryPolicy<T> + Send + Sync>;
+
+/// Identifies a listener registered via [`TtlQueue::on_expired`], [`TtlQueue::on_push`]
+/// or [`TtlQueue::watch_threshold`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ListenerId(u64);
+
+/// The direction in which a threshold registered via [`TtlQueue::watch_threshold`] was crossed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ThresholdCrossing {
+    /// The number of elements rose above the threshold.
+    Above,
+    /// The number of elements fell back to or below the threshold.
+    Below,
+}
+
+/// A threshold registered via [`TtlQueue::watch_threshold`].
+struct ThresholdWatch {
+    id: ListenerId,
+    threshold: usize,
+    above: bool,
+    callback: Box<dyn FnMut(ThresholdCrossing, usize) + Send + Sync>,
+}
+
+/// Statistics on the time between consecutive elements of a [`TtlQueue`].
+///
+/// See [`TtlQueue::inter_arrival_stats`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InterArrivalStats {
+    /// The average duration between two consecutive elements.
+    pub mean: Duration,
+    /// The (population) standard deviation of the durations between two consecutive elements.
+    pub std_dev: Duration,
+    /// The largest duration between two consecutive elements.
+    pub max: Duration,
+}
+
+/// Frame time statistics of a game loop, as displayed by frame rate overlays.
+///
+/// See [`TtlQueue::frame_timings`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameTimings {
+    /// The average frame time.
+    pub average: Duration,
+    /// The average frame time of the slowest 1% of frames, but at least of the slowest frame.
+    pub one_percent_low: Duration,
+    /// The longest frame time.
+    pub worst: Duration,
+}
+
+impl FrameTimings {
+    /// Gets the average number of frames per second.
+    pub fn average_fps(&self) -> f64 {
+        1.0 / self.average.as_secs_f64()
+    }
+
+    /// Gets the number of frames per second of the slowest 1% of frames.
+    pub fn one_percent_low_fps(&self) -> f64 {
+        1.0 / self.one_percent_low.as_secs_f64()
+    }
 }
 
 impl<T> TtlQueue<T> {
     /// Creates an empty [`TtlQueue`] with default capacity.
     pub fn new(ttl: Duration) -> Self {
-        Self {
-            ttl,
-            #[cfg(feature = "doublestack")]
-            stack_1: Vec::new(),
-            #[cfg(feature = "doublestack")]
-            stack_2: Vec::new(),
-            #[cfg(feature = "vecdeque")]
-            queue: VecDeque::new(),
-        }
+        Self::with_capacity(ttl, 0)
     }
 
     /// Creates an empty [`TtlQueue`] for at least `capacity` elements.
     pub fn with_capacity(ttl: Duration, capacity: usize) -> Self {
         Self {
             ttl,
+            grace: Duration::ZERO,
+            jitter: Duration::ZERO,
+            jitter_offsets: None,
+            jitter_sequence: 0,
             #[cfg(feature = "doublestack")]
             stack_1: Vec::with_capacity(capacity),
             #[cfg(feature = "doublestack")]
             stack_2: Vec::with_capacity(capacity),
             #[cfg(feature = "vecdeque")]
             queue: VecDeque::with_capacity(capacity),
+            smoothed_rate: None,
+            listeners: Vec::new(),
+            late_listeners: Vec::new(),
+            push_hooks: Vec::new(),
+            thresholds: Vec::new(),
+            next_listener_id: 0,
+            budget: None,
+            refresh_policy: RefreshPolicy::Manual,
+            last_refresh: None,
+            last_push: None,
+            allowed_lateness: Duration::ZERO,
+            expired_hint: AtomicUsize::new(0),
+            clock: Arc::new(SystemClock),
+            paused_at: None,
+            simulation: None,
+            max_len: None,
+            overflow_policy: OverflowPolicy::DropNewest,
+            tombstones: VecDeque::new(),
+            tombstone_capacity: 0,
+            expiry_policy: None,
+            frozen: FrozenSegments::default(),
+            #[cfg(feature = "chrono")]
+            anchor: (Instant::now(), chrono::Utc::now()),
         }
     }
 
-    /// Pushes an element to the end of the queue.
-    pub fn push_back(&mut self, element: T) {
-        self.push_back_entry(Instant::now(), element)
+    /// Creates an empty [`TtlQueue`] whose elements are additionally bounded by a memory budget.
+    ///
+    /// The cost of each element is determined by the `cost` function, e.g. [`Vec::len`] or
+    /// [`String::len`]. Whenever the total cost exceeds `max_bytes` after a push, the oldest
+    /// elements are evicted until the queue is within budget again. Evicted elements are
+    /// reported to [expiry listeners](Self::on_expired).
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use ttl_queue::TtlQueue;
+    /// let mut queue = TtlQueue::with_max_bytes(Duration::from_secs(60), 10, |s: &String| s.len());
+    /// queue.push_back(String::from("hello"));
+    /// queue.push_back(String::from("world"));
+    /// assert_eq!(queue.len(), 2);
+    ///
+    /// queue.push_back(String::from("!"));
+    /// assert_eq!(queue.len(), 2);
+    /// assert_eq!(queue.total_bytes(), 6);
+    /// ```
+    pub fn with_max_bytes<F>(ttl: Duration, max_bytes: usize, cost: F) -> Self
+    where
+        F: Fn(&T) -> usize + Send + Sync + 'static,
+    {
+        let mut queue = Self::new(ttl);
+        queue.budget = Some(ByteBudget {
+            max_bytes,
+            total_bytes: 0,
+            stale: false,
+            cost: Arc::new(cost),
+        });
+        queue
     }
 
-    /// Pushes an element to the end of the queue.
-    fn push_back_entry(&mut self, instant: Instant, element: T) {
-        let entry = (instant, element);
-        #[cfg(feature = "doublestack")]
-        {
-            self.stack_1.push(entry);
+    /// Creates an empty [`TtlQueue`] holding at most `max_len` elements.
+    ///
+    /// When pushing into a full queue, expired elements are removed first. If the queue
+    /// is still full, the [overflow policy](Self::set_overflow_policy) determines whether
+    /// the pushed element is discarded, which is the default, or replaces the oldest element.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `max_len` is zero.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use ttl_queue::{OverflowPolicy, TtlQueue};
+    /// let mut telemetry = TtlQueue::with_max_len(Duration::from_secs(60), 3);
+    /// telemetry.set_overflow_policy(OverflowPolicy::Overwrite);
+    /// for sample in 0..5 {
+    ///     telemetry.push_back(sample);
+    /// }
+    ///
+    /// let samples: Vec<_> = telemetry.iter().map(|(_, sample)| *sample).collect();
+    /// assert_eq!(samples, [2, 3, 4]);
+    /// ```
+    pub fn with_max_len(ttl: Duration, max_len: usize) -> Self {
+        assert_ne!(max_len, 0, "The maximum length must not be zero");
+        let mut queue = Self::with_capacity(ttl, max_len);
+        queue.max_len = Some(max_len);
+        queue
+    }
+
+    /// Creates an empty [`TtlQueue`] that retains the last `capacity` expired elements,
+    /// so that they can be inspected via [`recently_expired`](Self::recently_expired),
+    /// e.g. to debug why an element disappeared without registering a
+    /// [listener](Self::on_expired) in advance.
+    ///
+    /// Elements evicted due to the [maximum length](Self::with_max_len) or the
+    /// [memory budget](Self::with_max_bytes) are retained as well, whereas elements that
+    /// are explicitly removed, e.g. via [`pop_front`](Self::pop_front), are not.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use ttl_queue::{TickClock, TtlQueue};
+    /// let clock = TickClock::new();
+    /// let mut queue = TtlQueue::with_tombstone_capacity(TickClock::ttl(10), 2);
+    /// queue.set_clock(clock.clone());
+    ///
+    /// for job in ["a", "b", "c"] {
+    ///     queue.push_back(job);
+    /// }
+    /// clock.advance(10);
+    /// assert_eq!(queue.refresh(), 0);
+    ///
+    /// let expired: Vec<_> = queue.recently_expired().map(|(_, job)| *job).collect();
+    /// assert_eq!(expired, ["b", "c"]);
+    /// ```
+    pub fn with_tombstone_capacity(ttl: Duration, capacity: usize) -> Self {
+        let mut queue = Self::new(ttl);
+        queue.tombstone_capacity = capacity;
+        queue.tombstones = VecDeque::with_capacity(capacity);
+        queue
+    }
+
+    /// Gets the number of expired elements retained as configured via
+    /// [`with_tombstone_capacity`](Self::with_tombstone_capacity).
+    pub fn tombstone_capacity(&self) -> usize {
+        self.tombstone_capacity
+    }
+
+    /// Returns an iterator over the most recently expired elements, oldest first, as
+    /// well as the time instants at which they were added.
+    ///
+    /// Only retains elements if the queue was created via
+    /// [`with_tombstone_capacity`](Self::with_tombstone_capacity).
+    pub fn recently_expired(&self) -> std::collections::vec_deque::Iter<'_, (Instant, T)> {
+        self.tombstones.iter()
+    }
+
+    /// Gets the maximum number of elements configured via [`with_max_len`](Self::with_max_len).
+    pub fn max_len(&self) -> Option<usize> {
+        self.max_len
+    }
+
+    /// Gets the policy applied when pushing into a full queue.
+    pub fn overflow_policy(&self) -> OverflowPolicy {
+        self.overflow_policy
+    }
+
+    /// Sets the policy applied when pushing into a queue holding its
+    /// [maximum number](Self::with_max_len) of unexpired elements.
+    ///
+    /// Defaults to [`OverflowPolicy::DropNewest`]. Without a maximum length, the
+    /// policy has no effect.
+    pub fn set_overflow_policy(&mut self, policy: OverflowPolicy) {
+        self.overflow_policy = policy;
+    }
+
+    /// Makes room for another element if the queue is at its maximum length and
+    /// returns `false` if the element is to be discarded instead.
+    fn make_room(&mut self) -> bool {
+        let Some(max_len) = self.max_len else {
+            return true;
+        };
+        if self.len() < max_len || self.refresh() < max_len {
+            return true;
         }
-        #[cfg(feature = "vecdeque")]
-        {
-            self.queue.push_back(entry)
+
+        match self.overflow_policy {
+            OverflowPolicy::DropNewest => false,
+            OverflowPolicy::Overwrite => {
+                while self.len() >= max_len {
+                    let Some(entry) = self.pop_front_entry() else {
+                        break;
+                    };
+                    self.retire(entry);
+                }
+                true
+            }
         }
     }
 
-    /// Pushes an element to the end of the queue and returns the number of items
-    /// currently in the queue. This operation is O(N) at worst.
-    pub fn refresh_and_push_back(&mut self, element: T) -> usize {
-        let count = self.refresh();
-        self.push_back(element);
-        count + 1
+    /// Gets the memory budget configured via [`with_max_bytes`](Self::with_max_bytes).
+    pub fn max_bytes(&self) -> Option<usize> {
+        self.budget.as_ref().map(|budget| budget.max_bytes)
     }
 
-    /// Gets the element from the front of the queue if it exists, as well as the
-    /// time instant at which it was added.
-    pub fn pop_front(&mut self) -> Option<(Instant, T)> {
-        #[cfg(feature = "doublestack")]
-        {
-            self.ensure_stack_full(false);
-            self.stack_2.pop()
+    /// Gets the total cost of all elements in the queue as determined by the cost function
+    /// configured via [`with_max_bytes`](Self::with_max_bytes), or zero if there is none.
+    pub fn total_bytes(&self) -> usize {
+        match &self.budget {
+            Some(budget) if budget.stale => self.compute_cost(budget),
+            Some(budget) => budget.total_bytes,
+            None => 0,
         }
-        #[cfg(feature = "vecdeque")]
+    }
+
+    fn add_cost(&mut self, element: &T) {
+        if let Some(budget) = &mut self.budget {
+            budget.total_bytes = budget.total_bytes.saturating_add((budget.cost)(element));
+        }
+    }
+
+    fn sub_cost(&mut self, element: &T) {
+        if let Some(budget) = &mut self.budget {
+            budget.total_bytes = budget.total_bytes.saturating_sub((budget.cost)(element));
+        }
+    }
+
+    /// Marks the total cost as outdated, e.g. because elements are modified in place.
+    fn invalidate_cost(&mut self) {
+        if let Some(budget) = &mut self.budget {
+            budget.stale = true;
+        }
+    }
+
+    /// Computes the total cost of all elements.
+    fn compute_cost(&self, budget: &ByteBudget<T>) -> usize {
+        self.iter()
+            .map(|(_, element)| (budget.cost)(element))
+            .fold(0, usize::saturating_add)
+    }
+
+    /// Evicts the oldest elements until the total cost is within the memory budget.
+    fn enforce_budget(&mut self) {
+        if let Some(budget) = &self.budget {
+            if budget.stale {
+                let total_bytes = self.compute_cost(budget);
+                if let Some(budget) = &mut self.budget {
+                    budget.total_bytes = total_bytes;
+                    budget.stale = false;
+                }
+            }
+        }
+
+        while self
+            .budget
+            .as_ref()
+            .is_some_and(|budget| budget.total_bytes > budget.max_bytes)
         {
-            self.queue.pop_front()
+            let Some(entry) = self.pop_front_entry() else {
+                break;
+            };
+            self.retire(entry);
         }
     }
 
-    /// Similar to [`pop_front`](Self::pop_front) but without removing the element.
-    pub fn peek_front(&mut self) -> Option<&(Instant, T)> {
+    /// Gets the capacity of the backing buffers, in elements.
+    fn capacity(&self) -> usize {
         #[cfg(feature = "doublestack")]
         {
-            self.ensure_stack_full(false);
-            self.stack_2.first()
+            self.stack_1.capacity() + self.stack_2.capacity()
         }
         #[cfg(feature = "vecdeque")]
         {
-            self.queue.front()
+            self.queue.capacity()
         }
     }
 
-    #[cfg(feature = "doublestack")]
-    fn ensure_stack_full(&mut self, force: bool) {
-        if self.stack_2.is_empty() || force {
-            while let Some(item) = self.stack_1.pop() {
-                self.stack_2.push(item);
+    /// Returns the approximate number of bytes occupied by the queue itself and its
+    /// backing buffers, including unused capacity.
+    ///
+    /// Memory owned by the elements themselves (e.g. the heap buffer of a `String`) is
+    /// not included; use [`approx_memory_bytes_with`](Self::approx_memory_bytes_with)
+    /// to account for it.
+    pub fn approx_memory_bytes(&self) -> usize {
+        size_of::<Self>()
+            + self.capacity() * size_of::<(Instant, T)>()
+            + self.listeners.capacity() * size_of::<(ListenerId, ExpiryListener<T>)>()
+            + self.late_listeners.capacity() * size_of::<(ListenerId, ExpiryListener<T>)>()
+            + self.push_hooks.capacity() * size_of::<(ListenerId, PushHook<T>)>()
+            + self.thresholds.capacity() * size_of::<ThresholdWatch>()
+            + self.tombstones.capacity() * size_of::<(Instant, T)>()
+    }
+
+    /// Like [`approx_memory_bytes`](Self::approx_memory_bytes), but additionally includes
+    /// the heap memory owned by each element as reported by the `sizer` function.
+    ///
+    /// This operation is O(N).
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use ttl_queue::TtlQueue;
+    /// let mut queue = TtlQueue::new(Duration::from_secs(60));
+    /// queue.push_back(vec![0u8; 1024]);
+    ///
+    /// let shallow = queue.approx_memory_bytes();
+    /// let deep = queue.approx_memory_bytes_with(|value| value.capacity());
+    /// assert_eq!(deep, shallow + 1024);
+    /// ```
+    pub fn approx_memory_bytes_with<F>(&self, sizer: F) -> usize
+    where
+        F: Fn(&T) -> usize,
+    {
+        self.iter()
+            .map(|(_, element)| sizer(element))
+            .fold(self.approx_memory_bytes(), usize::saturating_add)
+    }
+
+    /// Creates an empty queue sharing the TTL configuration, but not the listeners, of this queue.
+    fn empty_like<U>(&self) -> TtlQueue<U> {
+        let mut queue = TtlQueue::new(self.ttl);
+        queue.grace = self.grace;
+        queue.jitter = self.jitter;
+        queue.jitter_offsets = self.jitter_offsets.as_ref().map(|_| VecDeque::new());
+        queue.allowed_lateness = self.allowed_lateness;
+        queue.clock = self.clock.clone();
+        queue.paused_at = self.paused_at;
+        queue.simulation = self.simulation.clone();
+        queue.max_len = self.max_len;
+        queue.overflow_policy = self.overflow_policy;
+        queue.tombstone_capacity = self.tombstone_capacity;
+        #[cfg(feature = "chrono")]
+        {
+            queue.anchor = self.anchor;
+        }
+        queue
+    }
+
+    /// Splits the queue into two queues: the first contains all elements for which the
+    /// predicate returns `true`, the second all others.
+    ///
+    /// Timestamps and ordering are retained in both queues, as is the TTL configuration.
+    /// Registered listeners are dropped.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use ttl_queue::TtlQueue;
+    /// let mut queue = TtlQueue::new(Duration::from_secs(60));
+    /// for i in 0..10 {
+    ///     queue.push_back(i);
+    /// }
+    ///
+    /// let (even, odd) = queue.partition(|_instant, value| value % 2 == 0);
+    /// assert_eq!(even.len(), 5);
+    /// assert_eq!(odd.len(), 5);
+    /// ```
+    pub fn partition<F>(self, mut predicate: F) -> (TtlQueue<T>, TtlQueue<T>)
+    where
+        F: FnMut(Instant, &T) -> bool,
+    {
+        let mut matching = self.empty_like();
+        let mut others = self.empty_like();
+        for (instant, element) in self {
+            if predicate(instant, &element) {
+                matching.push_back_entry(instant, element);
+            } else {
+                others.push_back_entry(instant, element);
             }
         }
+        (matching, others)
     }
 
-    /// Gets the number elements currently in the queue, including potentially expired elements.
+    /// Transforms every element into a new queue, retaining the timestamps, ordering and
+    /// TTL configuration.
     ///
-    /// This operation is O(1). In order to obtain an accurate count in O(N) (worst-case),
-    /// use [`refresh`](Self::refresh) instead.
-    pub fn len(&self) -> usize {
+    /// Like [`partition`](Self::partition), registered listeners are dropped, as is the
+    /// [memory budget](Self::with_max_bytes), whose cost function does not apply to the
+    /// transformed elements. Expired elements are transformed as well unless the queue
+    /// was [refreshed](Self::refresh) first.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use ttl_queue::TtlQueue;
+    /// let mut requests = TtlQueue::new(Duration::from_secs(60));
+    /// requests.push_back("GET /index.html 200");
+    /// requests.push_back("GET /missing 404");
+    ///
+    /// let statuses = requests.map(|line| line.rsplit(' ').next().unwrap().to_string());
+    /// let values: Vec<_> = statuses.iter().map(|(_, status)| status.as_str()).collect();
+    /// assert_eq!(values, ["200", "404"]);
+    /// ```
+    pub fn map<U, F>(self, mut f: F) -> TtlQueue<U>
+    where
+        F: FnMut(T) -> U,
+    {
+        self.filter_map(|element| Some(f(element)))
+    }
+
+    /// Transforms every element into a new queue like [`map`](Self::map), but drops the
+    /// elements for which `f` returns `None`.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use ttl_queue::TtlQueue;
+    /// let mut readings = TtlQueue::new(Duration::from_secs(60));
+    /// for reading in ["21.5", "n/a", "22.0"] {
+    ///     readings.push_back(reading);
+    /// }
+    ///
+    /// let celsius = readings.filter_map(|reading| reading.parse::<f64>().ok());
+    /// assert_eq!(celsius.len(), 2);
+    /// ```
+    pub fn filter_map<U, F>(self, mut f: F) -> TtlQueue<U>
+    where
+        F: FnMut(T) -> Option<U>,
+    {
+        let mut mapped = self.empty_like();
+        for (instant, element) in self {
+            if let Some(element) = f(element) {
+                mapped.push_back_entry(instant, element);
+            }
+        }
+        mapped
+    }
+
+    /// Moves the oldest `n` elements into a new queue and returns it, retaining their
+    /// timestamps, e.g. to hand a batch over to a worker.
+    ///
+    /// Like [`partition`](Self::partition), the new queue shares the TTL configuration,
+    /// but not the listeners, of this queue. It also shares the
+    /// [memory budget](Self::with_max_bytes), accounting for the moved elements. Expired elements are moved as well unless
+    /// the queue was [refreshed](Self::refresh) first.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `n` is greater than the number of elements.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use ttl_queue::TtlQueue;
+    /// let mut queue = TtlQueue::new(Duration::from_secs(60));
+    /// for i in 0..10 {
+    ///     queue.push_back(i);
+    /// }
+    ///
+    /// let batch = queue.split_to(4);
+    /// assert_eq!(batch.iter().map(|(_, value)| *value).collect::<Vec<_>>(), [0, 1, 2, 3]);
+    /// assert_eq!(queue.len(), 6);
+    /// ```
+    pub fn split_to(&mut self, n: usize) -> TtlQueue<T> {
+        assert!(
+            n <= self.len(),
+            "Cannot split off {n} elements from a queue of {} elements",
+            self.len()
+        );
+
+        let mut batch = self.empty_like();
         #[cfg(feature = "doublestack")]
         {
-            self.stack_1.len() + self.stack_2.len()
+            let from_front = n.min(self.stack_2.len());
+            batch.stack_2 = self.stack_2.split_off(self.stack_2.len() - from_front);
+            batch.stack_1 = self.stack_1.drain(..n - from_front).collect();
         }
         #[cfg(feature = "vecdeque")]
         {
-            self.queue.len()
+            let rest = self.queue.split_off(n);
+            batch.queue = std::mem::replace(&mut self.queue, rest);
+        }
+
+        if let Some(budget) = &self.budget {
+            batch.budget = Some(budget.empty_like());
+            for (_, element) in batch.iter() {
+                self.sub_cost(element);
+            }
+            batch.invalidate_cost();
+        }
+        batch.last_push = batch.back_entry().map(|(instant, _)| *instant);
+        if let (Some(offsets), Some(batch_offsets)) =
+            (&self.jitter_offsets, &mut batch.jitter_offsets)
+        {
+            batch_offsets.extend(offsets.iter().take(n));
         }
+        self.forget_front(n);
+        self.update_thresholds(self.len());
+        self.validate_if_enabled();
+        batch
     }
 
-    /// Returns `true` if the queue is definitely empty or `false` if the queue is
-    /// possibly empty.
+    /// Merges bursts of elements whose timestamps are less than `gap` apart into
+    /// single elements and returns the number of elements removed by merging.
     ///
-    /// This operation is O(1). In order to obtain an accurate count in O(N) (worst-case),
-    /// use [`refresh`](Self::refresh) instead.
-    pub fn is_empty(&self) -> bool {
+    /// Each element of a burst is merged into the first element of the burst using
+    /// the `merge` function, in order. The merged element carries the timestamp of the
+    /// last element of the burst, so it does not expire before any of its parts.
+    ///
+    /// Elements are merged in place and keep their [jitter](Self::set_ttl_jitter); no
+    /// element is evicted, even if merging exceeds the [memory budget](Self::with_max_bytes),
+    /// until the next push.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use ttl_queue::{Instant, TtlQueue};
+    /// let now = Instant::now();
+    /// let events: Vec<_> = [(0, "a.rs"), (5, "b.rs"), (8, "a.rs"), (100, "c.rs")]
+    ///     .into_iter()
+    ///     .map(|(ms, path)| (now + Duration::from_millis(ms), vec![path]))
+    ///     .collect();
+    ///
+    /// let mut queue: TtlQueue<Vec<&str>> = TtlQueue::try_from(events).unwrap();
+    ///
+    /// let merged = queue.coalesce_within(Duration::from_millis(10), |paths, next| {
+    ///     paths.extend(next)
+    /// });
+    ///
+    /// assert_eq!(merged, 2);
+    /// let bursts: Vec<_> = queue.iter().map(|(_, paths)| paths.clone()).collect();
+    /// assert_eq!(bursts, [vec!["a.rs", "b.rs", "a.rs"], vec!["c.rs"]]);
+    /// ```
+    pub fn coalesce_within<F>(&mut self, gap: Duration, mut merge: F) -> usize
+    where
+        F: FnMut(&mut T, T),
+    {
+        let len = self.len();
+
+        // Take the entries in order, oldest first, without going through the per-element
+        // bookkeeping, which is adjusted once after merging.
+        #[cfg(feature = "doublestack")]
+        let entries = {
+            let mut entries = std::mem::take(&mut self.stack_2);
+            entries.reverse();
+            entries.append(&mut self.stack_1);
+            entries
+        };
+        #[cfg(feature = "vecdeque")]
+        let entries = Vec::from(std::mem::take(&mut self.queue));
+
+        let mut offsets = self
+            .jitter_offsets
+            .take()
+            .map(Vec::from)
+            .map(Vec::into_iter);
+        let mut kept_offsets = offsets
+            .as_ref()
+            .map(|offsets| Vec::with_capacity(offsets.len()));
+        let mut kept: Vec<(Instant, T)> = Vec::with_capacity(entries.len());
+        let mut first_merged = None;
+        for (instant, element) in entries {
+            let offset = offsets.as_mut().and_then(Iterator::next);
+            match kept.last_mut() {
+                Some(current) if instant.saturating_duration_since(current.0) < gap => {
+                    merge(&mut current.1, element);
+                    current.0 = instant;
+                    first_merged.get_or_insert(kept.len() - 1);
+                }
+                _ => {
+                    kept.push((instant, element));
+                    if let (Some(kept_offsets), Some(offset)) = (&mut kept_offsets, offset) {
+                        kept_offsets.push(offset);
+                    }
+                }
+            }
+        }
+
+        let merged = len - kept.len();
+        self.jitter_offsets = kept_offsets.map(VecDeque::from);
         #[cfg(feature = "doublestack")]
         {
-            self.stack_1.is_empty() && self.stack_2.is_empty()
+            kept.reverse();
+            self.stack_2 = kept;
         }
         #[cfg(feature = "vecdeque")]
         {
-            self.queue.is_empty()
+            self.queue = VecDeque::from(kept);
         }
-    }
 
-    /// Refreshes the queue and returns the number of currently contained elements.
-    #[cfg(feature = "doublestack")]
-    pub fn refresh(&mut self) -> usize {
-        let now = Instant::now();
+        if let Some(index) = first_merged {
+            *self.expired_hint.get_mut() = 0;
+            self.frozen.modified(index);
+            self.invalidate_cost();
+            self.update_thresholds(self.len());
+        }
+        self.validate_if_enabled();
+        merged
+    }
 
-        while let Some((instant, _element)) = self.stack_2.first() {
-            if (now - *instant) < self.ttl {
-                break;
-            }
+    /// Registers a listener that is invoked with every element that expires during a
+    /// [`refresh`](Self::refresh) or is evicted due to the [memory budget](Self::with_max_bytes),
+    /// as well as the time instant at which it was added.
+    ///
+    /// Any number of listeners can be registered; they are invoked in the order of
+    /// registration. Elements that are explicitly removed, e.g. via
+    /// [`pop_front`](Self::pop_front), are not reported.
+    pub fn on_expired<F>(&mut self, listener: F) -> ListenerId
+    where
+        F: FnMut(Instant, &T) + Send + Sync + 'static,
+    {
+        let id = ListenerId(self.next_listener_id);
+        self.next_listener_id += 1;
+        self.listeners.push((id, Box::new(listener)));
+        id
+    }
 
-            let _result = self.stack_2.pop();
-            debug_assert!(_result.is_some());
-        }
+    /// Registers a callback that is invoked whenever the number of elements rises
+    /// above or falls back to (or below) the specified threshold.
+    ///
+    /// The count is re-evaluated after every push and pop, as well as on every
+    /// [`refresh`](Self::refresh). Since expired elements are only removed by a refresh,
+    /// pushes and pops operate on the possibly outdated [`len`](Self::len).
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use std::sync::{Arc, Mutex};
+    /// # use std::time::Duration;
+    /// # use ttl_queue::{ThresholdCrossing, TtlQueue};
+    /// let mut queue = TtlQueue::new(Duration::from_secs(60));
+    /// let crossings = Arc::new(Mutex::new(Vec::new()));
+    ///
+    /// queue.watch_threshold(2, {
+    ///     let crossings = crossings.clone();
+    ///     move |crossing, _count| crossings.lock().unwrap().push(crossing)
+    /// });
+    ///
+    /// for i in 0..3 {
+    ///     queue.push_back(i);
+    /// }
+    /// queue.pop_front();
+    ///
+    /// let crossings = crossings.lock().unwrap();
+    /// assert_eq!(*crossings, [ThresholdCrossing::Above, ThresholdCrossing::Below]);
+    /// ```
+    pub fn watch_threshold<F>(&mut self, threshold: usize, callback: F) -> ListenerId
+    where
+        F: FnMut(ThresholdCrossing, usize) + Send + Sync + 'static,
+    {
+        let id = ListenerId(self.next_listener_id);
+        self.next_listener_id += 1;
+        self.thresholds.push(ThresholdWatch {
+            id,
+            threshold,
+            above: self.len() > threshold,
+            callback: Box::new(callback),
+        });
+        id
+    }
 
-        if !self.stack_2.is_empty() {
-            return self.len();
-        }
+    /// Registers a listener that is invoked with every element rejected by
+    /// [`insert_at`](Self::insert_at) for arriving behind the watermark, as well as
+    /// its timestamp.
+    pub fn on_late<F>(&mut self, listener: F) -> ListenerId
+    where
+        F: FnMut(Instant, &T) + Send + Sync + 'static,
+    {
+        let id = ListenerId(self.next_listener_id);
+        self.next_listener_id += 1;
+        self.late_listeners.push((id, Box::new(listener)));
+        id
+    }
 
-        while let Some((instant, _element)) = self.stack_1.first() {
-            if (now - *instant) < self.ttl {
-                break;
-            }
+    /// Registers a hook that is invoked with every element before it is pushed, e.g. to
+    /// normalize values or to sample the input.
+    ///
+    /// The hook returns the element to store, or `None` to drop it. Any number of hooks
+    /// can be registered; they are chained in the order of registration, and the first
+    /// hook returning `None` stops the chain. Hooks apply to [`push_back`](Self::push_back),
+    /// [`try_push_back`](Self::try_push_back), [`refresh_and_push_back`](Self::refresh_and_push_back),
+    /// [`insert_at`](Self::insert_at) and [`extend`](Extend::extend); dropped elements
+    /// are neither stored nor reported to any listener.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use ttl_queue::TtlQueue;
+    /// let mut queue = TtlQueue::new(Duration::from_secs(60));
+    ///
+    /// let mut seen = 0;
+    /// queue.on_push(move |value: f64| {
+    ///     seen += 1;
+    ///     (seen % 10 == 1).then_some(value)
+    /// });
+    /// queue.on_push(|value| Some(value.clamp(0.0, 1.0)));
+    ///
+    /// for i in 0..20 {
+    ///     queue.push_back(i as f64);
+    /// }
+    ///
+    /// let values: Vec<_> = queue.iter().map(|(_, value)| *value).collect();
+    /// assert_eq!(values, [0.0, 1.0]);
+    /// ```
+    pub fn on_push<F>(&mut self, hook: F) -> ListenerId
+    where
+        F: FnMut(T) -> Option<T> + Send + Sync + 'static,
+    {
+        let id = ListenerId(self.next_listener_id);
+        self.next_listener_id += 1;
+        self.push_hooks.push((id, Box::new(hook)));
+        id
+    }
 
-            let _result = self.stack_1.pop();
-            debug_assert!(_result.is_some());
-        }
+    /// Removes a listener registered via [`on_expired`](Self::on_expired),
+    /// [`on_late`](Self::on_late), [`on_push`](Self::on_push) or
+    /// [`watch_threshold`](Self::watch_threshold).
+    ///
+    /// Returns `true` if the listener was registered.
+    pub fn remove_listener(&mut self, id: ListenerId) -> bool {
+        let count = self.listener_count();
+        self.listeners.retain(|(listener_id, _)| *listener_id != id);
+        self.late_listeners
+            .retain(|(listener_id, _)| *listener_id != id);
+        self.push_hooks.retain(|(hook_id, _)| *hook_id != id);
+        self.thresholds.retain(|watch| watch.id != id);
+        self.listener_count() != count
+    }
 
-        debug_assert_eq!(self.stack_1.len(), self.len());
-        self.stack_1.len()
+    /// Gets the number of registered listeners, hooks and threshold watches.
+    fn listener_count(&self) -> usize {
+        self.listeners.len()
+            + self.late_listeners.len()
+            + self.push_hooks.len()
+            + self.thresholds.len()
     }
 
-    /// Refreshes the queue and returns the number of currently contained elements.
-    #[cfg(feature = "vecdeque")]
-    pub fn refresh(&mut self) -> usize {
-        let now = Instant::now();
+    /// Passes an element through the registered push hooks and returns it unless
+    /// a hook dropped it.
+    fn apply_push_hooks(&mut self, element: T) -> Option<T> {
+        self.push_hooks
+            .iter_mut()
+            .try_fold(element, |element, (_id, hook)| hook(element))
+    }
 
-        while let Some((instant, _element)) = self.queue.front() {
-            if (now - *instant) < self.ttl {
-                break;
+    /// Invokes the threshold watches whose threshold was crossed.
+    fn update_thresholds(&mut self, count: usize) {
+        for watch in &mut self.thresholds {
+            let above = count > watch.threshold;
+            if above == watch.above {
+                continue;
             }
 
-            let _result = self.queue.pop_front();
-            debug_assert!(_result.is_some());
+            watch.above = above;
+            let crossing = if above {
+                ThresholdCrossing::Above
+            } else {
+                ThresholdCrossing::Below
+            };
+            (watch.callback)(crossing, count);
         }
+    }
 
-        self.queue.len()
+    /// Notifies all registered listeners about an expired element.
+    fn notify_expired(&mut self, (instant, element): &(Instant, T)) {
+        for (_id, listener) in &mut self.listeners {
+            listener(*instant, element);
+        }
     }
 
-    /// Returns an iterator to the data.
-    pub fn iter(&self) -> impl Iterator<Item = &(Instant, T)> {
-        #[cfg(feature = "doublestack")]
-        {
-            return DoubleStackIterator::new(&self);
+    /// Notifies the listeners about an expired element and retains it as a tombstone.
+    fn retire(&mut self, entry: (Instant, T)) {
+        self.notify_expired(&entry);
+        if self.tombstone_capacity == 0 {
+            return;
         }
-        #[cfg(feature = "vecdeque")]
-        {
-            self.queue.iter()
+        if self.tombstones.len() == self.tombstone_capacity {
+            self.tombstones.pop_front();
         }
+        self.tombstones.push_back(entry);
     }
 
-    /// Returns the average duration between two events.
-    pub fn avg_delta(&self) -> Duration {
-        if self.len() <= 1 {
-            return Duration::ZERO;
+    /// Pushes an element to the end of the queue.
+    ///
+    /// If the queue is at its [maximum length](Self::with_max_len), the
+    /// [overflow policy](Self::set_overflow_policy) applies.
+    pub fn push_back(&mut self, element: T) {
+        self.push_back_stamped(self.now(), element);
+    }
+
+    /// Pushes an element stamped elsewhere, e.g. by a [`TtlSender`], like
+    /// [`push_back`](Self::push_back) and returns `true` unless it was dropped by a
+    /// hook or due to the maximum length.
+    ///
+    /// The timestamp is clamped to the time at which expiry was [paused](Self::pause)
+    /// and to the newest element, so that the ordering of the queue is retained.
+    pub(crate) fn push_back_stamped(&mut self, instant: Instant, element: T) -> bool {
+        let Some(element) = self.apply_push_hooks(element) else {
+            return false;
+        };
+        self.auto_refresh(RefreshTrigger::Push);
+        if !self.make_room() {
+            return false;
+        }
+
+        self.push_back_entry(self.clamp_stamp(instant), element);
+        true
+    }
+
+    /// Clamps the timestamp of a pushed element to the time at which expiry was
+    /// [paused](Self::pause) and to the newest element, so that the ordering of the
+    /// queue is retained.
+    fn clamp_stamp(&self, instant: Instant) -> Instant {
+        let instant = self
+            .paused_at
+            .map_or(instant, |paused_at| instant.min(paused_at));
+        match self.back_entry() {
+            Some((newest, _)) if *newest > instant => *newest,
+            _ => instant,
+        }
+    }
+
+    /// Pushes an element to the end of the queue unless it is at its
+    /// [maximum length](Self::with_max_len) and returns the number of elements
+    /// afterwards.
+    ///
+    /// Unlike [`push_back`](Self::push_back), this never evicts unexpired elements
+    /// regardless of the [overflow policy](Self::set_overflow_policy). If the queue is
+    /// still full after removing expired elements, the element is returned in the error
+    /// along with the number of elements, so that producers can shed load as they see fit.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use ttl_queue::TtlQueue;
+    /// let mut queue = TtlQueue::with_max_len(Duration::from_secs(60), 2);
+    /// assert_eq!(queue.try_push_back("a"), Ok(1));
+    /// assert_eq!(queue.try_push_back("b"), Ok(2));
+    ///
+    /// let error = queue.try_push_back("c").unwrap_err();
+    /// assert_eq!(error.live_count(), 2);
+    /// assert_eq!(error.into_inner(), "c");
+    /// ```
+    pub fn try_push_back(&mut self, element: T) -> Result<usize, PushError<T>> {
+        let Some(element) = self.apply_push_hooks(element) else {
+            return Ok(self.len());
+        };
+        self.auto_refresh(RefreshTrigger::Push);
+        if let Some(max_len) = self.max_len {
+            if self.len() >= max_len && self.refresh() >= max_len {
+                return Err(PushError {
+                    element,
+                    len: self.len(),
+                });
+            }
+        }
+        self.push_back_entry(self.clamp_stamp(self.now()), element);
+        Ok(self.len())
+    }
+
+    /// Pushes an element to the end of the queue.
+    fn push_back_entry(&mut self, instant: Instant, element: T) {
+        self.add_cost(&element);
+        self.last_push = self.last_push.max(Some(instant));
+        if self.jitter_offsets.is_some() {
+            let offset = self.next_jitter_offset();
+            if let Some(offsets) = &mut self.jitter_offsets {
+                offsets.push_back(offset);
+            }
+        }
+        let entry = (instant, element);
+        #[cfg(feature = "doublestack")]
+        {
+            self.stack_1.push(entry);
+        }
+        #[cfg(feature = "vecdeque")]
+        {
+            self.queue.push_back(entry)
+        }
+
+        self.enforce_budget();
+        self.update_thresholds(self.len());
+        self.validate_if_enabled();
+    }
+
+    /// Inserts an element with a past timestamp, e.g. an event that arrived out of order,
+    /// at its position in the queue.
+    ///
+    /// Elements are accepted up to the [allowed lateness](Self::set_allowed_lateness)
+    /// behind the newest element, the watermark. Older elements are reported to the
+    /// [late listeners](Self::on_late) and returned as an error, so that the ordering
+    /// of the queue is retained.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use ttl_queue::{Instant, TtlQueue};
+    /// # #[cfg(feature = "mock_instant")]
+    /// # mock_instant::global::MockClock::set_time(Duration::from_secs(3600));
+    /// let mut queue = TtlQueue::new(Duration::from_secs(60));
+    /// queue.set_allowed_lateness(Duration::from_secs(5));
+    ///
+    /// let now = Instant::now();
+    /// assert!(queue.insert_at(now, "on time").is_ok());
+    /// assert!(queue.insert_at(now - Duration::from_secs(2), "late").is_ok());
+    /// assert!(queue.insert_at(now - Duration::from_secs(10), "too late").is_err());
+    ///
+    /// let values: Vec<_> = queue.iter().map(|(_, value)| *value).collect();
+    /// assert_eq!(values, ["late", "on time"]);
+    /// ```
+    pub fn insert_at(&mut self, instant: Instant, element: T) -> Result<(), (Instant, T)> {
+        self.insert_entry(instant, element).map(|_| ())
+    }
+
+    /// Passes an element through the push hooks and inserts it at its position as by
+    /// [`insert_at`](Self::insert_at), subject to the watermark and the maximum length.
+    ///
+    /// Returns `Ok(true)` if the element was stored and `Ok(false)` if a hook dropped it.
+    fn insert_entry(&mut self, instant: Instant, element: T) -> Result<bool, (Instant, T)> {
+        let Some(element) = self.apply_push_hooks(element) else {
+            return Ok(false);
+        };
+        if self
+            .watermark()
+            .is_some_and(|watermark| instant < watermark)
+        {
+            for (_id, listener) in &mut self.late_listeners {
+                listener(instant, &element);
+            }
+            return Err((instant, element));
+        }
+        if !self.make_room() {
+            return Err((instant, element));
+        }
+
+        if self
+            .back_entry()
+            .map_or(true, |(newest, _)| *newest <= instant)
+        {
+            self.push_back_entry(instant, element);
+            return Ok(true);
+        }
+
+        let index = self
+            .binary_search_by(|(entry, _)| {
+                if *entry <= instant {
+                    Ordering::Less
+                } else {
+                    Ordering::Greater
+                }
+            })
+            .unwrap_or_else(|index| index);
+
+        self.last_push = self.last_push.max(Some(instant));
+        self.insert(index, (instant, element));
+        self.enforce_budget();
+        self.update_thresholds(self.len());
+        Ok(true)
+    }
+
+    /// Gets the oldest timestamp currently accepted by [`insert_at`](Self::insert_at),
+    /// i.e. the timestamp of the newest element minus the allowed lateness.
+    ///
+    /// Returns `None` if the queue is empty.
+    pub fn watermark(&self) -> Option<Instant> {
+        let (newest, _) = self.back_entry()?;
+        Some(newest.checked_sub(self.allowed_lateness).unwrap_or(*newest))
+    }
+
+    /// Gets how far behind the newest element [`insert_at`](Self::insert_at) accepts elements.
+    pub fn allowed_lateness(&self) -> Duration {
+        self.allowed_lateness
+    }
+
+    /// Sets how far behind the newest element [`insert_at`](Self::insert_at) accepts elements.
+    ///
+    /// Defaults to [`Duration::ZERO`], i.e. no element older than the newest is accepted.
+    pub fn set_allowed_lateness(&mut self, lateness: Duration) {
+        self.allowed_lateness = lateness;
+    }
+
+    /// Pushes an element to the end of the queue and returns the number of items
+    /// currently in the queue. This operation is O(N) at worst.
+    pub fn refresh_and_push_back(&mut self, element: T) -> usize {
+        let count = self.refresh();
+        if self.push_back_stamped(self.now(), element) {
+            self.len()
+        } else {
+            count
+        }
+    }
+
+    /// Gets the element from the front of the queue if it exists, as well as the
+    /// time instant at which it was added.
+    pub fn pop_front(&mut self) -> Option<(Instant, T)> {
+        self.auto_refresh(RefreshTrigger::Read);
+        let entry = self.pop_front_entry();
+        self.update_thresholds(self.len());
+        entry
+    }
+
+    /// Removes the element from the front of the queue and returns it, as well as the
+    /// time instant at which it was added, only if the predicate returns `true` for it.
+    ///
+    /// Unlike a [`peek_front`](Self::peek_front) followed by a [`pop_front`](Self::pop_front),
+    /// the decision and the removal refer to the same element.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use ttl_queue::TtlQueue;
+    /// let mut jobs = TtlQueue::new(Duration::from_secs(60));
+    /// jobs.push_back(("resize", 2));
+    /// jobs.push_back(("upload", 5));
+    ///
+    /// let mut budget = 4;
+    /// while let Some((_, (_, cost))) = jobs.pop_front_if(|_, (_, cost)| *cost <= budget) {
+    ///     budget -= cost;
+    /// }
+    ///
+    /// assert_eq!(budget, 2);
+    /// assert_eq!(jobs.len(), 1);
+    /// ```
+    pub fn pop_front_if<F>(&mut self, predicate: F) -> Option<(Instant, T)>
+    where
+        F: FnOnce(Instant, &T) -> bool,
+    {
+        self.auto_refresh(RefreshTrigger::Read);
+        let (instant, element) = self.front_entry()?;
+        if !predicate(*instant, element) {
+            return None;
+        }
+
+        let entry = self.pop_front_entry();
+        self.update_thresholds(self.len());
+        entry
+    }
+
+    /// Removes the element from the front of the queue without notifying threshold watches.
+    fn pop_front_entry(&mut self) -> Option<(Instant, T)> {
+        #[cfg(feature = "doublestack")]
+        let entry = {
+            self.ensure_stack_full(false);
+            self.stack_2.pop()
+        };
+        #[cfg(feature = "vecdeque")]
+        let entry = self.queue.pop_front();
+
+        if let Some((_, element)) = &entry {
+            self.sub_cost(element);
+            self.forget_front(1);
+        }
+        self.validate_if_enabled();
+        entry
+    }
+
+    /// Accounts for the removal of `count` elements from the front of the queue in the
+    /// cached expired prefix and the [frozen](Self::freeze) segments.
+    fn forget_front(&mut self, count: usize) {
+        let hint = self.expired_hint.get_mut();
+        *hint = hint.saturating_sub(count);
+        self.frozen.consume_front(count);
+        if let Some(offsets) = &mut self.jitter_offsets {
+            offsets.drain(..count.min(offsets.len()));
+        }
+    }
+
+    /// Gets the oldest element of the queue, which may have expired.
+    ///
+    /// Unlike [`peek_front`](Self::peek_front), this neither returns the timestamp nor
+    /// triggers an implicit [refresh](Self::set_refresh_policy), so it only requires
+    /// shared access.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use ttl_queue::TtlQueue;
+    /// let mut queue = TtlQueue::new(Duration::from_secs(60));
+    /// assert_eq!(queue.front(), None);
+    ///
+    /// queue.push_back("a");
+    /// queue.push_back("b");
+    /// assert_eq!(queue.front(), Some(&"a"));
+    /// assert_eq!(queue.back(), Some(&"b"));
+    /// ```
+    pub fn front(&self) -> Option<&T> {
+        self.front_entry().map(|(_, element)| element)
+    }
+
+    /// Gets the newest element of the queue.
+    ///
+    /// See [`front`](Self::front).
+    pub fn back(&self) -> Option<&T> {
+        self.back_entry().map(|(_, element)| element)
+    }
+
+    /// Similar to [`pop_front`](Self::pop_front) but without removing the element.
+    pub fn peek_front(&mut self) -> Option<&(Instant, T)> {
+        self.auto_refresh(RefreshTrigger::Read);
+        #[cfg(feature = "doublestack")]
+        {
+            self.ensure_stack_full(false);
+            self.stack_2.last()
+        }
+        #[cfg(feature = "vecdeque")]
+        {
+            self.queue.front()
+        }
+    }
+
+    /// Gets the oldest entry without requiring mutable access.
+    fn front_entry(&self) -> Option<&(Instant, T)> {
+        #[cfg(feature = "doublestack")]
+        {
+            self.stack_2.last().or_else(|| self.stack_1.first())
+        }
+        #[cfg(feature = "vecdeque")]
+        {
+            self.queue.front()
+        }
+    }
+
+    /// Gets the newest entry without requiring mutable access.
+    fn back_entry(&self) -> Option<&(Instant, T)> {
+        #[cfg(feature = "doublestack")]
+        {
+            self.stack_1.last().or_else(|| self.stack_2.first())
+        }
+        #[cfg(feature = "vecdeque")]
+        {
+            self.queue.back()
+        }
+    }
+
+    /// Gets the entry at the specified position, counting from the front of the queue.
+    fn get(&self, index: usize) -> Option<&(Instant, T)> {
+        #[cfg(feature = "doublestack")]
+        {
+            let front_len = self.stack_2.len();
+            if index < front_len {
+                self.stack_2.get(front_len - 1 - index)
+            } else {
+                self.stack_1.get(index - front_len)
+            }
+        }
+        #[cfg(feature = "vecdeque")]
+        {
+            self.queue.get(index)
+        }
+    }
+
+    /// Removes the entry at the specified position, counting from the front of the queue.
+    fn remove(&mut self, index: usize) -> Option<(Instant, T)> {
+        #[cfg(feature = "doublestack")]
+        let entry = {
+            let front_len = self.stack_2.len();
+            if index < front_len {
+                Some(self.stack_2.remove(front_len - 1 - index))
+            } else if index - front_len < self.stack_1.len() {
+                Some(self.stack_1.remove(index - front_len))
+            } else {
+                None
+            }
+        };
+        #[cfg(feature = "vecdeque")]
+        let entry = self.queue.remove(index);
+
+        if let Some((_, element)) = &entry {
+            self.sub_cost(element);
+            let hint = self.expired_hint.get_mut();
+            if index < *hint {
+                *hint -= 1;
+            }
+            self.frozen.modified(index);
+            if let Some(offsets) = &mut self.jitter_offsets {
+                offsets.remove(index);
+            }
+        }
+        self.validate_if_enabled();
+        entry
+    }
+
+    /// Gets the entry at the specified position mutably, counting from the front of the queue.
+    fn get_mut(&mut self, index: usize) -> Option<&mut (Instant, T)> {
+        self.frozen.modified(index);
+        #[cfg(feature = "doublestack")]
+        {
+            let front_len = self.stack_2.len();
+            if index < front_len {
+                self.stack_2.get_mut(front_len - 1 - index)
+            } else {
+                self.stack_1.get_mut(index - front_len)
+            }
+        }
+        #[cfg(feature = "vecdeque")]
+        {
+            self.queue.get_mut(index)
+        }
+    }
+
+    /// Inserts an entry at the specified position, counting from the front of the queue.
+    ///
+    /// The caller is responsible for retaining the ordering of the timestamps.
+    fn insert(&mut self, index: usize, entry: (Instant, T)) {
+        self.add_cost(&entry.1);
+        let hint = self.expired_hint.get_mut();
+        *hint = (*hint).min(index);
+        self.frozen.modified(index);
+        if self.jitter_offsets.is_some() {
+            let offset = self.next_jitter_offset();
+            if let Some(offsets) = &mut self.jitter_offsets {
+                offsets.insert(index, offset);
+            }
+        }
+        #[cfg(feature = "doublestack")]
+        {
+            let front_len = self.stack_2.len();
+            if index <= front_len {
+                self.stack_2.insert(front_len - index, entry);
+            } else {
+                self.stack_1.insert(index - front_len, entry);
+            }
+        }
+        #[cfg(feature = "vecdeque")]
+        {
+            self.queue.insert(index, entry);
+        }
+        self.validate_if_enabled();
+    }
+
+    #[cfg(feature = "doublestack")]
+    fn ensure_stack_full(&mut self, force: bool) {
+        if self.stack_2.is_empty() || force {
+            while let Some(item) = self.stack_1.pop() {
+                self.stack_2.push(item);
+            }
+        }
+    }
+
+    /// Gets the number elements currently in the queue, including potentially expired elements.
+    ///
+    /// This operation is O(1). In order to obtain an accurate count in O(N) (worst-case),
+    /// use [`refresh`](Self::refresh) instead.
+    pub fn len(&self) -> usize {
+        #[cfg(feature = "doublestack")]
+        {
+            self.stack_1.len() + self.stack_2.len()
+        }
+        #[cfg(feature = "vecdeque")]
+        {
+            self.queue.len()
+        }
+    }
+
+    /// Returns `true` if the queue is definitely empty or `false` if the queue is
+    /// possibly empty.
+    ///
+    /// This operation is O(1). In order to obtain an accurate count in O(N) (worst-case),
+    /// use [`refresh`](Self::refresh) instead.
+    pub fn is_empty(&self) -> bool {
+        #[cfg(feature = "doublestack")]
+        {
+            self.stack_1.is_empty() && self.stack_2.is_empty()
+        }
+        #[cfg(feature = "vecdeque")]
+        {
+            self.queue.is_empty()
+        }
+    }
+
+    /// Refreshes the queue and returns the number of currently contained elements.
+    ///
+    /// If a [grace period](Self::set_grace_period) is configured, elements older than
+    /// the TTL are kept as stale elements until the grace period has passed as well;
+    /// stale elements are not included in the returned count.
+    pub fn refresh(&mut self) -> usize {
+        self.refresh_at(self.now())
+    }
+
+    /// Refreshes the queue as of the specified time and returns the number of
+    /// currently contained elements.
+    pub(crate) fn refresh_at(&mut self, now: Instant) -> usize {
+        let min_hard_ttl = self.ttl.saturating_add(self.grace);
+        self.last_refresh = Some(now);
+
+        // Without jitter, elements expire in order and the expired prefix can be located
+        // by a binary search and removed at once.
+        if self.expires_in_order() {
+            let expired = self.expired_prefix_len(now, min_hard_ttl);
+            self.remove_front(expired);
+
+            let count = self.len() - self.stale_len_at(now);
+            self.update_thresholds(count);
+            return count;
+        }
+
+        // Elements younger than the minimum TTL cannot have expired yet. With jitter,
+        // elements in between may expire out of order and are removed individually.
+        // An expiry policy may expire any element, so all elements are checked.
+        let mut index = 0;
+        while let Some(entry) = self.get(index) {
+            let age = now.saturating_duration_since(entry.0);
+            if age < min_hard_ttl && self.expiry_policy.is_none() {
+                break;
+            }
+
+            if !self.is_hard_expired_at(index, now) {
+                index += 1;
+                continue;
+            }
+
+            let entry = if index == 0 {
+                self.pop_front_entry()
+            } else {
+                self.remove(index)
+            };
+
+            debug_assert!(entry.is_some());
+            if let Some(entry) = entry {
+                self.retire(entry);
+            }
+        }
+
+        let count = self.len() - self.stale_len_at(now);
+        self.update_thresholds(count);
+        count
+    }
+
+    /// Removes all elements at least `ttl` old, regardless of the configured TTL, and
+    /// returns the number of remaining elements.
+    ///
+    /// This allows a one-off purge with a different horizon, e.g. dropping everything
+    /// older than one second from a queue that normally keeps ten seconds, without
+    /// reconfiguring the queue. The [grace period](Self::set_grace_period),
+    /// [TTL jitter](Self::set_ttl_jitter) and [expiry policy](Self::set_expiry_policy)
+    /// do not apply. Removed elements are reported to the expiry listeners.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use ttl_queue::{TickClock, TtlQueue};
+    /// let clock = TickClock::new();
+    /// let mut queue = TtlQueue::new(TickClock::ttl(10));
+    /// queue.set_clock(clock.clone());
+    /// for i in 0..5 {
+    ///     queue.push_back(i);
+    ///     clock.tick();
+    /// }
+    ///
+    /// assert_eq!(queue.refresh_with_ttl(TickClock::ttl(3)), 2);
+    /// assert_eq!(queue.front(), Some(&3));
+    /// ```
+    pub fn refresh_with_ttl(&mut self, ttl: Duration) -> usize {
+        let now = self.now();
+        let expired = self.expired_prefix_len(now, ttl);
+        self.remove_front(expired);
+
+        let count = self.len();
+        self.update_thresholds(count);
+        count
+    }
+
+    /// Removes the expired elements, like [`refresh`](Self::refresh), but moves them into
+    /// the `downstream` queue with their original timestamps instead of dropping them,
+    /// and returns the number of moved elements.
+    ///
+    /// Chaining a short-TTL queue into a longer-TTL queue builds a multi-resolution
+    /// history, e.g. the raw events of the last minute in one queue and the events of
+    /// the last hour in another. Moved elements are not reported to the expiry
+    /// listeners of this queue.
+    ///
+    /// Every element is inserted into `downstream` as by [`insert_at`](Self::insert_at),
+    /// so its [push hooks](Self::on_push) and [maximum length](Self::with_max_len)
+    /// apply, and elements older than its [watermark](Self::watermark) are rejected.
+    /// Rejected elements are reported to the expiry listeners of this queue instead, as
+    /// if they had expired without a downstream queue, and are not counted as moved.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use ttl_queue::{TickClock, TtlQueue};
+    /// let clock = TickClock::new();
+    /// let mut minute = TtlQueue::new(TickClock::ttl(60));
+    /// let mut hour = TtlQueue::new(TickClock::ttl(3600));
+    /// minute.set_clock(clock.clone());
+    /// hour.set_clock(clock.clone());
+    ///
+    /// minute.push_back("request");
+    /// clock.advance(90);
+    /// assert_eq!(minute.chain_into(&mut hour), 1);
+    ///
+    /// assert!(minute.is_empty());
+    /// assert_eq!(hour.refresh(), 1);
+    /// ```
+    pub fn chain_into(&mut self, downstream: &mut TtlQueue<T>) -> usize {
+        let now = self.now();
+        let min_hard_ttl = self.ttl.saturating_add(self.grace);
+        self.last_refresh = Some(now);
+
+        let mut moved = 0;
+        let mut index = 0;
+        while let Some(entry) = self.get(index) {
+            let age = now.saturating_duration_since(entry.0);
+            if age < min_hard_ttl && self.expiry_policy.is_none() {
+                break;
+            }
+
+            if !self.is_hard_expired_at(index, now) {
+                index += 1;
+                continue;
+            }
+
+            let entry = if index == 0 {
+                self.pop_front_entry()
+            } else {
+                self.remove(index)
+            };
+
+            if let Some((instant, element)) = entry {
+                match downstream.insert_entry(instant, element) {
+                    Ok(true) => moved += 1,
+                    Ok(false) => {}
+                    Err(entry) => self.retire(entry),
+                }
+            }
+        }
+
+        self.update_thresholds(self.len() - self.stale_len_at(now));
+        moved
+    }
+
+    /// Returns the number of leading elements that are at least `age` old at `now`.
+    fn expired_prefix_len(&self, now: Instant, age: Duration) -> usize {
+        self.binary_search_by(|(instant, _)| {
+            if now.saturating_duration_since(*instant) >= age {
+                Ordering::Less
+            } else {
+                Ordering::Greater
+            }
+        })
+        .unwrap_or_else(|index| index)
+    }
+
+    /// Removes the specified number of elements from the front of the queue.
+    ///
+    /// Unless expiry listeners, a memory budget or the [tombstones](Self::recently_expired)
+    /// need to observe every element, the elements are dropped in bulk.
+    fn remove_front(&mut self, count: usize) {
+        if !self.listeners.is_empty() || self.budget.is_some() || self.tombstone_capacity > 0 {
+            for _ in 0..count {
+                if let Some(entry) = self.pop_front_entry() {
+                    self.retire(entry);
+                }
+            }
+            return;
+        }
+
+        #[cfg(feature = "doublestack")]
+        {
+            self.ensure_stack_full(false);
+            let from_front = count.min(self.stack_2.len());
+            self.stack_2.truncate(self.stack_2.len() - from_front);
+            self.stack_1.drain(..count - from_front);
+        }
+        #[cfg(feature = "vecdeque")]
+        {
+            self.queue.drain(..count);
+        }
+        self.forget_front(count);
+        self.validate_if_enabled();
+    }
+
+    /// Verifies the internal invariants of the queue and panics if any is violated.
+    ///
+    /// This checks that the timestamps are monotone from front to back, including
+    /// across both stacks of the `doublestack` storage, and that cached bookkeeping such
+    /// as the [total cost](Self::total_bytes) agrees with the elements. It is O(N) and
+    /// meant to catch misuse early, e.g. in tests. With the `validate` crate feature, it
+    /// runs automatically after every mutating operation.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if an invariant is violated.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use ttl_queue::TtlQueue;
+    /// let mut queue = TtlQueue::new(Duration::from_secs(60));
+    /// queue.push_back(1);
+    /// queue.push_back(2);
+    /// queue.debug_validate();
+    /// ```
+    pub fn debug_validate(&self) {
+        let mut previous: Option<&Instant> = None;
+        for (index, (instant, _)) in self.iter().enumerate() {
+            if let Some(previous) = previous {
+                assert!(
+                    previous <= instant,
+                    "The timestamp of element {index} is earlier than that of its predecessor"
+                );
+            }
+            previous = Some(instant);
+        }
+
+        #[cfg(feature = "doublestack")]
+        assert!(
+            self.stack_2.windows(2).all(|pair| pair[0].0 >= pair[1].0)
+                && self.stack_1.windows(2).all(|pair| pair[0].0 <= pair[1].0),
+            "The stacks must be ordered in opposite directions"
+        );
+
+        assert!(
+            self.expired_hint.load(AtomicOrdering::Relaxed) <= self.len(),
+            "The number of known expired elements must not exceed the length"
+        );
+        assert!(
+            self.frozen.len() <= self.len(),
+            "The frozen segments must not cover more elements than the queue holds"
+        );
+        if let Some(offsets) = &self.jitter_offsets {
+            assert_eq!(
+                offsets.len(),
+                self.len(),
+                "Every element must have a jitter offset"
+            );
+        }
+
+        if let Some(budget) = self.budget.as_ref().filter(|budget| !budget.stale) {
+            assert_eq!(
+                budget.total_bytes,
+                self.compute_cost(budget),
+                "The total cost must match the cost of the elements"
+            );
+        }
+    }
+
+    /// Runs [`debug_validate`](Self::debug_validate) if the `validate` feature is enabled.
+    #[inline]
+    fn validate_if_enabled(&self) {
+        #[cfg(feature = "validate")]
+        self.debug_validate();
+    }
+
+    /// Gets the time-to-live of the elements.
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Sets the time-to-live of the elements. This affects all elements, including
+    /// those already in the queue, starting with the next [`refresh`](Self::refresh).
+    pub fn set_ttl(&mut self, ttl: Duration) {
+        self.ttl = ttl;
+        *self.expired_hint.get_mut() = 0;
+    }
+
+    /// Gets the policy determining when expired elements are removed implicitly.
+    pub fn refresh_policy(&self) -> RefreshPolicy {
+        self.refresh_policy
+    }
+
+    /// Sets the clock used to timestamp new elements and to determine expiry.
+    ///
+    /// Defaults to the [`SystemClock`]. Elements already in the queue keep their
+    /// timestamps, so the new clock should be compatible with the previous one.
+    pub fn set_clock(&mut self, clock: impl Clock + 'static) {
+        self.set_shared_clock(Arc::new(clock));
+    }
+
+    /// Sets a clock that may be shared with other queues.
+    pub(crate) fn set_shared_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+        self.simulation = None;
+        *self.expired_hint.get_mut() = 0;
+        #[cfg(feature = "chrono")]
+        {
+            self.anchor = (self.now(), chrono::Utc::now());
+        }
+    }
+
+    /// Creates an empty [`TtlQueue`] in virtual time, which only passes when the queue
+    /// is [advanced](Self::advance), e.g. to test hours of TTL behavior in microseconds.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use ttl_queue::TtlQueue;
+    /// let mut queue = TtlQueue::simulated(Duration::from_secs(3600));
+    /// queue.push_back("session");
+    ///
+    /// queue.advance(Duration::from_secs(3599));
+    /// assert_eq!(queue.refresh(), 1);
+    ///
+    /// queue.advance(Duration::from_secs(1));
+    /// assert_eq!(queue.refresh(), 0);
+    /// ```
+    pub fn simulated(ttl: Duration) -> Self {
+        let clock = TickClock::new();
+        let mut queue = Self::new(ttl);
+        queue.set_clock(clock.clone());
+        queue.simulation = Some(clock);
+        queue
+    }
+
+    /// Returns `true` if the queue runs in virtual time, i.e. was created by
+    /// [`simulated`](Self::simulated) and no other clock was set since.
+    pub fn is_simulated(&self) -> bool {
+        self.simulation.is_some()
+    }
+
+    /// Advances the virtual time of a [simulated](Self::simulated) queue.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if the queue is not simulated.
+    pub fn advance(&mut self, duration: Duration) {
+        let clock = self
+            .simulation
+            .as_ref()
+            .expect("Only simulated queues can be advanced");
+        clock.advance(u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX));
+    }
+
+    /// Gets the current time according to the configured clock, or the time at
+    /// which expiry was [paused](Self::pause).
+    pub(crate) fn now(&self) -> Instant {
+        self.paused_at.unwrap_or_else(|| self.clock.now())
+    }
+
+    /// Freezes the expiry clock of this queue, e.g. while a game or simulation is suspended.
+    ///
+    /// While paused, elements do not age: refreshes use the time of the pause, and
+    /// pushed elements are stamped with it. Calling `pause` on a paused queue has no effect.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use ttl_queue::{TickClock, TtlQueue};
+    /// let clock = TickClock::new();
+    /// let mut queue = TtlQueue::new(TickClock::ttl(10));
+    /// queue.set_clock(clock.clone());
+    /// queue.push_back("spawned");
+    ///
+    /// clock.advance(5);
+    /// queue.pause();
+    /// clock.advance(100);
+    /// assert_eq!(queue.refresh(), 1);
+    ///
+    /// queue.resume();
+    /// clock.advance(4);
+    /// assert_eq!(queue.refresh(), 1);
+    /// clock.advance(1);
+    /// assert_eq!(queue.refresh(), 0);
+    /// ```
+    pub fn pause(&mut self) {
+        if self.paused_at.is_none() {
+            self.paused_at = Some(self.clock.now());
+        }
+    }
+
+    /// Resumes the expiry clock after a [`pause`](Self::pause).
+    ///
+    /// The timestamps of all elements are moved forward by the duration of the pause,
+    /// so that their remaining time-to-live is preserved. With a [TTL jitter](Self::set_ttl_jitter),
+    /// each element keeps its jitter as well. Calling `resume` on a queue that is not paused
+    /// has no effect.
+    pub fn resume(&mut self) {
+        let Some(paused_at) = self.paused_at.take() else {
+            return;
+        };
+
+        let paused_for = self.clock.now().saturating_duration_since(paused_at);
+        if paused_for.is_zero() {
+            return;
+        }
+
+        for index in 0..self.len() {
+            if let Some((instant, _)) = self.get_mut(index) {
+                *instant += paused_for;
+            }
+        }
+
+        for instant in [&mut self.last_refresh, &mut self.last_push]
+            .into_iter()
+            .flatten()
+        {
+            *instant += paused_for;
+        }
+        *self.expired_hint.get_mut() = 0;
+        self.validate_if_enabled();
+    }
+
+    /// Returns `true` if expiry is currently [paused](Self::pause).
+    pub fn is_paused(&self) -> bool {
+        self.paused_at.is_some()
+    }
+
+    /// Sets the policy determining when expired elements are removed implicitly.
+    ///
+    /// Defaults to [`RefreshPolicy::Manual`].
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use ttl_queue::{RefreshPolicy, TtlQueue};
+    /// let mut queue = TtlQueue::new(Duration::ZERO);
+    /// queue.set_refresh_policy(RefreshPolicy::OnPush);
+    ///
+    /// queue.push_back(1);
+    /// queue.push_back(2);
+    ///
+    /// // The first element expired immediately and was removed by the second push.
+    /// assert_eq!(queue.len(), 1);
+    /// ```
+    pub fn set_refresh_policy(&mut self, policy: RefreshPolicy) {
+        self.refresh_policy = policy;
+    }
+
+    /// Refreshes the queue if required by the configured [`RefreshPolicy`].
+    fn auto_refresh(&mut self, trigger: RefreshTrigger) {
+        let refresh = match self.refresh_policy {
+            RefreshPolicy::Manual => false,
+            RefreshPolicy::OnPush => trigger == RefreshTrigger::Push,
+            RefreshPolicy::OnRead => trigger == RefreshTrigger::Read,
+            RefreshPolicy::Every(interval) => self.last_refresh.map_or(true, |last| {
+                self.now().saturating_duration_since(last) >= interval
+            }),
+        };
+
+        if refresh {
+            self.refresh();
+        }
+    }
+
+    /// Sets the grace period during which elements older than the TTL are kept
+    /// as stale elements before being dropped by a [`refresh`](Self::refresh).
+    ///
+    /// Defaults to [`Duration::ZERO`], i.e. elements are dropped as soon as they expire.
+    pub fn set_grace_period(&mut self, grace: Duration) {
+        self.grace = grace;
+    }
+
+    /// Gets the grace period during which expired elements are kept as stale elements.
+    pub fn grace_period(&self) -> Duration {
+        self.grace
+    }
+
+    /// Sets the maximum random jitter that is added to the TTL of each element.
+    ///
+    /// Each element's effective TTL is extended by a pseudo-random duration in the
+    /// range `[0, jitter)`, which is drawn for every element individually, including
+    /// elements already in the queue. This spreads out the expiry of elements that were
+    /// pushed in bulk, even if they share a timestamp.
+    ///
+    /// Defaults to [`Duration::ZERO`], i.e. all elements share the same TTL.
+    pub fn set_ttl_jitter(&mut self, jitter: Duration) {
+        self.jitter = jitter;
+        *self.expired_hint.get_mut() = 0;
+        if jitter.is_zero() {
+            self.jitter_offsets = None;
+        } else if self.jitter_offsets.is_none() {
+            let offsets = (0..self.len()).map(|_| self.next_jitter_offset()).collect();
+            self.jitter_offsets = Some(offsets);
+        }
+    }
+
+    /// Gets the maximum random jitter that is added to the TTL of each element.
+    pub fn ttl_jitter(&self) -> Duration {
+        self.jitter
+    }
+
+    /// Gets the number of stale elements, i.e. elements older than the TTL that are
+    /// still within their [grace period](Self::set_grace_period).
+    pub fn stale_len(&self) -> usize {
+        self.stale_len_at(self.now())
+    }
+
+    /// Returns an iterator over the stale elements, i.e. elements older than the TTL
+    /// that are still within their [grace period](Self::set_grace_period).
+    pub fn iter_stale(&self) -> impl Iterator<Item = &(Instant, T)> {
+        let now = self.now();
+        self.iter()
+            .enumerate()
+            .take_while(move |(_, (instant, _))| {
+                now.saturating_duration_since(*instant) >= self.ttl
+            })
+            .filter(move |(index, (instant, _))| self.is_stale_at(*index, instant, now))
+            .map(|(_, entry)| entry)
+    }
+
+    fn stale_len_at(&self, now: Instant) -> usize {
+        self.iter()
+            .enumerate()
+            .take_while(|(_, (instant, _))| now.saturating_duration_since(*instant) >= self.ttl)
+            .filter(|(index, (instant, _))| self.is_stale_at(*index, instant, now))
+            .count()
+    }
+
+    /// Draws the jitter offset of a new element by hashing its sequence number.
+    fn next_jitter_offset(&mut self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.jitter_sequence.hash(&mut hasher);
+        self.jitter_sequence = self.jitter_sequence.wrapping_add(1);
+        hasher.finish()
+    }
+
+    /// Gets the effective TTL of the element at the specified position.
+    fn entry_ttl(&self, index: usize) -> Duration {
+        let Some(offset) = self
+            .jitter_offsets
+            .as_ref()
+            .and_then(|offsets| offsets.get(index))
+        else {
+            return self.ttl;
+        };
+
+        let range = u64::try_from(self.jitter.as_nanos()).unwrap_or(u64::MAX);
+        self.ttl
+            .saturating_add(Duration::from_nanos(offset % range))
+    }
+
+    /// Returns `true` if elements expire in the order they were added, i.e. without
+    /// TTL jitter or an expiry policy.
+    fn expires_in_order(&self) -> bool {
+        self.jitter.is_zero() && self.expiry_policy.is_none()
+    }
+
+    /// Determines whether the element at the specified position has expired.
+    fn is_expired_at(&self, index: usize, now: Instant) -> bool {
+        let Some((instant, element)) = self.get(index) else {
+            return false;
+        };
+        match &self.expiry_policy {
+            Some(policy) => policy.expired(*instant, now, element),
+            None => now.saturating_duration_since(*instant) >= self.entry_ttl(index),
+        }
+    }
+
+    /// Determines whether the element at the specified position has expired and its
+    /// grace period has passed.
+    fn is_hard_expired_at(&self, index: usize, now: Instant) -> bool {
+        let Some((instant, element)) = self.get(index) else {
+            return false;
+        };
+        match &self.expiry_policy {
+            Some(policy) => policy.expired(*instant, now, element),
+            None => {
+                let age = now.saturating_duration_since(*instant);
+                age >= self.entry_ttl(index).saturating_add(self.grace)
+            }
+        }
+    }
+
+    /// Determines whether the element at the specified position, added at the specified
+    /// instant, has expired, but is still within its grace period.
+    fn is_stale_at(&self, index: usize, instant: &Instant, now: Instant) -> bool {
+        if self.expiry_policy.is_some() {
+            return false;
+        }
+
+        let age = now.saturating_duration_since(*instant);
+        let ttl = self.entry_ttl(index);
+        age >= ttl && age < ttl.saturating_add(self.grace)
+    }
+
+    /// Gets the number of elements that have not expired, without removing expired
+    /// elements from the queue.
+    ///
+    /// The number of leading expired elements is cached and only advanced past newly
+    /// expired elements, so repeated calls do not rescan the same expired prefix.
+    /// Without [TTL jitter](Self::set_ttl_jitter), this operation is O(1) amortized.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use ttl_queue::TtlQueue;
+    /// let mut queue = TtlQueue::new(Duration::ZERO);
+    /// queue.push_back(10);
+    ///
+    /// assert_eq!(queue.len(), 1);
+    /// assert_eq!(queue.valid_len(), 0);
+    /// ```
+    pub fn valid_len(&self) -> usize {
+        let now = self.now();
+        let hint = self.advance_expired_hint(now);
+        if self.expires_in_order() {
+            self.len() - hint
+        } else {
+            self.iter_valid_at(hint, now).count()
+        }
+    }
+
+    /// Returns an iterator over the elements that have not expired, without removing
+    /// expired elements from the queue.
+    ///
+    /// See [`valid_len`](Self::valid_len) for how the expired prefix is skipped.
+    pub fn iter_valid(&self) -> impl Iterator<Item = &(Instant, T)> {
+        let now = self.now();
+        let hint = self.advance_expired_hint(now);
+        self.iter_valid_at(hint, now)
+    }
+
+    /// Gets the `n`-th oldest element that has not expired, as well as the time instant
+    /// at which it was added, without removing expired elements from the queue.
+    ///
+    /// Like [`valid_len`](Self::valid_len), this skips the cached expired prefix, so
+    /// without [TTL jitter](Self::set_ttl_jitter) it is O(1) amortized.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use ttl_queue::{TickClock, TtlQueue};
+    /// let clock = TickClock::new();
+    /// let mut samples = TtlQueue::new(TickClock::ttl(3));
+    /// samples.set_clock(clock.clone());
+    /// for sample in [10, 20, 30, 40] {
+    ///     clock.tick();
+    ///     samples.push_back(sample);
+    /// }
+    ///
+    /// assert_eq!(samples.peek_nth(0).map(|(_, sample)| *sample), Some(20));
+    /// assert_eq!(samples.peek_nth(2).map(|(_, sample)| *sample), Some(40));
+    /// assert_eq!(samples.peek_nth(3), None);
+    /// ```
+    pub fn peek_nth(&self, n: usize) -> Option<&(Instant, T)> {
+        let now = self.now();
+        let hint = self.advance_expired_hint(now);
+        if self.expires_in_order() {
+            self.get(hint.checked_add(n)?)
+        } else {
+            self.iter_valid_at(hint, now).nth(n)
+        }
+    }
+
+    fn iter_valid_at(&self, skip: usize, now: Instant) -> impl Iterator<Item = &(Instant, T)> {
+        self.iter_valid_indexed_at(skip, now)
+            .map(|(_, entry)| entry)
+    }
+
+    /// Returns an iterator over the positions and entries of the elements that have
+    /// not expired, starting at the specified position.
+    fn iter_valid_indexed_at(
+        &self,
+        skip: usize,
+        now: Instant,
+    ) -> impl Iterator<Item = (usize, &(Instant, T))> {
+        self.iter()
+            .enumerate()
+            .skip(skip)
+            .filter(move |(index, _)| !self.is_expired_at(*index, now))
+    }
+
+    /// Advances the cached number of leading expired elements and returns it.
+    fn advance_expired_hint(&self, now: Instant) -> usize {
+        let mut hint = self.expired_hint.load(AtomicOrdering::Relaxed);
+        while hint < self.len() && self.is_expired_at(hint, now) {
+            hint += 1;
+        }
+
+        self.expired_hint.store(hint, AtomicOrdering::Relaxed);
+        hint
+    }
+
+    /// Returns an iterator to the data.
+    pub fn iter(&self) -> impl Iterator<Item = &(Instant, T)> {
+        #[cfg(feature = "doublestack")]
+        {
+            DoubleStackIterator::new(self)
+        }
+        #[cfg(feature = "vecdeque")]
+        {
+            self.queue.iter()
+        }
+    }
+
+    /// Binary searches the queue for an element added at the specified instant.
+    ///
+    /// Since elements are ordered by their timestamps, this behaves like
+    /// [`slice::binary_search`]: if a matching element is found, `Ok` is returned with
+    /// its position counting from the front of the queue (any one of them, if there
+    /// are multiple matches). Otherwise, `Err` is returned with the position at which
+    /// an element with this timestamp could be inserted while retaining the order.
+    ///
+    /// This operation is O(log N).
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use ttl_queue::{Instant, TtlQueue};
+    /// let mut queue = TtlQueue::new(Duration::from_secs(60));
+    /// let before = Instant::now();
+    /// # #[cfg(feature = "mock_instant")]
+    /// # mock_instant::global::MockClock::advance(Duration::from_millis(1));
+    /// queue.push_back(1);
+    /// queue.push_back(2);
+    /// # #[cfg(feature = "mock_instant")]
+    /// # mock_instant::global::MockClock::advance(Duration::from_millis(1));
+    ///
+    /// // Elements added after the specified instant start at the returned position.
+    /// assert_eq!(queue.binary_search_timestamp(before), Err(0));
+    /// assert_eq!(queue.binary_search_timestamp(Instant::now()), Err(2));
+    /// ```
+    pub fn binary_search_timestamp(&self, instant: Instant) -> Result<usize, usize> {
+        self.binary_search_by(|(timestamp, _)| timestamp.cmp(&instant))
+    }
+
+    /// Binary searches the queue with a comparator function, see [`slice::binary_search_by`].
+    fn binary_search_by<F>(&self, mut f: F) -> Result<usize, usize>
+    where
+        F: FnMut(&(Instant, T)) -> Ordering,
+    {
+        let (mut low, mut high) = (0, self.len());
+        while low < high {
+            let mid = low + (high - low) / 2;
+            let entry = self.get(mid).expect("index is within bounds");
+            match f(entry) {
+                Ordering::Less => low = mid + 1,
+                Ordering::Greater => high = mid,
+                Ordering::Equal => return Ok(mid),
+            }
+        }
+        Err(low)
+    }
+
+    /// Returns the duration covered by the elements in the queue, i.e. the time
+    /// between the oldest and the newest element.
+    ///
+    /// Returns [`Duration::ZERO`] if the queue holds fewer than two elements.
+    /// This does not refresh the queue; call [`refresh`](Self::refresh) first in
+    /// order to exclude expired elements.
+    pub fn span(&self) -> Duration {
+        match (self.front_entry(), self.back_entry()) {
+            (Some((oldest, _)), Some((newest, _))) => newest.duration_since(*oldest),
+            _ => Duration::ZERO,
+        }
+    }
+
+    /// Gets the timestamp of the most recently pushed element, even if it has since
+    /// expired or was removed, or `None` if no element was ever pushed.
+    ///
+    /// This operation is O(1).
+    pub fn last_push(&self) -> Option<Instant> {
+        self.last_push
+    }
+
+    /// Gets the time elapsed since the most recently pushed element, or `None` if no
+    /// element was ever pushed.
+    ///
+    /// This operation is O(1).
+    pub fn time_since_last_push(&self) -> Option<Duration> {
+        self.last_push
+            .map(|last_push| self.now().saturating_duration_since(last_push))
+    }
+
+    /// Returns `true` if no element was pushed within the specified threshold, e.g. to
+    /// flag a stream that has gone quiet in a health check.
+    ///
+    /// A queue that never received an element is considered stale. This is unrelated to
+    /// the stale elements kept during the [grace period](Self::set_grace_period).
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use ttl_queue::{TickClock, TtlQueue};
+    /// let clock = TickClock::new();
+    /// let mut queue = TtlQueue::new(TickClock::ttl(10));
+    /// queue.set_clock(clock.clone());
+    /// assert!(queue.is_stale(TickClock::ttl(100)));
+    ///
+    /// queue.push_back("heartbeat");
+    /// clock.advance(50);
+    /// assert_eq!(queue.refresh(), 0);
+    /// assert!(!queue.is_stale(TickClock::ttl(100)));
+    ///
+    /// clock.advance(50);
+    /// assert!(queue.is_stale(TickClock::ttl(100)));
+    /// ```
+    pub fn is_stale(&self, threshold: Duration) -> bool {
+        self.time_since_last_push()
+            .map_or(true, |elapsed| elapsed >= threshold)
+    }
+
+    /// Returns the time until all elements currently in the queue have expired,
+    /// assuming no further elements are pushed.
+    ///
+    /// Returns `None` if the queue is empty and [`Duration::ZERO`] if all elements
+    /// have already expired but were not yet removed by a [`refresh`](Self::refresh).
+    pub fn time_until_empty(&self) -> Option<Duration> {
+        let now = self.now();
+        let remaining = |index: usize, instant: &Instant| {
+            let age = now.saturating_duration_since(*instant);
+            self.entry_ttl(index).saturating_sub(age)
+        };
+
+        if self.expires_in_order() {
+            let (newest, _) = self.back_entry()?;
+            return Some(remaining(self.len() - 1, newest));
+        }
+
+        self.iter()
+            .enumerate()
+            .map(|(index, (instant, _))| remaining(index, instant))
+            .max()
+    }
+
+    /// Returns the time until the next unexpired element expires, or `None` if there is
+    /// none.
+    ///
+    /// It can be used to schedule the next [`refresh`](Self::refresh) precisely instead
+    /// of polling.
+    pub fn time_until_next_expiry(&self) -> Option<Duration> {
+        let now = self.now();
+        let hint = self.advance_expired_hint(now);
+        let mut remaining = self
+            .iter_valid_indexed_at(hint, now)
+            .map(|(index, (instant, _))| {
+                let age = now.saturating_duration_since(*instant);
+                self.entry_ttl(index).saturating_sub(age)
+            });
+
+        if self.expires_in_order() {
+            return remaining.next();
+        }
+        remaining.min()
+    }
+
+    /// Returns the number of live (non-expired) elements per second.
+    ///
+    /// The count is divided by the effectively observed window, i.e. the smaller of
+    /// the TTL and the time since the oldest live element was added. This avoids
+    /// under-reporting the rate while the queue has not yet been filled for a full TTL.
+    ///
+    /// Returns `0.0` if there are no live elements. This operation is O(N) at worst.
+    pub fn rate(&self) -> f64 {
+        let now = self.now();
+        let mut live = self.iter_valid_at(0, now);
+
+        let Some((oldest, _)) = live.next() else {
+            return 0.0;
+        };
+
+        let count = 1 + live.count();
+        let window = now.saturating_duration_since(*oldest).min(self.ttl);
+        if window.is_zero() {
+            return 0.0;
+        }
+
+        count as f64 / window.as_secs_f64()
+    }
+
+    /// Samples the current [`rate`](Self::rate) and folds it into an exponentially
+    /// weighted moving average, which is then returned.
+    ///
+    /// The average is only updated when this method is called, but each sample is
+    /// weighted by the time elapsed since the previous one: a sample taken after
+    /// `time_constant` has passed moves the average about 63% of the way towards the
+    /// current rate, regardless of how often it was sampled in between. The first call,
+    /// or any call with a zero time constant, returns the current rate as-is.
+    pub fn smoothed_rate(&mut self, time_constant: Duration) -> f64 {
+        let now = self.now();
+        let rate = self.rate();
+        let smoothed = match self.smoothed_rate {
+            Some((sampled_at, previous)) if !time_constant.is_zero() => {
+                let elapsed = now.saturating_duration_since(sampled_at);
+                let decay = (-elapsed.as_secs_f64() / time_constant.as_secs_f64()).exp();
+                rate + decay * (previous - rate)
+            }
+            _ => rate,
+        };
+
+        self.smoothed_rate = Some((now, smoothed));
+        smoothed
+    }
+
+    /// Returns the average duration between two events.
+    pub fn avg_delta(&self) -> Duration {
+        if self.len() <= 1 {
+            return Duration::ZERO;
         }
 
         let (count, sum) = self
@@ -278,8 +2608,318 @@ impl<T> TtlQueue<T> {
                 (count + 1, sum + (rhs.0 - lhs.0))
             });
 
-        debug_assert_ne!(count, 0);
-        sum / count
+        debug_assert_ne!(count, 0);
+        sum / count
+    }
+
+    /// Returns the mean, standard deviation and maximum of the durations between
+    /// consecutive elements, or `None` if the queue holds fewer than two elements.
+    ///
+    /// This operation is O(N).
+    pub fn inter_arrival_stats(&self) -> Option<InterArrivalStats> {
+        if self.len() <= 1 {
+            return None;
+        }
+
+        let (count, sum, sum_sq, max) = self.iter().zip(self.iter().skip(1)).fold(
+            (0usize, 0.0, 0.0, Duration::ZERO),
+            |(count, sum, sum_sq, max), (lhs, rhs)| {
+                let delta = rhs.0.duration_since(lhs.0);
+                let secs = delta.as_secs_f64();
+                (count + 1, sum + secs, sum_sq + secs * secs, max.max(delta))
+            },
+        );
+
+        debug_assert_ne!(count, 0);
+        let mean = sum / count as f64;
+        let variance = (sum_sq / count as f64 - mean * mean).max(0.0);
+
+        Some(InterArrivalStats {
+            mean: Duration::from_secs_f64(mean),
+            std_dev: Duration::from_secs_f64(variance.sqrt()),
+            max,
+        })
+    }
+
+    /// Returns the z-score of the most recent rate, i.e. by how many standard deviations
+    /// the duration between the two newest elements deviates from the mean duration
+    /// between consecutive elements, or `None` if there are fewer than three elements
+    /// or all durations are equal.
+    ///
+    /// The sign is that of the rate: a positive score indicates a burst, a negative
+    /// score a lull. This operation is O(N).
+    pub fn rate_zscore(&self) -> Option<f64> {
+        if self.len() <= 2 {
+            return None;
+        }
+
+        let stats = self.inter_arrival_stats()?;
+        let (newest, _) = self.back_entry()?;
+        let (previous, _) = self.get(self.len() - 2)?;
+        let latest = newest.saturating_duration_since(*previous).as_secs_f64();
+
+        let std_dev = stats.std_dev.as_secs_f64();
+        (std_dev > 0.0).then(|| (stats.mean.as_secs_f64() - latest) / std_dev)
+    }
+
+    /// Returns `true` if the most recent rate deviates from the rate over the window by
+    /// at least the specified number of standard deviations, in either direction.
+    ///
+    /// See [`rate_zscore`](Self::rate_zscore).
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use ttl_queue::{Instant, TtlQueue};
+    /// let now = Instant::now();
+    /// let mut events: Vec<_> = (0..20u64)
+    ///     .map(|i| (now + Duration::from_millis(i * 1000 + (i % 2) * 100), ()))
+    ///     .collect();
+    /// let queue: TtlQueue<()> = TtlQueue::try_from(events.clone()).unwrap();
+    /// assert!(!queue.rate_anomaly(3.0));
+    ///
+    /// events.push((events[19].0 + Duration::from_millis(1), ()));
+    /// let queue: TtlQueue<()> = TtlQueue::try_from(events).unwrap();
+    /// assert!(queue.rate_anomaly(3.0));
+    /// ```
+    pub fn rate_anomaly(&self, zscore_threshold: f64) -> bool {
+        self.rate_zscore()
+            .is_some_and(|zscore| zscore.abs() >= zscore_threshold)
+    }
+
+    /// Returns the frame time statistics when every element marks a frame boundary,
+    /// or `None` if the queue holds fewer than two elements.
+    ///
+    /// A frame time is the duration between two consecutive elements. This operation
+    /// is O(N) and allocates a buffer of N frame times.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use ttl_queue::{Instant, TtlQueue};
+    /// let start = Instant::now();
+    /// let mut frame = start;
+    /// let mut frames = vec![(frame, ())];
+    /// for i in 0..200 {
+    ///     frame += Duration::from_millis(if i % 100 == 99 { 50 } else { 10 });
+    ///     frames.push((frame, ()));
+    /// }
+    ///
+    /// let queue: TtlQueue<()> = TtlQueue::try_from(frames).unwrap();
+    /// let timings = queue.frame_timings().unwrap();
+    /// assert_eq!(timings.average, Duration::from_micros(10_400));
+    /// assert_eq!(timings.one_percent_low, Duration::from_millis(50));
+    /// assert_eq!(timings.worst, Duration::from_millis(50));
+    /// assert_eq!(timings.one_percent_low_fps(), 20.0);
+    /// ```
+    pub fn frame_timings(&self) -> Option<FrameTimings> {
+        if self.len() <= 1 {
+            return None;
+        }
+
+        let mut frame_times: Vec<Duration> = self
+            .iter()
+            .zip(self.iter().skip(1))
+            .map(|(lhs, rhs)| rhs.0.duration_since(lhs.0))
+            .collect();
+
+        let count = frame_times.len();
+        let average = frame_times.iter().sum::<Duration>() / count as u32;
+        let worst = frame_times.iter().copied().max().unwrap_or_default();
+
+        let slowest = count.div_ceil(100);
+        frame_times.select_nth_unstable(count - slowest);
+        let one_percent_low =
+            frame_times[count - slowest..].iter().sum::<Duration>() / slowest as u32;
+
+        Some(FrameTimings {
+            average,
+            one_percent_low,
+            worst,
+        })
+    }
+}
+
+/// Creates a queue with a TTL of [`Duration::MAX`] from the elements, all stamped with
+/// the current time. Use [`TtlQueue::set_ttl`] to configure the TTL.
+impl<T> From<Vec<T>> for TtlQueue<T> {
+    fn from(elements: Vec<T>) -> Self {
+        let now = Instant::now();
+        let mut queue = TtlQueue::with_capacity(Duration::MAX, elements.len());
+        for element in elements {
+            queue.push_back_entry(now, element);
+        }
+        queue
+    }
+}
+
+/// Creates a queue with a TTL of [`Duration::MAX`] from timestamped entries, oldest first.
+/// Use [`TtlQueue::set_ttl`] to configure the TTL.
+///
+/// Fails if the timestamps are not in ascending order.
+impl<T> TryFrom<Vec<(Instant, T)>> for TtlQueue<T> {
+    type Error = UnorderedError<T>;
+
+    fn try_from(entries: Vec<(Instant, T)>) -> Result<Self, Self::Error> {
+        if let Some(index) = entries.windows(2).position(|pair| pair[0].0 > pair[1].0) {
+            return Err(UnorderedError {
+                index: index + 1,
+                entries,
+            });
+        }
+
+        let mut queue = TtlQueue::with_capacity(Duration::MAX, entries.len());
+        for (instant, element) in entries {
+            queue.push_back_entry(instant, element);
+        }
+        Ok(queue)
+    }
+}
+
+/// Extends the queue with elements of a known age, oldest first, e.g. loaded from disk
+/// or received over the network.
+///
+/// Each age is converted to a timestamp relative to the current time of the queue's
+/// clock, and every element is inserted as by [`insert_at`](TtlQueue::insert_at): the
+/// push hooks run once per element and the [overflow policy](TtlQueue::set_overflow_policy)
+/// applies. An element older than its predecessor is accepted within the allowed lateness
+/// and otherwise reported to the late listeners and dropped. Elements whose age reaches
+/// back further than the clock can represent are dropped as well.
+///
+/// ## Example
+///
+/// ```
+/// # use std::time::Duration;
+/// # use ttl_queue::TtlQueue;
+/// # #[cfg(feature = "mock_instant")]
+/// # mock_instant::global::MockClock::set_time(Duration::from_secs(3600));
+/// let mut queue = TtlQueue::new(Duration::from_secs(60));
+/// queue.extend([
+///     (Duration::from_secs(30), "older"),
+///     (Duration::from_secs(10), "newer"),
+///     (Duration::from_secs(20), "late"),
+/// ]);
+///
+/// assert_eq!(queue.len(), 2);
+/// let (instant, _) = queue.iter().next().unwrap();
+/// assert!(instant.elapsed() >= Duration::from_secs(30));
+/// ```
+impl<T> Extend<(Duration, T)> for TtlQueue<T> {
+    fn extend<I: IntoIterator<Item = (Duration, T)>>(&mut self, iter: I) {
+        let now = self.now();
+        for (age, element) in iter {
+            let Some(instant) = now.checked_sub(age) else {
+                continue;
+            };
+            let _ = self.insert_entry(instant, element);
+        }
+    }
+}
+
+/// Converts the queue into its entries, oldest first, dropping the TTL semantics.
+///
+/// With the `vecdeque` feature, this reuses the underlying buffer.
+impl<T> From<TtlQueue<T>> for VecDeque<(Instant, T)> {
+    fn from(queue: TtlQueue<T>) -> Self {
+        #[cfg(feature = "vecdeque")]
+        {
+            queue.queue
+        }
+        #[cfg(feature = "doublestack")]
+        {
+            queue.into_iter().collect()
+        }
+    }
+}
+
+/// Converts the queue into its entries, oldest first, dropping the TTL semantics.
+impl<T> From<TtlQueue<T>> for Vec<(Instant, T)> {
+    fn from(queue: TtlQueue<T>) -> Self {
+        #[cfg(feature = "vecdeque")]
+        {
+            queue.queue.into()
+        }
+        #[cfg(feature = "doublestack")]
+        {
+            let mut entries = queue.stack_2;
+            entries.reverse();
+            entries.extend(queue.stack_1);
+            entries
+        }
+    }
+}
+
+/// The error returned when adopting entries whose timestamps are not in ascending order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnorderedError<T> {
+    index: usize,
+    entries: Vec<(Instant, T)>,
+}
+
+impl<T> UnorderedError<T> {
+    /// Gets the position of the first entry that is older than its predecessor.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns the rejected entries.
+    pub fn into_inner(self) -> Vec<(Instant, T)> {
+        self.entries
+    }
+}
+
+impl<T> fmt::Display for UnorderedError<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "entry at position {} is older than its predecessor",
+            self.index
+        )
+    }
+}
+
+impl<T: Debug> std::error::Error for UnorderedError<T> {}
+
+/// The error returned by [`TtlQueue::try_push_back`] when the queue is full.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PushError<T> {
+    element: T,
+    len: usize,
+}
+
+impl<T> PushError<T> {
+    /// Gets the number of unexpired elements in the queue at the time of the push.
+    pub fn live_count(&self) -> usize {
+        self.len
+    }
+
+    /// Returns the rejected element.
+    pub fn into_inner(self) -> T {
+        self.element
+    }
+}
+
+impl<T> fmt::Display for PushError<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "queue is full with {} elements", self.len)
+    }
+}
+
+impl<T: Debug> std::error::Error for PushError<T> {}
+
+impl<T: Debug> Debug for TtlQueue<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TtlQueue")
+            .field("ttl", &self.ttl)
+            .field("grace", &self.grace)
+            .field("jitter", &self.jitter)
+            .field("max_bytes", &self.max_bytes())
+            .field("refresh_policy", &self.refresh_policy)
+            .field("entries", &self.iter().collect::<Vec<_>>())
+            .field("listeners", &self.listener_count())
+            .finish_non_exhaustive()
     }
 }
 
@@ -302,10 +2942,7 @@ impl<T> IntoIterator for TtlQueue<T> {
         }
         #[cfg(feature = "doublestack")]
         {
-            self.stack_2
-                .into_iter()
-                .rev()
-                .chain(self.stack_1.into_iter())
+            self.stack_2.into_iter().rev().chain(self.stack_1)
         }
     }
 }
@@ -372,9 +3009,17 @@ impl<'a, T> Iterator for DoubleStackIterator<'a, T> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    #[cfg(not(feature = "mock_instant"))]
     use std::thread;
 
+    /// Serializes the tests driving the global `MockClock`. Tests relying on the
+    /// wall clock are disabled with the `mock_instant` feature, since mocked time
+    /// neither passes on its own nor reaches back before its start.
+    #[cfg(feature = "mock_instant")]
+    static MOCK_CLOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
     #[test]
+    #[cfg(not(feature = "mock_instant"))]
     fn it_works() {
         let mut queue = TtlQueue::new(Duration::from_millis(50));
         queue.push_back(10);
@@ -391,6 +3036,43 @@ mod tests {
         assert_eq!(queue.refresh(), 0);
     }
 
+    #[test]
+    fn peek_front_returns_oldest() {
+        let mut queue = TtlQueue::new(Duration::MAX);
+        queue.push_back(10);
+        queue.push_back(20);
+        queue.push_back(30);
+        assert_eq!(queue.pop_front().unwrap().1, 10);
+        assert_eq!(queue.peek_front().unwrap().1, 20);
+    }
+
+    #[test]
+    #[cfg(not(feature = "mock_instant"))]
+    fn refresh_drops_oldest_first() {
+        let now = Instant::now();
+        let entries = [
+            (now - Duration::from_secs(30), 0),
+            (now - Duration::from_secs(20), 1),
+            (now - Duration::from_secs(15), 2),
+            (now, 3),
+        ];
+
+        let mut queue = TtlQueue::new(Duration::from_secs(10));
+        for (instant, value) in entries {
+            queue.push_back_entry(instant, value);
+        }
+        assert_eq!(queue.refresh(), 1);
+        assert_eq!(queue.pop_front().unwrap().1, 3);
+
+        // Popping moves the remaining elements to the second stack.
+        for (instant, value) in entries {
+            queue.push_back_entry(instant, value);
+        }
+        assert_eq!(queue.pop_front().unwrap().1, 0);
+        assert_eq!(queue.refresh(), 1);
+        assert_eq!(queue.pop_front().unwrap().1, 3);
+    }
+
     #[test]
     fn iter_works() {
         let mut queue = TtlQueue::new(Duration::MAX);
@@ -462,6 +3144,770 @@ mod tests {
     }
 
     #[test]
+    fn inter_arrival_stats_works() {
+        let mut queue = TtlQueue::new(Duration::MAX);
+        queue.push_back(());
+        assert_eq!(queue.inter_arrival_stats(), None);
+
+        let mut queue = TtlQueue::new(Duration::MAX);
+        let now = Instant::now();
+        for offset in [0, 1, 2, 5, 6] {
+            queue.push_back_entry(now + Duration::from_secs(offset), ());
+        }
+
+        // Gaps are 1, 1, 3 and 1 seconds.
+        let stats = queue.inter_arrival_stats().unwrap();
+        assert_eq!(stats.mean, Duration::from_millis(1500));
+        assert_eq!(stats.max, Duration::from_secs(3));
+        assert!((stats.std_dev.as_secs_f64() - 0.866).abs() < 0.001);
+    }
+
+    #[test]
+    #[cfg(not(feature = "mock_instant"))]
+    fn grace_period_works() {
+        let mut queue = TtlQueue::new(Duration::from_secs(10));
+        queue.set_grace_period(Duration::from_secs(5));
+
+        let now = Instant::now();
+        queue.push_back_entry(now - Duration::from_secs(20), 1);
+        queue.push_back_entry(now - Duration::from_secs(12), 2);
+        queue.push_back_entry(now - Duration::from_secs(11), 3);
+        queue.push_back_entry(now, 4);
+
+        // The first element is past the grace period, the next two are stale.
+        assert_eq!(queue.refresh(), 1);
+        assert_eq!(queue.len(), 3);
+        assert_eq!(queue.stale_len(), 2);
+
+        let stale: Vec<_> = queue.iter_stale().map(|(_, value)| *value).collect();
+        assert_eq!(stale, [2, 3]);
+    }
+
+    #[test]
+    fn ttl_jitter_works() {
+        let mut queue = TtlQueue::new(Duration::from_secs(10));
+        queue.set_ttl_jitter(Duration::from_secs(10));
+
+        let now = Instant::now();
+        for i in 0..100 {
+            queue.push_back_entry(now - Duration::from_secs(15) + Duration::from_nanos(i), i);
+        }
+
+        // All elements are 15 seconds old; with up to 10 seconds of jitter,
+        // only some of them expire.
+        let count = queue.refresh();
+        assert!(count > 0 && count < 100, "unexpected count {count}");
+        assert!((0..queue.len()).all(|index| queue.entry_ttl(index) > Duration::from_secs(15)));
+
+        // Ordering is retained.
+        assert!(queue
+            .iter()
+            .zip(queue.iter().skip(1))
+            .all(|(a, b)| a.1 < b.1));
+    }
+
+    #[test]
+    fn ttl_jitter_spreads_identical_timestamps() {
+        let clock = TickClock::new();
+        let mut queue = TtlQueue::new(TickClock::ttl(10));
+        queue.set_clock(clock.clone());
+        queue.set_ttl_jitter(TickClock::ttl(10));
+        for i in 0..100 {
+            queue.push_back(i);
+        }
+
+        clock.advance(15);
+        let count = queue.refresh();
+        assert!(count > 0 && count < 100, "unexpected count {count}");
+
+        // Elements keep their jitter when others are removed or split off.
+        let ttls: Vec<_> = (0..count).map(|index| queue.entry_ttl(index)).collect();
+        let batch = queue.split_to(count / 2);
+        let split_ttls: Vec<_> = (0..batch.len())
+            .map(|index| batch.entry_ttl(index))
+            .chain((0..queue.len()).map(|index| queue.entry_ttl(index)))
+            .collect();
+        assert_eq!(split_ttls, ttls);
+        queue.debug_validate();
+    }
+
+    #[test]
+    #[cfg(not(feature = "mock_instant"))]
+    fn expiry_listeners_work() {
+        use std::sync::{Arc, Mutex};
+
+        let mut queue = TtlQueue::new(Duration::from_secs(10));
+        let first = Arc::new(Mutex::new(Vec::new()));
+        let second = Arc::new(Mutex::new(0));
+
+        let id = queue.on_expired({
+            let first = first.clone();
+            move |_instant, value| first.lock().unwrap().push(*value)
+        });
+        queue.on_expired({
+            let second = second.clone();
+            move |_instant, _value| *second.lock().unwrap() += 1
+        });
+
+        let now = Instant::now();
+        queue.push_back_entry(now - Duration::from_secs(20), 1);
+        queue.push_back_entry(now - Duration::from_secs(15), 2);
+        queue.push_back_entry(now, 3);
+
+        // Popped elements are not reported.
+        queue.pop_front();
+        assert_eq!(queue.refresh(), 1);
+        assert_eq!(*first.lock().unwrap(), [2]);
+        assert_eq!(*second.lock().unwrap(), 1);
+
+        assert!(queue.remove_listener(id));
+        assert!(!queue.remove_listener(id));
+    }
+
+    #[test]
+    fn tombstones_retain_expired_and_evicted_elements() {
+        let clock = TickClock::new();
+        let mut queue = TtlQueue::with_tombstone_capacity(TickClock::ttl(5), 3);
+        queue.set_clock(clock.clone());
+        queue.max_len = Some(2);
+        queue.set_overflow_policy(OverflowPolicy::Overwrite);
+
+        for i in 0..4 {
+            queue.push_back(i);
+            clock.tick();
+        }
+        queue.pop_front();
+        let tombstones: Vec<_> = queue.recently_expired().map(|(_, value)| *value).collect();
+        assert_eq!(tombstones, [0, 1]);
+
+        clock.advance(5);
+        assert_eq!(queue.refresh(), 0);
+        let tombstones: Vec<_> = queue.recently_expired().map(|(_, value)| *value).collect();
+        assert_eq!(tombstones, [0, 1, 3]);
+
+        queue.push_back(4);
+        clock.advance(5);
+        queue.refresh();
+        let tombstones: Vec<_> = queue.recently_expired().map(|(_, value)| *value).collect();
+        assert_eq!(tombstones, [1, 3, 4]);
+    }
+
+    #[test]
+    fn push_hooks_transform_and_reject() {
+        let mut queue = TtlQueue::new(Duration::from_secs(10));
+        let id = queue.on_push(|value: i32| (value % 2 == 0).then_some(value));
+        queue.on_push(|value| Some(value * 10));
+
+        queue.push_back(1);
+        queue.push_back(2);
+        assert_eq!(queue.try_push_back(3), Ok(1));
+        assert_eq!(queue.refresh_and_push_back(4), 2);
+        assert!(queue.insert_at(Instant::now(), 5).is_ok());
+        queue.extend([(Duration::ZERO, 6), (Duration::ZERO, 7)]);
+
+        let values: Vec<_> = queue.iter().map(|(_, value)| *value).collect();
+        assert_eq!(values, [20, 40, 60]);
+
+        assert!(queue.remove_listener(id));
+        queue.push_back(9);
+        assert_eq!(queue.back_entry().map(|(_, value)| *value), Some(90));
+    }
+
+    #[test]
+    fn extend_applies_hooks_once_and_max_len() {
+        let clock = TickClock::new();
+        clock.advance(100);
+        let mut queue = TtlQueue::with_max_len(TickClock::ttl(50), 3);
+        queue.set_clock(clock.clone());
+        queue.set_allowed_lateness(TickClock::ttl(10));
+        let calls = Arc::new(AtomicUsize::new(0));
+        queue.on_push({
+            let calls = calls.clone();
+            move |value: i32| {
+                calls.fetch_add(1, AtomicOrdering::Relaxed);
+                Some(value + 1)
+            }
+        });
+
+        queue.extend([
+            (TickClock::ttl(30), 0),
+            (TickClock::ttl(20), 10),
+            (TickClock::ttl(25), 20),
+            (TickClock::ttl(10), 30),
+        ]);
+
+        assert_eq!(calls.load(AtomicOrdering::Relaxed), 4);
+        let values: Vec<_> = queue.iter().map(|(_, value)| *value).collect();
+        assert_eq!(values, [1, 21, 11]);
+    }
+
+    #[test]
+    fn threshold_watch_works() {
+        use std::sync::{Arc, Mutex};
+
+        let mut queue = TtlQueue::new(Duration::from_secs(10));
+        let crossings = Arc::new(Mutex::new(Vec::new()));
+        queue.watch_threshold(1, {
+            let crossings = crossings.clone();
+            move |crossing, count| crossings.lock().unwrap().push((crossing, count))
+        });
+
+        let now = Instant::now();
+        queue.push_back_entry(now - Duration::from_secs(20), ());
+        queue.push_back_entry(now, ());
+        queue.push_back_entry(now, ());
+        assert_eq!(queue.refresh(), 2);
+        assert_eq!(queue.pop_front().map(|(instant, _)| instant), Some(now));
+
+        assert_eq!(
+            *crossings.lock().unwrap(),
+            [(ThresholdCrossing::Above, 2), (ThresholdCrossing::Below, 1)]
+        );
+    }
+
+    #[test]
+    fn coalesce_within_keeps_per_element_state() {
+        use std::sync::{Arc, Mutex};
+
+        let clock = TickClock::new();
+        let mut queue = TtlQueue::new(TickClock::ttl(100));
+        queue.set_clock(clock.clone());
+        queue.set_ttl_jitter(TickClock::ttl(10));
+        let crossings = Arc::new(Mutex::new(Vec::new()));
+        queue.watch_threshold(2, {
+            let crossings = crossings.clone();
+            move |crossing, count| crossings.lock().unwrap().push((crossing, count))
+        });
+        for i in 0..5 {
+            queue.push_back(i);
+            clock.advance(if i == 1 { 10 } else { 1 });
+        }
+        crossings.lock().unwrap().clear();
+        let ttls: Vec<_> = (0..queue.len())
+            .map(|index| queue.entry_ttl(index))
+            .collect();
+
+        assert_eq!(
+            queue.coalesce_within(Duration::ZERO, |_, _| unreachable!()),
+            0
+        );
+        assert!(crossings.lock().unwrap().is_empty());
+        let unchanged: Vec<_> = (0..queue.len())
+            .map(|index| queue.entry_ttl(index))
+            .collect();
+        assert_eq!(unchanged, ttls);
+
+        // The bursts [0, 1] and [2, 3, 4] keep the jitter of their first element.
+        assert_eq!(queue.coalesce_within(TickClock::ttl(5), |a, b| *a += b), 3);
+        let values: Vec<_> = queue.iter().map(|(_, value)| *value).collect();
+        assert_eq!(values, [1, 9]);
+        assert_eq!(queue.entry_ttl(0), ttls[0]);
+        assert_eq!(queue.entry_ttl(1), ttls[2]);
+        assert_eq!(*crossings.lock().unwrap(), [(ThresholdCrossing::Below, 2)]);
+        queue.debug_validate();
+    }
+
+    #[test]
+    fn binary_search_timestamp_works() {
+        let mut queue = TtlQueue::new(Duration::MAX);
+        let now = Instant::now();
+        assert_eq!(queue.binary_search_timestamp(now), Err(0));
+
+        for i in 0..100 {
+            queue.push_back_entry(now + Duration::from_secs(i * 2), i);
+
+            // Ensure data is both in stack 1 and stack 2
+            #[cfg(feature = "doublestack")]
+            {
+                if i == 50 {
+                    queue.ensure_stack_full(true);
+                }
+            }
+        }
+
+        for i in 0..100 {
+            let instant = now + Duration::from_secs(i * 2);
+            assert_eq!(queue.binary_search_timestamp(instant), Ok(i as usize));
+
+            let instant = instant + Duration::from_secs(1);
+            assert_eq!(queue.binary_search_timestamp(instant), Err(i as usize + 1));
+        }
+    }
+
+    #[test]
+    fn partition_works() {
+        let mut queue = TtlQueue::new(Duration::from_secs(10));
+        queue.set_grace_period(Duration::from_secs(1));
+
+        let now = Instant::now();
+        for i in 0..10 {
+            queue.push_back_entry(now + Duration::from_secs(i), i);
+        }
+
+        let (small, large) = queue.partition(|_instant, value| *value < 3);
+        assert_eq!(small.grace_period(), Duration::from_secs(1));
+
+        let small: Vec<_> = small.into_iter().collect();
+        let large: Vec<_> = large.into_iter().collect();
+        assert_eq!(
+            small,
+            (0..3)
+                .map(|i| (now + Duration::from_secs(i), i))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            large,
+            (3..10)
+                .map(|i| (now + Duration::from_secs(i), i))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn split_to_spans_both_stacks() {
+        let mut queue = TtlQueue::new(Duration::MAX);
+        for value in 0..3 {
+            queue.push_back(value);
+        }
+        #[cfg(feature = "doublestack")]
+        queue.ensure_stack_full(true);
+        for value in 3..6 {
+            queue.push_back(value);
+        }
+
+        let batch = queue.split_to(4);
+        let values = |queue: &TtlQueue<i32>| queue.iter().map(|(_, v)| *v).collect::<Vec<_>>();
+        assert_eq!(values(&batch), [0, 1, 2, 3]);
+        assert_eq!(values(&queue), [4, 5]);
+        assert_eq!(queue.split_to(0).len(), 0);
+    }
+
+    #[test]
+    fn split_to_carries_last_push_and_budget() {
+        let clock = TickClock::new();
+        let mut queue = TtlQueue::with_max_bytes(TickClock::ttl(100), 10, |s: &String| s.len());
+        queue.set_clock(clock.clone());
+        for word in ["one", "two", "six"] {
+            queue.push_back(word.to_string());
+            clock.tick();
+        }
+
+        let mut batch = queue.split_to(2);
+        assert_eq!(
+            batch.last_push().map(|instant| clock.ticks_at(instant)),
+            Some(1)
+        );
+        assert_eq!(batch.max_bytes(), Some(10));
+        assert_eq!(batch.total_bytes(), 6);
+        assert_eq!(queue.total_bytes(), 3);
+
+        // The batch evicts its oldest elements once it exceeds the budget.
+        batch.push_back("three".to_string());
+        assert_eq!(batch.len(), 2);
+        assert_eq!(batch.total_bytes(), 8);
+    }
+
+    #[test]
+    fn extend_from_ages_respects_lateness() {
+        let clock = TickClock::new();
+        clock.advance(100);
+        let mut queue = TtlQueue::new(TickClock::ttl(50));
+        queue.set_clock(clock.clone());
+        queue.set_allowed_lateness(TickClock::ttl(5));
+
+        queue.extend([(TickClock::ttl(40), 'a'), (TickClock::ttl(20), 'b')]);
+        queue.extend([(TickClock::ttl(22), 'c'), (TickClock::ttl(30), 'd')]);
+
+        let values: Vec<_> = queue.iter().map(|(_, value)| *value).collect();
+        assert_eq!(values, ['a', 'c', 'b']);
+        assert_eq!(
+            queue.front_entry().map(|(instant, _)| *instant),
+            clock.now().checked_sub(TickClock::ttl(40))
+        );
+    }
+
+    #[test]
+    fn simulated_time_is_shared_by_split_queues() {
+        let mut queue = TtlQueue::simulated(Duration::from_secs(60));
+        queue.push_back(1);
+        queue.push_back(2);
+        let mut batch = queue.split_to(1);
+        assert!(batch.is_simulated());
+
+        queue.advance(Duration::from_secs(60));
+        assert_eq!(batch.refresh(), 0);
+
+        queue.set_clock(SystemClock);
+        assert!(!queue.is_simulated());
+    }
+
+    #[test]
+    #[cfg(feature = "mock_instant")]
+    fn mock_clock_controls_expiry() {
+        use mock_instant::global::MockClock;
+
+        let _guard = MOCK_CLOCK
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        MockClock::set_time(Duration::from_secs(100));
+        let mut queue = TtlQueue::new(Duration::from_secs(10));
+        queue.push_back(1);
+        MockClock::advance(Duration::from_secs(9));
+        assert_eq!(queue.refresh(), 1);
+        MockClock::advance(Duration::from_secs(1));
+        assert_eq!(queue.refresh(), 0);
+    }
+
+    #[test]
+    fn pushes_retain_order() {
+        let clock = TickClock::new();
+        let mut queue = TtlQueue::new(TickClock::ttl(100));
+        queue.set_clock(clock.clone());
+        let ahead = clock.now() + TickClock::ttl(30);
+        assert!(queue.insert_at(ahead, "ahead").is_ok());
+
+        assert_eq!(queue.try_push_back("now"), Ok(2));
+        assert_eq!(queue.back_entry().map(|(instant, _)| *instant), Some(ahead));
+        assert_eq!(queue.refresh_and_push_back("later"), 3);
+        assert_eq!(queue.back_entry().map(|(instant, _)| *instant), Some(ahead));
+        queue.debug_validate();
+    }
+
+    #[test]
+    fn max_len_drops_newest_until_elements_expire() {
+        let clock = TickClock::new();
+        let mut queue = TtlQueue::with_max_len(TickClock::ttl(10), 2);
+        queue.set_clock(clock.clone());
+        queue.push_back(1);
+        clock.advance(5);
+        queue.push_back(2);
+        queue.push_back(3);
+        assert_eq!(queue.refresh_and_push_back(4), 2);
+        assert!(queue.insert_at(clock.now(), 5).is_err());
+
+        clock.advance(5);
+        assert_eq!(queue.refresh_and_push_back(6), 2);
+        let values: Vec<_> = queue.iter().map(|(_, value)| *value).collect();
+        assert_eq!(values, [2, 6]);
+    }
+
+    #[test]
+    fn chain_into_forwards_jittered_elements_in_order() {
+        let clock = TickClock::new();
+        let mut short = TtlQueue::new(TickClock::ttl(10));
+        short.set_ttl_jitter(TickClock::ttl(5));
+        short.set_clock(clock.clone());
+        let mut long = TtlQueue::new(TickClock::ttl(100));
+        long.set_clock(clock.clone());
+        long.set_allowed_lateness(TickClock::ttl(5));
+
+        for value in 0..20 {
+            short.push_back(value);
+            clock.tick();
+        }
+        clock.advance(20);
+        assert_eq!(short.chain_into(&mut long), 20);
+        assert_eq!(short.chain_into(&mut long), 0);
+
+        let values: Vec<_> = long.iter().map(|(_, value)| *value).collect();
+        assert_eq!(values, (0..20).collect::<Vec<_>>());
+        assert_eq!(long.refresh(), 20);
+    }
+
+    #[test]
+    fn chain_into_older_element_respects_downstream_watermark() {
+        let clock = TickClock::new();
+        let mut short = TtlQueue::new(TickClock::ttl(10));
+        short.set_clock(clock.clone());
+        let mut long = TtlQueue::with_max_len(TickClock::ttl(100), 3);
+        long.set_clock(clock.clone());
+        let pushed = Arc::new(AtomicUsize::new(0));
+        long.on_push({
+            let pushed = pushed.clone();
+            move |value| {
+                pushed.fetch_add(1, AtomicOrdering::Relaxed);
+                Some(value)
+            }
+        });
+        let expired = Arc::new(AtomicUsize::new(0));
+        short.on_expired({
+            let expired = expired.clone();
+            move |_, _| {
+                expired.fetch_add(1, AtomicOrdering::Relaxed);
+            }
+        });
+
+        short.push_back("old");
+        clock.advance(5);
+        long.push_back("new");
+        clock.advance(5);
+        assert_eq!(short.chain_into(&mut long), 0);
+        assert_eq!(expired.load(AtomicOrdering::Relaxed), 1);
+
+        long.set_allowed_lateness(TickClock::ttl(5));
+        short.push_back("late");
+        clock.advance(3);
+        long.push_back("newer");
+        clock.advance(7);
+        assert_eq!(short.chain_into(&mut long), 1);
+        assert_eq!(pushed.load(AtomicOrdering::Relaxed), 4);
+        let values: Vec<_> = long.iter().map(|(_, value)| *value).collect();
+        assert_eq!(values, ["new", "late", "newer"]);
+
+        short.push_back("overflow");
+        clock.advance(10);
+        assert_eq!(short.chain_into(&mut long), 0);
+        assert_eq!(expired.load(AtomicOrdering::Relaxed), 2);
+        assert_eq!(long.len(), 3);
+    }
+
+    #[test]
+    fn refresh_with_ttl_purges_with_other_horizon() {
+        let clock = TickClock::new();
+        let mut queue = TtlQueue::new(TickClock::ttl(10));
+        queue.set_clock(clock.clone());
+        let expired = Arc::new(AtomicUsize::new(0));
+        let counter = expired.clone();
+        queue.on_expired(move |_, _| {
+            counter.fetch_add(1, AtomicOrdering::Relaxed);
+        });
+
+        for i in 0..8 {
+            queue.push_back(i);
+            clock.tick();
+        }
+        assert_eq!(queue.refresh_with_ttl(TickClock::ttl(20)), 8);
+        assert_eq!(queue.refresh_with_ttl(TickClock::ttl(3)), 2);
+        assert_eq!(expired.load(AtomicOrdering::Relaxed), 6);
+        assert_eq!(queue.ttl(), TickClock::ttl(10));
+        assert_eq!(queue.refresh(), 2);
+    }
+
+    #[test]
+    fn from_vec_works() {
+        let mut queue = TtlQueue::from(vec![1, 2, 3]);
+        assert_eq!(queue.ttl(), Duration::MAX);
+        assert_eq!(queue.refresh(), 3);
+
+        queue.set_ttl(Duration::ZERO);
+        assert_eq!(queue.refresh(), 0);
+    }
+
+    #[test]
+    fn try_from_vec_validates_order() {
+        let now = Instant::now();
+        let entries = vec![(now, 1), (now + Duration::from_secs(1), 2)];
+        let queue = TtlQueue::try_from(entries.clone()).unwrap();
+        assert!(queue.into_iter().eq(entries));
+
+        let entries = vec![(now, 1), (now + Duration::from_secs(2), 2), (now, 3)];
+        let error = TtlQueue::<i32>::try_from(entries.clone()).unwrap_err();
+        assert_eq!(error.index(), 2);
+        assert_eq!(error.into_inner(), entries);
+    }
+
+    #[test]
+    fn into_std_collections_works() {
+        let mut queue = TtlQueue::new(Duration::MAX);
+        for i in 0..100 {
+            queue.push_back(i);
+
+            // Ensure data is both in stack 1 and stack 2
+            #[cfg(feature = "doublestack")]
+            {
+                if i == 50 {
+                    queue.ensure_stack_full(true);
+                }
+            }
+        }
+
+        let expected: Vec<_> = queue.iter().copied().collect();
+        let entries = VecDeque::from(TtlQueue::try_from(expected.clone()).unwrap());
+        assert!(entries.into_iter().eq(expected.iter().copied()));
+
+        let entries = Vec::from(queue);
+        assert_eq!(entries, expected);
+    }
+
+    #[test]
+    fn max_bytes_evicts_oldest() {
+        use std::sync::{Arc, Mutex};
+
+        let mut queue = TtlQueue::with_max_bytes(Duration::MAX, 10, Vec::len);
+        let evicted = Arc::new(Mutex::new(Vec::new()));
+        queue.on_expired({
+            let evicted = evicted.clone();
+            move |_instant, value: &Vec<u8>| evicted.lock().unwrap().push(value.len())
+        });
+
+        queue.push_back(vec![0; 4]);
+        queue.push_back(vec![0; 4]);
+        assert_eq!(queue.total_bytes(), 8);
+
+        queue.push_back(vec![0; 3]);
+        assert_eq!(queue.total_bytes(), 7);
+        assert_eq!(queue.len(), 2);
+
+        queue.pop_front();
+        assert_eq!(queue.total_bytes(), 3);
+
+        // An element exceeding the budget on its own is evicted as well.
+        queue.push_back(vec![0; 11]);
+        assert!(queue.is_empty());
+        assert_eq!(queue.total_bytes(), 0);
+        assert_eq!(*evicted.lock().unwrap(), [4, 3, 11]);
+    }
+
+    #[test]
+    fn approx_memory_bytes_works() {
+        let queue = TtlQueue::<u64>::new(Duration::MAX);
+        assert_eq!(queue.approx_memory_bytes(), size_of::<TtlQueue<u64>>());
+
+        let mut queue = TtlQueue::with_capacity(Duration::MAX, 100);
+        for i in 0..10u64 {
+            queue.push_back(i);
+        }
+
+        let entries = queue.approx_memory_bytes() - size_of::<TtlQueue<u64>>();
+        assert!(entries >= 100 * size_of::<(Instant, u64)>());
+        assert_eq!(
+            queue.approx_memory_bytes_with(|_| 8),
+            queue.approx_memory_bytes() + 80
+        );
+    }
+
+    #[test]
+    fn refresh_removes_expired_prefix() {
+        use std::sync::{Arc, Mutex};
+
+        let now = Instant::now();
+        let mut queue = TtlQueue::new(Duration::from_secs(10));
+        for value in 0..6 {
+            queue.push_back_entry(now - Duration::from_secs(14 - value * 2), value);
+        }
+        #[cfg(feature = "doublestack")]
+        queue.ensure_stack_full(true);
+        for value in 6..10 {
+            queue.push_back_entry(now, value);
+        }
+
+        assert_eq!(queue.refresh(), 7);
+        assert_eq!(queue.pop_front().map(|(_, value)| value), Some(3));
+
+        let expired = Arc::new(Mutex::new(Vec::new()));
+        let sink = expired.clone();
+        queue.on_expired(move |_, value| sink.lock().unwrap().push(*value));
+        queue.set_ttl(Duration::from_secs(1));
+        assert_eq!(queue.refresh(), 4);
+        assert_eq!(*expired.lock().unwrap(), vec![4, 5]);
+    }
+
+    #[test]
+    fn valid_len_skips_expired_prefix() {
+        let now = Instant::now();
+        let mut queue = TtlQueue::new(Duration::from_secs(10));
+        for value in 0..4 {
+            queue.push_back_entry(now - Duration::from_secs(20), value);
+        }
+        #[cfg(feature = "doublestack")]
+        queue.ensure_stack_full(true);
+        for value in 4..6 {
+            queue.push_back_entry(now, value);
+        }
+
+        assert_eq!(queue.valid_len(), 2);
+        assert_eq!(queue.expired_hint.load(AtomicOrdering::Relaxed), 4);
+
+        queue.pop_front();
+        assert_eq!(queue.expired_hint.load(AtomicOrdering::Relaxed), 3);
+        let valid: Vec<_> = queue.iter_valid().map(|(_, value)| *value).collect();
+        assert_eq!(valid, vec![4, 5]);
+
+        queue.set_ttl(Duration::from_secs(60));
+        assert_eq!(queue.valid_len(), 5);
+    }
+
+    #[test]
+    fn cached_clock_shares_timestamps() {
+        let mut queue = TtlQueue::new(Duration::from_secs(10));
+        queue.set_clock(CachedClock::every(100));
+        for value in 0..10 {
+            queue.push_back(value);
+        }
+
+        let first = queue.front_entry().map(|(instant, _)| *instant);
+        assert!(queue.iter().all(|(instant, _)| Some(*instant) == first));
+    }
+
+    #[test]
+    #[cfg(not(feature = "mock_instant"))]
+    fn late_elements_are_reported() {
+        let mut queue = TtlQueue::new(Duration::MAX);
+        queue.set_allowed_lateness(Duration::from_secs(5));
+        let late = Arc::new(AtomicUsize::new(0));
+        queue.on_late({
+            let late = late.clone();
+            move |_, _| {
+                late.fetch_add(1, AtomicOrdering::Relaxed);
+            }
+        });
+
+        let now = Instant::now();
+        assert_eq!(queue.insert_at(now, 1), Ok(()));
+        assert_eq!(queue.insert_at(now - Duration::from_secs(3), 2), Ok(()));
+        assert_eq!(queue.insert_at(now - Duration::from_secs(3), 3), Ok(()));
+        let too_late = now - Duration::from_secs(6);
+        assert_eq!(queue.insert_at(too_late, 4), Err((too_late, 4)));
+
+        let values: Vec<_> = queue.iter().map(|(_, value)| *value).collect();
+        assert_eq!(values, [2, 3, 1]);
+        assert_eq!(late.load(AtomicOrdering::Relaxed), 1);
+    }
+
+    #[test]
+    fn refresh_policy_works() {
+        let expired = Instant::now() - Duration::from_secs(20);
+        let queue_with = |policy| {
+            let mut queue = TtlQueue::new(Duration::from_secs(10));
+            queue.set_refresh_policy(policy);
+            queue.push_back_entry(expired, 0);
+            queue
+        };
+
+        let mut queue = queue_with(RefreshPolicy::Manual);
+        queue.push_back(1);
+        assert_eq!(queue.pop_front().map(|(_, value)| value), Some(0));
+
+        let mut queue = queue_with(RefreshPolicy::OnPush);
+        assert_eq!(queue.peek_front().map(|(_, value)| *value), Some(0));
+        queue.push_back(1);
+        assert_eq!(queue.len(), 1);
+
+        let mut queue = queue_with(RefreshPolicy::OnRead);
+        queue.push_back(1);
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.pop_front().map(|(_, value)| value), Some(1));
+
+        let mut queue = queue_with(RefreshPolicy::Every(Duration::from_secs(60)));
+        queue.push_back(1);
+        assert_eq!(queue.len(), 1);
+
+        let clock = TickClock::new();
+        let mut queue = TtlQueue::new(TickClock::ttl(10));
+        queue.set_clock(clock.clone());
+        queue.set_refresh_policy(RefreshPolicy::Every(TickClock::ttl(60)));
+        queue.push_back(1);
+        clock.advance(20);
+        queue.push_back(2);
+        assert_eq!(queue.len(), 2);
+        clock.advance(50);
+        queue.push_back(3);
+        assert_eq!(queue.len(), 1);
+    }
+
+    #[allow(clippy::manual_range_contains)]
+    #[test]
+    #[cfg(not(feature = "mock_instant"))]
     fn fps_counter() {
         let mut fps_counter = TtlQueue::new(Duration::from_secs(1));
 
@@ -481,4 +3927,87 @@ mod tests {
         let delta = fps_counter.avg_delta();
         debug_assert!(delta >= Duration::from_millis(19) && delta <= Duration::from_millis(21));
     }
+
+    #[test]
+    fn span_works() {
+        let mut queue = TtlQueue::new(Duration::MAX);
+        assert_eq!(queue.span(), Duration::ZERO);
+
+        let now = Instant::now();
+        queue.push_back_entry(now, ());
+        assert_eq!(queue.span(), Duration::ZERO);
+
+        for i in 1..10 {
+            queue.push_back_entry(now + Duration::from_secs(i), ());
+        }
+        assert_eq!(queue.span(), Duration::from_secs(9));
+
+        queue.pop_front();
+        assert_eq!(queue.span(), Duration::from_secs(8));
+    }
+
+    #[test]
+    fn time_until_empty_works() {
+        let mut queue = TtlQueue::new(Duration::from_secs(10));
+        assert_eq!(queue.time_until_empty(), None);
+
+        let now = Instant::now();
+        queue.push_back_entry(now - Duration::from_secs(20), ());
+        assert_eq!(queue.time_until_empty(), Some(Duration::ZERO));
+
+        queue.push_back_entry(now - Duration::from_secs(4), ());
+        let remaining = queue.time_until_empty().unwrap();
+        assert!(remaining <= Duration::from_secs(6));
+        assert!(remaining > Duration::from_secs(5));
+    }
+
+    #[test]
+    fn rate_works() {
+        let mut queue = TtlQueue::new(Duration::from_secs(10));
+        assert_eq!(queue.rate(), 0.0);
+
+        // Expired entries must not be counted.
+        let now = Instant::now();
+        queue.push_back_entry(now - Duration::from_secs(30), ());
+
+        // Five events over the last four seconds.
+        for i in (0..5).rev() {
+            queue.push_back_entry(now - Duration::from_secs(i), ());
+        }
+
+        let rate = queue.rate();
+        assert!((rate - 1.25).abs() < 0.01, "unexpected rate {rate}");
+    }
+
+    #[test]
+    fn smoothed_rate_works() {
+        let clock = TickClock::new();
+        let mut queue = TtlQueue::new(TickClock::ttl(1000));
+        queue.set_clock(clock.clone());
+        for _ in 0..5 {
+            queue.push_back(());
+            clock.tick();
+        }
+        clock.advance(5);
+
+        let time_constant = TickClock::ttl(100);
+        let first = queue.smoothed_rate(time_constant);
+        assert_eq!(first, queue.rate());
+
+        // A burst does not move the average until time passes.
+        for _ in 0..5 {
+            queue.push_back(());
+        }
+        assert_eq!(queue.smoothed_rate(time_constant), first);
+
+        // After one time constant, the average moved ~63% towards the current rate.
+        clock.advance(100);
+        let rate = queue.rate();
+        let expected = rate + (-1f64).exp() * (first - rate);
+        let second = queue.smoothed_rate(time_constant);
+        assert!(
+            (second - expected).abs() < expected * 1e-9,
+            "unexpected rate {second}"
+        );
+    }
 }
@@ -0,0 +1,190 @@
+use crate::{ListenerId, TtlQueue};
+use log::Level;
+use std::fmt::{Debug, Formatter};
+
+/// Summarizes an expired element for the log message.
+type Summary<T> = Box<dyn Fn(&T) -> String + Send + Sync>;
+
+/// Configures how a [`TtlQueue`] logs expired elements via the [`log`] facade.
+///
+/// See [`TtlQueue::log_expired`].
+pub struct ExpiryLogger<T> {
+    level: Level,
+    target: String,
+    summary: Option<Summary<T>>,
+}
+
+impl<T> ExpiryLogger<T> {
+    /// Creates a logger logging expired elements at the debug level to the `ttl_queue`
+    /// target.
+    pub fn new() -> Self {
+        Self {
+            level: Level::Debug,
+            target: String::from("ttl_queue"),
+            summary: None,
+        }
+    }
+
+    /// Sets the level expired elements are logged at.
+    pub fn level(mut self, level: Level) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Sets the target expired elements are logged to.
+    pub fn target(mut self, target: impl Into<String>) -> Self {
+        self.target = target.into();
+        self
+    }
+
+    /// Sets a closure describing the expired element in the log message.
+    pub fn summary<F>(mut self, summary: F) -> Self
+    where
+        F: Fn(&T) -> String + Send + Sync + 'static,
+    {
+        self.summary = Some(Box::new(summary));
+        self
+    }
+}
+
+impl<T> Default for ExpiryLogger<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Debug for ExpiryLogger<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ExpiryLogger")
+            .field("level", &self.level)
+            .field("target", &self.target)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> TtlQueue<T> {
+    /// Logs every expired or evicted element, along with its age at eviction, via the
+    /// [`log`] facade, e.g. for quick diagnostics in production.
+    ///
+    /// The logger is registered as an [expiry listener](Self::on_expired) and can be
+    /// removed via [`remove_listener`](Self::remove_listener).
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use log::Level;
+    /// # use ttl_queue::{ExpiryLogger, TtlQueue};
+    /// let mut jobs = TtlQueue::new(Duration::ZERO);
+    /// jobs.log_expired(
+    ///     ExpiryLogger::new()
+    ///         .level(Level::Warn)
+    ///         .target("jobs")
+    ///         .summary(|job: &&str| format!("job {job} was never processed")),
+    /// );
+    ///
+    /// jobs.push_back("resize image");
+    /// jobs.refresh();
+    /// ```
+    pub fn log_expired(&mut self, logger: ExpiryLogger<T>) -> ListenerId
+    where
+        T: 'static,
+    {
+        let clock = self.clock.clone();
+        self.on_expired(move |instant, element| {
+            if !log::log_enabled!(target: &logger.target, logger.level) {
+                return;
+            }
+
+            let age = clock.now().saturating_duration_since(instant);
+            match &logger.summary {
+                Some(summary) => log::log!(
+                    target: &logger.target,
+                    logger.level,
+                    "Expired element after {age:?}: {}",
+                    summary(element)
+                ),
+                None => log::log!(
+                    target: &logger.target,
+                    logger.level,
+                    "Expired element after {age:?}"
+                ),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TickClock;
+    use log::{LevelFilter, Log, Metadata, Record};
+    use std::sync::Mutex;
+
+    static RECORDS: Mutex<Vec<(Level, String, String)>> = Mutex::new(Vec::new());
+
+    struct CapturingLogger;
+
+    impl Log for CapturingLogger {
+        fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn log(&self, record: &Record<'_>) {
+            RECORDS.lock().unwrap().push((
+                record.level(),
+                record.target().to_string(),
+                record.args().to_string(),
+            ));
+        }
+
+        fn flush(&self) {}
+    }
+
+    #[test]
+    fn expired_elements_are_logged_with_age() {
+        log::set_logger(&CapturingLogger).unwrap();
+        log::set_max_level(LevelFilter::Trace);
+
+        let clock = TickClock::new();
+        let mut queue = TtlQueue::new(TickClock::ttl(10));
+        queue.set_clock(clock.clone());
+        queue.log_expired(
+            ExpiryLogger::new()
+                .level(Level::Info)
+                .target("test")
+                .summary(|value: &u32| format!("value {value}")),
+        );
+        let id = queue.log_expired(ExpiryLogger::new());
+
+        queue.push_back(7);
+        clock.advance(12);
+        queue.refresh();
+        queue.remove_listener(id);
+        queue.push_back(8);
+        clock.advance(10);
+        queue.refresh();
+
+        let records = RECORDS.lock().unwrap();
+        assert_eq!(
+            *records,
+            [
+                (
+                    Level::Info,
+                    "test".to_string(),
+                    "Expired element after 12ns: value 7".to_string()
+                ),
+                (
+                    Level::Debug,
+                    "ttl_queue".to_string(),
+                    "Expired element after 12ns".to_string()
+                ),
+                (
+                    Level::Info,
+                    "test".to_string(),
+                    "Expired element after 10ns: value 8".to_string()
+                ),
+            ]
+        );
+    }
+}
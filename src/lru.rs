@@ -0,0 +1,308 @@
+use crate::Instant;
+use std::borrow::Borrow;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{Debug, Formatter};
+use std::hash::Hash;
+use std::time::Duration;
+
+/// A cache whose entries expire after a given amount of time or are evicted
+/// in least-recently-used order once the capacity is exceeded.
+///
+/// Expired entries are never returned; they are removed lazily on access or all
+/// at once by a [`refresh`](Self::refresh). Hits, misses, evictions and expirations
+/// are counted in the cache's [`stats`](Self::stats).
+///
+/// ## Example
+///
+/// ```
+/// # use std::time::Duration;
+/// # use ttl_queue::LruTtlCache;
+/// let mut cache = LruTtlCache::new(Duration::from_secs(60), 2);
+/// cache.insert("a", 1);
+/// cache.insert("b", 2);
+/// assert_eq!(cache.get("a"), Some(&1));
+///
+/// // "b" is the least recently used entry and makes room for "c".
+/// cache.insert("c", 3);
+/// assert_eq!(cache.get("b"), None);
+///
+/// let stats = cache.stats();
+/// assert_eq!((stats.hits, stats.misses, stats.evictions), (1, 1, 1));
+/// ```
+pub struct LruTtlCache<K, V> {
+    ttl: Duration,
+    capacity: usize,
+    entries: HashMap<K, CacheEntry<V>>,
+    /// The keys ordered by their last use, least recently used first.
+    recency: BTreeMap<u64, K>,
+    /// The use counter, increased on every access.
+    uses: u64,
+    stats: CacheStats,
+}
+
+/// A value stored in an [`LruTtlCache`].
+struct CacheEntry<V> {
+    value: V,
+    instant: Instant,
+    /// The value of the use counter at the last access.
+    last_use: u64,
+}
+
+/// Statistics of an [`LruTtlCache`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CacheStats {
+    /// The number of lookups that found an unexpired entry.
+    pub hits: u64,
+    /// The number of lookups that found no or only an expired entry.
+    pub misses: u64,
+    /// The number of entries removed to make room for new entries.
+    pub evictions: u64,
+    /// The number of entries removed because their time-to-live had elapsed.
+    pub expirations: u64,
+}
+
+impl CacheStats {
+    /// Gets the ratio of hits to lookups, or `0.0` if there were no lookups.
+    pub fn hit_ratio(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            return 0.0;
+        }
+        self.hits as f64 / lookups as f64
+    }
+}
+
+impl<K: Hash + Eq + Clone, V> LruTtlCache<K, V> {
+    /// Creates an empty cache holding at most `capacity` entries.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn new(ttl: Duration, capacity: usize) -> Self {
+        assert_ne!(capacity, 0, "The capacity must not be zero");
+        Self {
+            ttl,
+            capacity,
+            entries: HashMap::with_capacity(capacity),
+            recency: BTreeMap::new(),
+            uses: 0,
+            stats: CacheStats::default(),
+        }
+    }
+
+    /// Gets the time-to-live of the entries.
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Gets the maximum number of entries.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Gets the number of entries in the cache, including potentially expired entries.
+    ///
+    /// In order to obtain an accurate count, use [`refresh`](Self::refresh) instead.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if the cache is definitely empty or `false` if it is possibly empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Gets the hit, miss, eviction and expiration counts.
+    pub fn stats(&self) -> CacheStats {
+        self.stats
+    }
+
+    /// Resets the statistics to zero.
+    pub fn reset_stats(&mut self) {
+        self.stats = CacheStats::default();
+    }
+
+    /// Inserts an entry and returns the previous value of the key, if any.
+    ///
+    /// If the cache is full, expired entries are removed first; only if none of them
+    /// had expired is the least recently used entry evicted.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.insert_at(Instant::now(), key, value)
+    }
+
+    /// Inserts an entry stamped with the specified instant and returns the previous
+    /// value of the key, if any.
+    ///
+    /// The instant also serves as the current time when expired entries are
+    /// removed to make room.
+    pub fn insert_at(&mut self, instant: Instant, key: K, value: V) -> Option<V> {
+        let previous = self.remove(&key);
+        if self.entries.len() >= self.capacity && self.refresh_at(instant) >= self.capacity {
+            self.evict_lru();
+        }
+
+        let last_use = self.next_use();
+        self.recency.insert(last_use, key.clone());
+        self.entries.insert(
+            key,
+            CacheEntry {
+                value,
+                instant,
+                last_use,
+            },
+        );
+        previous
+    }
+
+    /// Gets the value of an unexpired entry and marks it as recently used.
+    pub fn get<Q>(&mut self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get_at(Instant::now(), key)
+    }
+
+    /// Gets the value of an entry unexpired at the specified instant and marks it
+    /// as recently used.
+    pub fn get_at<Q>(&mut self, now: Instant, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let Some(entry) = self.entries.get(key) else {
+            self.stats.misses += 1;
+            return None;
+        };
+
+        if self.is_expired_at(entry, now) {
+            self.stats.misses += 1;
+            self.stats.expirations += 1;
+            self.remove(key);
+            return None;
+        }
+
+        self.stats.hits += 1;
+        let last_use = self.next_use();
+        let entry = self.entries.get_mut(key)?;
+        if let Some(key) = self.recency.remove(&entry.last_use) {
+            self.recency.insert(last_use, key);
+        }
+        entry.last_use = last_use;
+        Some(&entry.value)
+    }
+
+    /// Returns `true` if the cache contains an unexpired entry for the key.
+    ///
+    /// Neither the recency of the entry nor the statistics are affected.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let now = Instant::now();
+        self.entries
+            .get(key)
+            .is_some_and(|entry| !self.is_expired_at(entry, now))
+    }
+
+    /// Removes an entry and returns its value, if any.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let entry = self.entries.remove(key)?;
+        self.recency.remove(&entry.last_use);
+        Some(entry.value)
+    }
+
+    /// Removes all expired entries and returns the number of remaining entries.
+    ///
+    /// This operation is O(N).
+    pub fn refresh(&mut self) -> usize {
+        self.refresh_at(Instant::now())
+    }
+
+    /// Removes all entries expired at the specified instant and returns the number
+    /// of remaining entries.
+    pub fn refresh_at(&mut self, now: Instant) -> usize {
+        let ttl = self.ttl;
+        let recency = &mut self.recency;
+        let stats = &mut self.stats;
+        self.entries.retain(|_, entry| {
+            let expired = now.saturating_duration_since(entry.instant) >= ttl;
+            if expired {
+                recency.remove(&entry.last_use);
+                stats.expirations += 1;
+            }
+            !expired
+        });
+        self.entries.len()
+    }
+
+    fn is_expired_at(&self, entry: &CacheEntry<V>, now: Instant) -> bool {
+        now.saturating_duration_since(entry.instant) >= self.ttl
+    }
+
+    fn next_use(&mut self) -> u64 {
+        self.uses += 1;
+        self.uses
+    }
+
+    fn evict_lru(&mut self) {
+        if let Some((_, key)) = self.recency.pop_first() {
+            self.entries.remove(&key);
+            self.stats.evictions += 1;
+        }
+    }
+}
+
+impl<K: Debug, V: Debug> Debug for LruTtlCache<K, V> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LruTtlCache")
+            .field("ttl", &self.ttl)
+            .field("capacity", &self.capacity)
+            .field("len", &self.entries.len())
+            .field("stats", &self.stats)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(all(test, not(feature = "mock_instant")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expired_entries_are_misses() {
+        let mut cache = LruTtlCache::new(Duration::from_secs(10), 4);
+        let now = Instant::now();
+        cache.insert_at(now - Duration::from_secs(20), "old", 1);
+        cache.insert_at(now - Duration::from_secs(20), "stale", 2);
+        cache.insert_at(now, "new", 3);
+
+        assert_eq!(cache.get_at(now, "old"), None);
+        assert_eq!(cache.get_at(now, "new"), Some(&3));
+        assert_eq!(cache.refresh_at(now), 1);
+
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses), (1, 1));
+        assert_eq!((stats.evictions, stats.expirations), (0, 2));
+        assert_eq!(stats.hit_ratio(), 0.5);
+    }
+
+    #[test]
+    fn full_cache_drops_expired_entries_before_evicting() {
+        let mut cache = LruTtlCache::new(Duration::from_secs(10), 2);
+        let now = Instant::now();
+        cache.insert_at(now, "live", 1);
+        cache.insert_at(now - Duration::from_secs(20), "expired", 2);
+
+        cache.insert_at(now, "new", 3);
+        assert_eq!(cache.get_at(now, "live"), Some(&1));
+        assert_eq!(cache.get_at(now, "new"), Some(&3));
+
+        let stats = cache.stats();
+        assert_eq!((stats.evictions, stats.expirations), (0, 1));
+    }
+}
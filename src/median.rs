@@ -0,0 +1,216 @@
+use crate::derived::DerivedState;
+use crate::{Clock, Instant, TtlQueue};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// A [`TtlQueue`] of ordered elements with an incrementally maintained median.
+///
+/// The elements are additionally kept in two ordered multisets holding the lower and
+/// the upper half, which are updated in O(log N) on every push and expiry. The
+/// [`median`](Self::median) is therefore available in O(log N) instead of sorting
+/// the window on every query.
+///
+/// ## Example
+///
+/// ```
+/// # use std::time::Duration;
+/// # use ttl_queue::MedianWindow;
+/// let mut latencies = MedianWindow::new(Duration::from_secs(60));
+/// for latency in [12, 950, 15, 11, 14] {
+///     latencies.push_back(latency);
+/// }
+///
+/// assert_eq!(latencies.refresh(), 5);
+/// assert_eq!(latencies.median(), Some(&14));
+/// ```
+#[derive(Debug)]
+pub struct MedianWindow<T> {
+    queue: TtlQueue<T>,
+    /// The smaller half of the elements, holding the extra element if the count is odd.
+    lower: Multiset<T>,
+    /// The larger half of the elements.
+    upper: Multiset<T>,
+}
+
+/// An ordered multiset counting the occurrences of each element.
+#[derive(Debug)]
+struct Multiset<T> {
+    counts: BTreeMap<T, usize>,
+    len: usize,
+}
+
+impl<T: Ord + Clone> Multiset<T> {
+    fn new() -> Self {
+        Self {
+            counts: BTreeMap::new(),
+            len: 0,
+        }
+    }
+
+    fn insert(&mut self, element: T) {
+        *self.counts.entry(element).or_default() += 1;
+        self.len += 1;
+    }
+
+    fn remove(&mut self, element: &T) -> bool {
+        let Some(count) = self.counts.get_mut(element) else {
+            return false;
+        };
+
+        *count -= 1;
+        if *count == 0 {
+            self.counts.remove(element);
+        }
+        self.len -= 1;
+        true
+    }
+
+    fn first(&self) -> Option<&T> {
+        self.counts.keys().next()
+    }
+
+    fn last(&self) -> Option<&T> {
+        self.counts.keys().next_back()
+    }
+
+    fn pop_first(&mut self) -> Option<T> {
+        let element = self.first()?.clone();
+        self.remove(&element);
+        Some(element)
+    }
+
+    fn pop_last(&mut self) -> Option<T> {
+        let element = self.last()?.clone();
+        self.remove(&element);
+        Some(element)
+    }
+}
+
+impl<T: Ord + Clone> MedianWindow<T> {
+    /// Creates an empty [`MedianWindow`].
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            queue: TtlQueue::new(ttl),
+            lower: Multiset::new(),
+            upper: Multiset::new(),
+        }
+    }
+
+    /// Gets the elements of the window.
+    pub fn queue(&self) -> &TtlQueue<T> {
+        &self.queue
+    }
+
+    /// Gets the number of elements, including potentially expired elements.
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Returns `true` if the window is definitely empty or `false` if it is possibly empty.
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// Pushes an element to the end of the window.
+    pub fn push_back(&mut self, element: T) {
+        self.push_back_at(self.queue.now(), element)
+    }
+
+    /// Pushes an element stamped with the specified instant to the end of the window.
+    ///
+    /// The instant must not be earlier than that of the previously pushed element.
+    pub fn push_back_at(&mut self, instant: Instant, element: T) {
+        if self.lower.last().is_some_and(|max| element > *max) {
+            self.upper.insert(element.clone());
+        } else {
+            self.lower.insert(element.clone());
+        }
+        self.rebalance();
+        self.queue.push_back_entry(instant, element);
+    }
+
+    /// Removes the element from the front of the window and returns it, as well as the
+    /// time instant at which it was added.
+    pub fn pop_front(&mut self) -> Option<(Instant, T)> {
+        let (instant, element) = self.queue.pop_front_entry()?;
+        if !self.upper.remove(&element) {
+            self.lower.remove(&element);
+        }
+        self.rebalance();
+        Some((instant, element))
+    }
+
+    /// Sets the clock used to timestamp new elements and to determine expiry.
+    ///
+    /// See [`TtlQueue::set_clock`].
+    pub fn set_clock(&mut self, clock: impl Clock + 'static) {
+        self.queue.set_clock(clock);
+    }
+
+    /// Refreshes the window and returns the number of currently contained elements.
+    pub fn refresh(&mut self) -> usize {
+        self.expire()
+    }
+
+    /// Refreshes the window as of the specified instant and returns the number of
+    /// currently contained elements.
+    pub fn refresh_at(&mut self, now: Instant) -> usize {
+        self.expire_at(now)
+    }
+
+    /// Gets the median of the elements, or `None` if the window is empty.
+    ///
+    /// For an even number of elements, this is the lower of the two middle elements.
+    pub fn median(&self) -> Option<&T> {
+        self.lower.last()
+    }
+
+    /// Moves elements between the halves until the lower half holds as many elements
+    /// as the upper half, or one more.
+    fn rebalance(&mut self) {
+        while self.lower.len > self.upper.len + 1 {
+            if let Some(element) = self.lower.pop_last() {
+                self.upper.insert(element);
+            }
+        }
+        while self.upper.len > self.lower.len {
+            if let Some(element) = self.upper.pop_first() {
+                self.lower.insert(element);
+            }
+        }
+    }
+}
+
+impl<T: Ord + Clone> DerivedState for MedianWindow<T> {
+    type Element = T;
+
+    fn queue(&self) -> &TtlQueue<T> {
+        &self.queue
+    }
+
+    fn remove_front(&mut self) {
+        self.pop_front();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn median_follows_expiry() {
+        let mut window = MedianWindow::new(Duration::from_secs(10));
+        let now = Instant::now();
+        for (i, value) in [1, 2, 9, 3, 8, 7].into_iter().enumerate() {
+            window.push_back_at(now + Duration::from_secs(i as u64), value);
+        }
+        assert_eq!(window.median(), Some(&3));
+
+        assert_eq!(window.refresh_at(now + Duration::from_secs(11)), 4);
+        assert_eq!(window.median(), Some(&7));
+        assert_eq!(window.refresh_at(now + Duration::from_secs(13)), 2);
+        assert_eq!(window.median(), Some(&7));
+        assert_eq!(window.refresh_at(now + Duration::from_secs(15)), 0);
+        assert_eq!(window.median(), None);
+    }
+}
@@ -0,0 +1,385 @@
+use memmap2::MmapMut;
+use std::fmt::{Debug, Formatter};
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Identifies files written by [`MmapTtlQueue`].
+const MAGIC: &[u8; 8] = b"TTLQMAP1";
+
+/// The magic, followed by the offsets of the first live entry and of the end of the data.
+const HEADER_LEN: usize = 24;
+
+/// The timestamp in nanoseconds since the Unix epoch, followed by the payload length.
+const ENTRY_HEADER_LEN: usize = 12;
+
+/// The size of a newly created file.
+const INITIAL_FILE_LEN: u64 = 4096;
+
+/// A TTL queue of byte payloads appended to a memory-mapped file, so that the window
+/// survives a restart or crash with the remaining TTLs intact.
+///
+/// Payloads are stamped with the wall-clock time, since [`Instant`](crate::Instant)s
+/// cannot be compared across processes. Expiry advances the head pointer stored in the
+/// file header; the space of expired entries is reclaimed when the file would otherwise
+/// grow.
+///
+/// Changes are written to the mapped memory and reach the file eventually; use
+/// [`flush`](Self::flush) to write them synchronously.
+///
+/// ## Example
+///
+/// ```
+/// # use std::time::Duration;
+/// # use ttl_queue::MmapTtlQueue;
+/// let path = std::env::temp_dir().join("mmap-ttl-queue-example.bin");
+/// # let _ = std::fs::remove_file(&path);
+/// {
+///     let mut queue = MmapTtlQueue::open(&path, Duration::from_secs(60)).unwrap();
+///     queue.push_back(b"first").unwrap();
+///     queue.push_back(b"second").unwrap();
+///     queue.flush().unwrap();
+/// }
+///
+/// let mut reopened = MmapTtlQueue::open(&path, Duration::from_secs(60)).unwrap();
+/// assert_eq!(reopened.refresh(), 2);
+/// let (_time, payload) = reopened.peek_front().unwrap();
+/// assert_eq!(payload, b"first");
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+pub struct MmapTtlQueue {
+    path: PathBuf,
+    file: File,
+    mmap: MmapMut,
+    ttl: Duration,
+    /// The offset of the first live entry.
+    head: usize,
+    /// The offset at which the next entry is appended.
+    tail: usize,
+    len: usize,
+}
+
+impl MmapTtlQueue {
+    /// Opens the queue stored in the specified file, creating the file if it does not exist.
+    ///
+    /// Entries of an existing file are retained, including those that expired while the
+    /// file was closed; call [`refresh`](Self::refresh) to remove them.
+    ///
+    /// The file is mapped into memory and must not be modified by other handles or
+    /// processes while the queue is open.
+    ///
+    /// ## Errors
+    ///
+    /// Returns an error of kind [`InvalidData`](io::ErrorKind::InvalidData) if the file
+    /// exists but was not written by this type or is corrupt.
+    pub fn open(path: impl AsRef<Path>, ttl: Duration) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)?;
+
+        let created = file.metadata()?.len() == 0;
+        if created {
+            file.set_len(INITIAL_FILE_LEN)?;
+        }
+
+        let mut queue = Self {
+            path,
+            mmap: Self::map(&file)?,
+            file,
+            ttl,
+            head: HEADER_LEN,
+            tail: HEADER_LEN,
+            len: 0,
+        };
+
+        if created {
+            queue.mmap[..MAGIC.len()].copy_from_slice(MAGIC);
+            queue.write_header();
+        } else {
+            queue.read_header()?;
+        }
+        Ok(queue)
+    }
+
+    fn map(file: &File) -> io::Result<MmapMut> {
+        // SAFETY: The file must not be modified externally while mapped, which is a
+        // documented requirement of `open`.
+        unsafe { MmapMut::map_mut(file) }
+    }
+
+    fn read_header(&mut self) -> io::Result<()> {
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message);
+        if self.mmap.len() < HEADER_LEN || &self.mmap[..MAGIC.len()] != MAGIC {
+            return Err(invalid("not a TTL queue file"));
+        }
+
+        let head = usize::try_from(self.read_u64(8)).unwrap_or(usize::MAX);
+        let tail = usize::try_from(self.read_u64(16)).unwrap_or(usize::MAX);
+        if head < HEADER_LEN || head > tail || tail > self.mmap.len() {
+            return Err(invalid("corrupt TTL queue header"));
+        }
+
+        let mut offset = head;
+        let mut len = 0;
+        while offset < tail {
+            offset = self
+                .next_offset(offset)
+                .filter(|next| *next <= tail)
+                .ok_or_else(|| invalid("corrupt TTL queue entry"))?;
+            len += 1;
+        }
+
+        self.head = head;
+        self.tail = tail;
+        self.len = len;
+        Ok(())
+    }
+
+    fn write_header(&mut self) {
+        self.mmap[8..16].copy_from_slice(&(self.head as u64).to_le_bytes());
+        self.mmap[16..24].copy_from_slice(&(self.tail as u64).to_le_bytes());
+    }
+
+    fn read_u64(&self, offset: usize) -> u64 {
+        let bytes = self.mmap[offset..offset + 8].try_into().expect("8 bytes");
+        u64::from_le_bytes(bytes)
+    }
+
+    /// Gets the offset of the entry following the entry at the specified offset, or
+    /// `None` if the entry exceeds the file.
+    fn next_offset(&self, offset: usize) -> Option<usize> {
+        let bytes = self.mmap.get(offset + 8..offset + ENTRY_HEADER_LEN)?;
+        let len = u32::from_le_bytes(bytes.try_into().expect("4 bytes")) as usize;
+        let next = offset + ENTRY_HEADER_LEN + len;
+        (next <= self.mmap.len()).then_some(next)
+    }
+
+    /// Reads the entry at the specified offset, which must be that of a live entry.
+    fn entry_at(&self, offset: usize) -> (SystemTime, &[u8]) {
+        let nanos = self.read_u64(offset);
+        let end = self.next_offset(offset).expect("live entries are valid");
+        (
+            UNIX_EPOCH + Duration::from_nanos(nanos),
+            &self.mmap[offset + ENTRY_HEADER_LEN..end],
+        )
+    }
+
+    /// Gets the path of the file backing the queue.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Gets the time-to-live of the payloads.
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Gets the number of payloads, including potentially expired payloads.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the queue is definitely empty or `false` if it is possibly empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Pushes a payload stamped with the current wall-clock time to the end of the queue.
+    pub fn push_back(&mut self, payload: impl AsRef<[u8]>) -> io::Result<()> {
+        self.push_back_at(SystemTime::now(), payload)
+    }
+
+    /// Pushes a payload stamped with the specified wall-clock time to the end of the queue.
+    ///
+    /// The time must not be earlier than that of the previously pushed payload.
+    ///
+    /// ## Errors
+    ///
+    /// Returns an error if the file cannot be grown, or of kind
+    /// [`InvalidInput`](io::ErrorKind::InvalidInput) if the payload exceeds 4 GiB or the
+    /// time cannot be represented.
+    pub fn push_back_at(&mut self, time: SystemTime, payload: impl AsRef<[u8]>) -> io::Result<()> {
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidInput, message);
+        let payload = payload.as_ref();
+        let len = u32::try_from(payload.len()).map_err(|_| invalid("payload too large"))?;
+        let nanos = time
+            .duration_since(UNIX_EPOCH)
+            .ok()
+            .and_then(|since| u64::try_from(since.as_nanos()).ok())
+            .ok_or_else(|| invalid("time not representable"))?;
+
+        let end = self.tail + ENTRY_HEADER_LEN + payload.len();
+        if end > self.mmap.len() {
+            self.make_room(end - self.tail)?;
+        }
+
+        let offset = self.tail;
+        self.mmap[offset..offset + 8].copy_from_slice(&nanos.to_le_bytes());
+        self.mmap[offset + 8..offset + ENTRY_HEADER_LEN].copy_from_slice(&len.to_le_bytes());
+        self.mmap[offset + ENTRY_HEADER_LEN..offset + ENTRY_HEADER_LEN + payload.len()]
+            .copy_from_slice(payload);
+
+        self.tail = offset + ENTRY_HEADER_LEN + payload.len();
+        self.len += 1;
+        self.write_header();
+        Ok(())
+    }
+
+    /// Reclaims the space of removed entries or grows the file to fit `additional` bytes.
+    fn make_room(&mut self, additional: usize) -> io::Result<()> {
+        // Live entries are only moved if they do not overlap their destination, so that
+        // the file remains consistent until the header is updated.
+        let live = self.tail - self.head;
+        if live <= self.head - HEADER_LEN && HEADER_LEN + live + additional <= self.mmap.len() {
+            self.compact();
+            return Ok(());
+        }
+
+        let required = (self.tail + additional) as u64;
+        let file_len = (self.mmap.len() as u64).saturating_mul(2).max(required);
+        self.mmap.flush()?;
+        self.file.set_len(file_len)?;
+        self.mmap = Self::map(&self.file)?;
+        Ok(())
+    }
+
+    /// Moves the live entries to the start of the file.
+    fn compact(&mut self) {
+        self.mmap.copy_within(self.head..self.tail, HEADER_LEN);
+        self.tail -= self.head - HEADER_LEN;
+        self.head = HEADER_LEN;
+        self.write_header();
+    }
+
+    /// Gets the payload at the front of the queue, as well as the wall-clock time at
+    /// which it was added.
+    pub fn peek_front(&self) -> Option<(SystemTime, &[u8])> {
+        (self.len > 0).then(|| self.entry_at(self.head))
+    }
+
+    /// Removes the payload from the front of the queue and returns it, as well as the
+    /// wall-clock time at which it was added.
+    pub fn pop_front(&mut self) -> Option<(SystemTime, Vec<u8>)> {
+        let (time, payload) = self.peek_front()?;
+        let entry = (time, payload.to_vec());
+        self.advance_head();
+        self.write_header();
+        Some(entry)
+    }
+
+    fn advance_head(&mut self) {
+        self.head = self.next_offset(self.head).expect("live entries are valid");
+        self.len -= 1;
+        if self.len == 0 {
+            self.head = HEADER_LEN;
+            self.tail = HEADER_LEN;
+        }
+    }
+
+    /// Refreshes the queue and returns the number of currently contained payloads.
+    pub fn refresh(&mut self) -> usize {
+        self.refresh_at(SystemTime::now())
+    }
+
+    /// Refreshes the queue as of the specified wall-clock time and returns the number
+    /// of currently contained payloads.
+    ///
+    /// Payloads stamped later than `now`, e.g. after the system time was set back, are
+    /// not considered expired.
+    pub fn refresh_at(&mut self, now: SystemTime) -> usize {
+        while let Some((time, _)) = self.peek_front() {
+            let expired = now.duration_since(time).is_ok_and(|age| age >= self.ttl);
+            if !expired {
+                break;
+            }
+            self.advance_head();
+        }
+        self.write_header();
+        self.len
+    }
+
+    /// Returns an iterator over the payloads, oldest first, as well as the wall-clock
+    /// times at which they were added.
+    pub fn iter(&self) -> impl Iterator<Item = (SystemTime, &[u8])> + '_ {
+        let mut offset = self.head;
+        std::iter::from_fn(move || {
+            if offset >= self.tail {
+                return None;
+            }
+            let entry = self.entry_at(offset);
+            offset = self.next_offset(offset).expect("live entries are valid");
+            Some(entry)
+        })
+        .take(self.len)
+    }
+
+    /// Writes all changes to the file and waits until they are persisted.
+    pub fn flush(&self) -> io::Result<()> {
+        self.mmap.flush()
+    }
+}
+
+impl Debug for MmapTtlQueue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MmapTtlQueue")
+            .field("path", &self.path)
+            .field("ttl", &self.ttl)
+            .field("len", &self.len)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reopened_queue_retains_remaining_ttl() {
+        let path = std::env::temp_dir().join(format!("ttl-queue-mmap-{}.bin", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let start = UNIX_EPOCH + Duration::from_secs(1_000_000);
+        let secs = |secs: u64| start + Duration::from_secs(secs);
+
+        {
+            let mut queue = MmapTtlQueue::open(&path, Duration::from_secs(10)).unwrap();
+            let payload = vec![7u8; 1000];
+            for i in 0..20 {
+                queue
+                    .push_back_at(secs(i), &payload[..i as usize * 50])
+                    .unwrap();
+            }
+            assert_eq!(queue.refresh_at(secs(20)), 9);
+            queue.flush().unwrap();
+        }
+
+        let mut queue = MmapTtlQueue::open(&path, Duration::from_secs(10)).unwrap();
+        assert_eq!(queue.len(), 9);
+        let (time, payload) = queue.peek_front().unwrap();
+        assert_eq!(time, secs(11));
+        assert_eq!(payload.len(), 550);
+
+        // Once the expired entries outweigh the live ones, their space is reclaimed
+        // instead of growing the file.
+        assert_eq!(queue.refresh_at(secs(26)), 3);
+        let file_len = queue.mmap.len();
+        let large = vec![1u8; file_len - queue.tail - ENTRY_HEADER_LEN + 1];
+        queue.push_back_at(secs(26), &large).unwrap();
+        assert_eq!(queue.mmap.len(), file_len);
+        assert_eq!(queue.head, HEADER_LEN);
+        drop(queue);
+
+        let mut queue = MmapTtlQueue::open(&path, Duration::from_secs(10)).unwrap();
+        assert_eq!(queue.refresh_at(secs(28)), 2);
+        let sizes: Vec<usize> = queue.iter().map(|(_, payload)| payload.len()).collect();
+        assert_eq!(sizes, [950, large.len()]);
+        assert_eq!(queue.pop_front().unwrap().0, secs(19));
+
+        drop(queue);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
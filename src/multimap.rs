@@ -0,0 +1,150 @@
+use crate::{Instant, TtlQueue};
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::time::Duration;
+
+/// A map holding multiple values per key, each of which expires independently
+/// after a given amount of time.
+///
+/// The values of each key are kept in insertion order in a [`TtlQueue`], e.g. to
+/// track the events per user within the last minute. Keys without any remaining
+/// values are removed by a [`refresh`](Self::refresh).
+///
+/// ## Example
+///
+/// ```
+/// # use std::time::Duration;
+/// # use ttl_queue::TtlMultiMap;
+/// let mut logins = TtlMultiMap::new(Duration::from_secs(60));
+/// logins.insert("alice", "10.0.0.1");
+/// logins.insert("alice", "10.0.0.2");
+/// logins.insert("bob", "10.0.0.3");
+///
+/// assert_eq!(logins.count("alice"), 2);
+/// assert_eq!(logins.values("alice").collect::<Vec<_>>(), [&"10.0.0.1", &"10.0.0.2"]);
+/// assert_eq!(logins.count("carol"), 0);
+/// ```
+#[derive(Debug)]
+pub struct TtlMultiMap<K, V> {
+    ttl: Duration,
+    entries: HashMap<K, TtlQueue<V>>,
+}
+
+impl<K: Hash + Eq, V> TtlMultiMap<K, V> {
+    /// Creates an empty [`TtlMultiMap`].
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Gets the time-to-live of the values.
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Gets the number of keys, including keys whose values may all have expired.
+    ///
+    /// In order to obtain an accurate count, [`refresh`](Self::refresh) the map first.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if the map is definitely empty or `false` if it is possibly empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Appends a value to the values of the key.
+    pub fn insert(&mut self, key: K, value: V) {
+        self.insert_at(Instant::now(), key, value)
+    }
+
+    /// Appends a value stamped with the specified instant to the values of the key.
+    ///
+    /// The instant must not be earlier than that of the previously inserted value
+    /// of the same key.
+    pub fn insert_at(&mut self, instant: Instant, key: K, value: V) {
+        let ttl = self.ttl;
+        self.entries
+            .entry(key)
+            .or_insert_with(|| TtlQueue::new(ttl))
+            .push_back_entry(instant, value);
+    }
+
+    /// Gets the number of unexpired values of the key.
+    pub fn count<Q>(&self, key: &Q) -> usize
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.entries.get(key).map_or(0, TtlQueue::valid_len)
+    }
+
+    /// Returns an iterator over the unexpired values of the key, oldest first.
+    pub fn values<Q>(&self, key: &Q) -> impl Iterator<Item = &V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.entries
+            .get(key)
+            .into_iter()
+            .flat_map(|queue| queue.iter_valid().map(|(_, value)| value))
+    }
+
+    /// Removes all values of the key and returns them as a queue, if any.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<TtlQueue<V>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.entries.remove(key)
+    }
+
+    /// Removes all expired values as well as keys without remaining values, and
+    /// returns the total number of remaining values.
+    pub fn refresh(&mut self) -> usize {
+        self.refresh_at(Instant::now())
+    }
+
+    /// Removes all values expired at the specified instant as well as keys without
+    /// remaining values, and returns the total number of remaining values.
+    pub fn refresh_at(&mut self, now: Instant) -> usize {
+        let mut count = 0;
+        self.entries.retain(|_, queue| {
+            let remaining = queue.refresh_at(now);
+            count += remaining;
+            remaining > 0
+        });
+        count
+    }
+
+    /// Returns an iterator over the keys, including keys whose values may all have expired.
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.entries.keys()
+    }
+}
+
+#[cfg(all(test, not(feature = "mock_instant")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_expire_independently() {
+        let mut map = TtlMultiMap::new(Duration::from_secs(10));
+        let now = Instant::now();
+        map.insert_at(now - Duration::from_secs(20), 1, "old");
+        map.insert_at(now - Duration::from_secs(20), 2, "old");
+        map.insert_at(now, 2, "new");
+
+        assert_eq!(map.count(&1), 0);
+        assert_eq!(map.values(&2).collect::<Vec<_>>(), [&"new"]);
+        assert_eq!(map.len(), 2);
+
+        assert_eq!(map.refresh_at(now), 1);
+        assert_eq!(map.keys().collect::<Vec<_>>(), [&2]);
+    }
+}
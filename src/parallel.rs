@@ -0,0 +1,99 @@
+use crate::{Instant, TtlQueue};
+use rayon::prelude::*;
+
+#[cfg(feature = "vecdeque")]
+type ParIter<'a, T> = rayon::collections::vec_deque::Iter<'a, (Instant, T)>;
+
+#[cfg(feature = "doublestack")]
+type ParIter<'a, T> = rayon::iter::Chain<
+    rayon::iter::Rev<rayon::slice::Iter<'a, (Instant, T)>>,
+    rayon::slice::Iter<'a, (Instant, T)>,
+>;
+
+#[cfg(feature = "vecdeque")]
+type IntoParIter<T> = rayon::collections::vec_deque::IntoIter<(Instant, T)>;
+
+#[cfg(feature = "doublestack")]
+type IntoParIter<T> = rayon::iter::Chain<
+    rayon::iter::Rev<rayon::vec::IntoIter<(Instant, T)>>,
+    rayon::vec::IntoIter<(Instant, T)>,
+>;
+
+impl<T: Sync> TtlQueue<T> {
+    /// Returns a parallel iterator to the data.
+    ///
+    /// Like [`iter`](Self::iter), this includes potentially expired elements;
+    /// call [`refresh`](Self::refresh) first in order to only visit live elements.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use ttl_queue::TtlQueue;
+    /// use rayon::prelude::*;
+    ///
+    /// let mut queue = TtlQueue::new(Duration::from_secs(60));
+    /// for i in 0..1000 {
+    ///     queue.push_back(i);
+    /// }
+    ///
+    /// let sum: u64 = queue.par_iter().map(|(_instant, value)| value).sum();
+    /// assert_eq!(sum, 499_500);
+    /// ```
+    pub fn par_iter(&self) -> ParIter<'_, T> {
+        #[cfg(feature = "doublestack")]
+        {
+            self.stack_2.par_iter().rev().chain(self.stack_1.par_iter())
+        }
+        #[cfg(feature = "vecdeque")]
+        {
+            self.queue.par_iter()
+        }
+    }
+}
+
+impl<'a, T: Sync> IntoParallelIterator for &'a TtlQueue<T> {
+    type Iter = ParIter<'a, T>;
+    type Item = &'a (Instant, T);
+
+    fn into_par_iter(self) -> Self::Iter {
+        self.par_iter()
+    }
+}
+
+impl<T: Send> IntoParallelIterator for TtlQueue<T> {
+    type Iter = IntoParIter<T>;
+    type Item = (Instant, T);
+
+    fn into_par_iter(self) -> Self::Iter {
+        #[cfg(feature = "doublestack")]
+        {
+            let (stack_1, stack_2) = (self.stack_1, self.stack_2);
+            stack_2.into_par_iter().rev().chain(stack_1.into_par_iter())
+        }
+        #[cfg(feature = "vecdeque")]
+        {
+            self.queue.into_par_iter()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn par_iter_preserves_order() {
+        let mut queue = TtlQueue::new(Duration::MAX);
+        for i in 0..1000 {
+            queue.push_back(i);
+        }
+
+        let values: Vec<_> = queue.par_iter().map(|(_, value)| *value).collect();
+        assert!(values.iter().copied().eq(0..1000));
+
+        let values: Vec<_> = queue.into_par_iter().map(|(_, value)| value).collect();
+        assert!(values.into_iter().eq(0..1000));
+    }
+}
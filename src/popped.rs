@@ -0,0 +1,162 @@
+use crate::{Instant, TtlQueue};
+use std::fmt::{Debug, Formatter};
+use std::ops::{Deref, DerefMut};
+
+/// An element popped from the front of a [`TtlQueue`] that is put back with its
+/// original timestamp when dropped, unless it was [committed](Self::commit).
+///
+/// See [`TtlQueue::pop_front_guarded`].
+pub struct PoppedGuard<'a, T> {
+    queue: &'a mut TtlQueue<T>,
+    entry: Option<(Instant, T)>,
+}
+
+impl<T> TtlQueue<T> {
+    /// Puts a popped element back at the front of the queue with its original timestamp,
+    /// e.g. after failing to process it, so that it is retried next.
+    ///
+    /// Returns the entry as an error if its timestamp is newer than that of the current
+    /// front element, since this would violate the ordering of the queue.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use ttl_queue::TtlQueue;
+    /// let mut jobs = TtlQueue::new(Duration::from_secs(60));
+    /// jobs.push_back("send mail");
+    /// jobs.push_back("resize image");
+    ///
+    /// let (instant, job) = jobs.pop_front().unwrap();
+    /// // Processing failed, retry later.
+    /// assert!(jobs.undo_pop(instant, job).is_ok());
+    ///
+    /// assert_eq!(jobs.pop_front().unwrap(), (instant, "send mail"));
+    /// ```
+    pub fn undo_pop(&mut self, instant: Instant, element: T) -> Result<(), (Instant, T)> {
+        if self
+            .front_entry()
+            .is_some_and(|(front, _)| *front < instant)
+        {
+            return Err((instant, element));
+        }
+
+        self.insert(0, (instant, element));
+        self.update_thresholds(self.len());
+        Ok(())
+    }
+
+    /// Removes the element from the front of the queue and returns a guard that puts
+    /// it back with its original timestamp when dropped, unless it is
+    /// [committed](PoppedGuard::commit).
+    ///
+    /// This allows consumers to give up on an element, e.g. by returning early with
+    /// an error, without losing it.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use ttl_queue::TtlQueue;
+    /// fn process(job: &str) -> Result<(), String> {
+    ///     Err(format!("cannot process {job}"))
+    /// }
+    ///
+    /// let mut jobs = TtlQueue::new(Duration::from_secs(60));
+    /// jobs.push_back("resize image");
+    ///
+    /// if let Some(job) = jobs.pop_front_guarded() {
+    ///     if process(*job).is_ok() {
+    ///         job.commit();
+    ///     }
+    /// }
+    ///
+    /// assert_eq!(jobs.len(), 1);
+    /// ```
+    pub fn pop_front_guarded(&mut self) -> Option<PoppedGuard<'_, T>> {
+        let entry = self.pop_front()?;
+        Some(PoppedGuard {
+            queue: self,
+            entry: Some(entry),
+        })
+    }
+}
+
+impl<'a, T> PoppedGuard<'a, T> {
+    /// Gets the time instant at which the element was added.
+    pub fn instant(&self) -> Instant {
+        self.entry().0
+    }
+
+    /// Keeps the element removed from the queue and returns it, as well as the time
+    /// instant at which it was added.
+    pub fn commit(mut self) -> (Instant, T) {
+        self.entry.take().expect("the entry is only taken once")
+    }
+
+    fn entry(&self) -> &(Instant, T) {
+        self.entry.as_ref().expect("the entry is only taken once")
+    }
+}
+
+impl<'a, T> Deref for PoppedGuard<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.entry().1
+    }
+}
+
+impl<'a, T> DerefMut for PoppedGuard<'a, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.entry.as_mut().expect("the entry is only taken once").1
+    }
+}
+
+impl<'a, T> Drop for PoppedGuard<'a, T> {
+    fn drop(&mut self) {
+        if let Some((instant, element)) = self.entry.take() {
+            // Nothing can be pushed while the queue is borrowed, so the element
+            // is still older than the front of the queue.
+            let _ = self.queue.undo_pop(instant, element);
+        }
+    }
+}
+
+impl<'a, T: Debug> Debug for PoppedGuard<'a, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PoppedGuard")
+            .field("entry", &self.entry)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn dropped_guard_restores_front() {
+        let mut queue = TtlQueue::new(Duration::from_secs(60));
+        queue.push_back(1);
+        queue.push_back(2);
+        let (front, _) = *queue.front_entry().unwrap();
+
+        {
+            let mut guard = queue.pop_front_guarded().unwrap();
+            assert_eq!(guard.instant(), front);
+            *guard += 10;
+        }
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.front_entry(), Some(&(front, 11)));
+
+        let (instant, value) = queue.pop_front_guarded().unwrap().commit();
+        assert_eq!((instant, value), (front, 11));
+        assert_eq!(queue.len(), 1);
+
+        let (newest, _) = *queue.back_entry().unwrap();
+        let later = newest + Duration::from_secs(1);
+        assert_eq!(queue.undo_pop(later, 3), Err((later, 3)));
+    }
+}
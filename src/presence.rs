@@ -0,0 +1,191 @@
+use crate::{Instant, TtlQueue};
+use std::fmt::{Debug, Formatter};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, Weak};
+use std::time::Duration;
+
+/// A thread-safe queue of elements that are present while a [`PresenceGuard`] is held.
+///
+/// An element pushed via [`push_guard`](Self::push_guard) is removed when its guard is
+/// dropped or when its TTL elapses, whichever happens first. This models liveness
+/// indicators such as "user is typing", where the owner of an indicator removes it
+/// explicitly but a crashed or stalled owner must not keep it alive forever.
+///
+/// Cloning a `PresenceQueue` creates another handle to the same queue.
+///
+/// ## Example
+///
+/// ```
+/// # use std::time::Duration;
+/// # use ttl_queue::PresenceQueue;
+/// let typing = PresenceQueue::new(Duration::from_secs(5));
+///
+/// let alice = typing.push_guard("alice");
+/// let bob = typing.push_guard("bob");
+/// assert_eq!(typing.present(), ["alice", "bob"]);
+///
+/// drop(alice);
+/// assert_eq!(typing.present(), ["bob"]);
+/// # drop(bob);
+/// ```
+pub struct PresenceQueue<T> {
+    inner: Arc<Mutex<Presence<T>>>,
+}
+
+/// Removes its element from a [`PresenceQueue`] when dropped.
+///
+/// See [`PresenceQueue::push_guard`].
+#[must_use = "the element is removed as soon as the guard is dropped"]
+pub struct PresenceGuard<T> {
+    inner: Weak<Mutex<Presence<T>>>,
+    id: u64,
+}
+
+struct Presence<T> {
+    /// The elements along with the ID of their guard, in ascending order of IDs.
+    queue: TtlQueue<(u64, T)>,
+    next_id: u64,
+}
+
+impl<T> Presence<T> {
+    /// Gets the position of the element of the specified guard.
+    fn position(&self, id: u64) -> Option<usize> {
+        self.queue
+            .binary_search_by(|(_, (entry, _))| entry.cmp(&id))
+            .ok()
+    }
+}
+
+impl<T> PresenceQueue<T> {
+    /// Creates an empty [`PresenceQueue`].
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(Presence {
+                queue: TtlQueue::new(ttl),
+                next_id: 0,
+            })),
+        }
+    }
+
+    /// Pushes an element to the end of the queue and returns a guard that removes
+    /// it when dropped.
+    pub fn push_guard(&self, element: T) -> PresenceGuard<T> {
+        let mut presence = self.lock();
+        let id = presence.next_id;
+        presence.next_id += 1;
+        presence.queue.push_back((id, element));
+        PresenceGuard {
+            inner: Arc::downgrade(&self.inner),
+            id,
+        }
+    }
+
+    /// Gets the number of elements, including potentially expired elements.
+    pub fn len(&self) -> usize {
+        self.lock().queue.len()
+    }
+
+    /// Returns `true` if the queue is definitely empty or `false` if it is possibly empty.
+    pub fn is_empty(&self) -> bool {
+        self.lock().queue.is_empty()
+    }
+
+    /// Refreshes the queue and returns the number of present elements.
+    pub fn refresh(&self) -> usize {
+        self.lock().queue.refresh()
+    }
+
+    /// Refreshes the queue and returns the present elements, oldest first.
+    pub fn present(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        let mut presence = self.lock();
+        presence.queue.refresh();
+        presence
+            .queue
+            .iter()
+            .map(|(_, (_, element))| element.clone())
+            .collect()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Presence<T>> {
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<T> Clone for PresenceQueue<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T> Debug for PresenceQueue<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PresenceQueue")
+            .field("len", &self.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> PresenceGuard<T> {
+    /// Returns `true` if the element has not yet expired, or `false` if it has expired
+    /// or the queue was dropped.
+    pub fn is_present(&self) -> bool {
+        self.is_present_at(Instant::now())
+    }
+
+    fn is_present_at(&self, now: Instant) -> bool {
+        let Some(inner) = self.inner.upgrade() else {
+            return false;
+        };
+
+        let presence = inner.lock().unwrap_or_else(PoisonError::into_inner);
+        presence.position(self.id).is_some_and(|index| {
+            index < presence.queue.len() && !presence.queue.is_expired_at(index, now)
+        })
+    }
+}
+
+impl<T> Drop for PresenceGuard<T> {
+    fn drop(&mut self) {
+        let Some(inner) = self.inner.upgrade() else {
+            return;
+        };
+
+        let mut presence = inner.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(index) = presence.position(self.id) {
+            presence.queue.remove(index);
+        }
+    }
+}
+
+impl<T> Debug for PresenceGuard<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PresenceGuard")
+            .field("id", &self.id)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn guards_remove_their_element() {
+        let queue = PresenceQueue::new(Duration::from_secs(10));
+        let first = queue.push_guard(1);
+        let second = queue.push_guard(2);
+        let third = queue.push_guard(3);
+        assert!(second.is_present());
+
+        drop(second);
+        assert_eq!(queue.present(), [1, 3]);
+        assert!(!third.is_present_at(Instant::now() + Duration::from_secs(10)));
+
+        drop(queue);
+        assert!(!first.is_present());
+    }
+}
@@ -0,0 +1,192 @@
+use crate::Instant;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::fmt::{Debug, Formatter};
+use std::time::Duration;
+
+/// A priority queue whose elements expire after a given amount of time.
+///
+/// Elements are ordered by their priority rather than their age. Expired elements
+/// are never returned by [`pop_max`](Self::pop_max) or [`peek_max`](Self::peek_max);
+/// they are purged lazily once they reach the top of the heap, or all at once
+/// by a [`refresh`](Self::refresh).
+///
+/// ## Example
+///
+/// ```
+/// # use std::time::Duration;
+/// # use ttl_queue::{Instant, TtlPriorityQueue};
+/// # #[cfg(feature = "mock_instant")]
+/// # mock_instant::global::MockClock::set_time(Duration::from_secs(3600));
+/// let mut queue = TtlPriorityQueue::new(Duration::from_secs(10));
+/// let now = Instant::now();
+/// queue.push_at(now - Duration::from_secs(20), 9, "expired");
+/// queue.push_at(now, 1, "low");
+/// queue.push_at(now, 5, "high");
+///
+/// assert_eq!(queue.pop_max_at(now), Some((5, "high")));
+/// assert_eq!(queue.pop_max_at(now), Some((1, "low")));
+/// assert_eq!(queue.pop_max_at(now), None);
+/// ```
+pub struct TtlPriorityQueue<P, T> {
+    ttl: Duration,
+    heap: BinaryHeap<PriorityEntry<P, T>>,
+}
+
+/// An element stored in a [`TtlPriorityQueue`], ordered by priority only.
+struct PriorityEntry<P, T> {
+    priority: P,
+    instant: Instant,
+    value: T,
+}
+
+impl<P: Ord, T> PartialEq for PriorityEntry<P, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.priority == other.priority
+    }
+}
+
+impl<P: Ord, T> Eq for PriorityEntry<P, T> {}
+
+impl<P: Ord, T> PartialOrd for PriorityEntry<P, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<P: Ord, T> Ord for PriorityEntry<P, T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.priority.cmp(&other.priority)
+    }
+}
+
+impl<P: Ord, T> TtlPriorityQueue<P, T> {
+    /// Creates an empty [`TtlPriorityQueue`].
+    pub fn new(ttl: Duration) -> Self {
+        Self::with_capacity(ttl, 0)
+    }
+
+    /// Creates an empty [`TtlPriorityQueue`] for at least `capacity` elements.
+    pub fn with_capacity(ttl: Duration, capacity: usize) -> Self {
+        Self {
+            ttl,
+            heap: BinaryHeap::with_capacity(capacity),
+        }
+    }
+
+    /// Gets the time-to-live of the elements.
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Gets the number of elements in the queue, including potentially expired elements.
+    ///
+    /// In order to obtain an accurate count, use [`refresh`](Self::refresh) instead.
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    /// Returns `true` if the queue is definitely empty or `false` if it is possibly empty.
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    /// Pushes an element with the specified priority.
+    pub fn push(&mut self, priority: P, value: T) {
+        self.push_at(Instant::now(), priority, value)
+    }
+
+    /// Pushes an element with the specified priority, stamped with the specified instant.
+    pub fn push_at(&mut self, instant: Instant, priority: P, value: T) {
+        self.heap.push(PriorityEntry {
+            priority,
+            instant,
+            value,
+        });
+    }
+
+    /// Removes the unexpired element with the highest priority and returns it.
+    pub fn pop_max(&mut self) -> Option<(P, T)> {
+        self.pop_max_at(Instant::now())
+    }
+
+    /// Removes the element with the highest priority that is unexpired at the
+    /// specified instant and returns it.
+    pub fn pop_max_at(&mut self, now: Instant) -> Option<(P, T)> {
+        self.purge_top(now);
+        self.heap.pop().map(|entry| (entry.priority, entry.value))
+    }
+
+    /// Gets the unexpired element with the highest priority without removing it.
+    ///
+    /// Expired elements on top of the heap are removed.
+    pub fn peek_max(&mut self) -> Option<(&P, &T)> {
+        self.purge_top(Instant::now());
+        self.heap
+            .peek()
+            .map(|entry| (&entry.priority, &entry.value))
+    }
+
+    /// Refreshes the queue and returns the number of currently contained elements.
+    ///
+    /// This operation is O(N).
+    pub fn refresh(&mut self) -> usize {
+        self.refresh_at(Instant::now())
+    }
+
+    /// Refreshes the queue as of the specified instant and returns the number of
+    /// currently contained elements.
+    pub fn refresh_at(&mut self, now: Instant) -> usize {
+        let ttl = self.ttl;
+        self.heap
+            .retain(|entry| now.saturating_duration_since(entry.instant) < ttl);
+        self.heap.len()
+    }
+
+    /// Returns an iterator over the elements in arbitrary order, including
+    /// potentially expired elements.
+    pub fn iter(&self) -> impl Iterator<Item = (Instant, &P, &T)> {
+        self.heap
+            .iter()
+            .map(|entry| (entry.instant, &entry.priority, &entry.value))
+    }
+
+    /// Removes expired elements from the top of the heap.
+    fn purge_top(&mut self, now: Instant) {
+        while let Some(entry) = self.heap.peek() {
+            if now.saturating_duration_since(entry.instant) < self.ttl {
+                break;
+            }
+            self.heap.pop();
+        }
+    }
+}
+
+impl<P: Debug, T: Debug> Debug for TtlPriorityQueue<P, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TtlPriorityQueue")
+            .field("ttl", &self.ttl)
+            .field("len", &self.heap.len())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(all(test, not(feature = "mock_instant")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn refresh_purges_buried_expired_elements() {
+        let mut queue = TtlPriorityQueue::new(Duration::from_secs(10));
+        let now = Instant::now();
+        queue.push_at(now, 5, "fresh");
+        queue.push_at(now - Duration::from_secs(20), 1, "expired");
+        queue.push_at(now - Duration::from_secs(5), 3, "older");
+
+        assert_eq!(queue.len(), 3);
+        assert_eq!(queue.refresh_at(now), 2);
+        assert_eq!(queue.pop_max_at(now), Some((5, "fresh")));
+        assert_eq!(queue.pop_max_at(now + Duration::from_secs(6)), None);
+        assert!(queue.is_empty());
+    }
+}
@@ -0,0 +1,107 @@
+use crate::{TtlQueue, TtlReceiver, TtlSender};
+
+/// The producing half of a queue turned into a [single-producer](TtlQueue::into_producer)
+/// channel, stamping elements with the time of the push.
+///
+/// Unlike a [`TtlSender`], a producer cannot be cloned, so the elements always arrive
+/// in the order of their timestamps.
+#[derive(Debug)]
+pub struct TtlProducer<T> {
+    sender: TtlSender<T>,
+}
+
+impl<T> TtlQueue<T> {
+    /// Turns the queue into the consuming half of a channel fed by a single
+    /// [`TtlProducer`], e.g. for a pipeline with one thread on each side.
+    ///
+    /// This is a convenience over [`into_channel`](Self::into_channel) that rules out
+    /// additional producers; the halves are connected by the same [`std::sync::mpsc`]
+    /// channel, so the producer never waits for the receiver. Elements are stamped with
+    /// the clock of this queue when pushed and move into the timed window whenever the
+    /// [`TtlReceiver`] accesses it. The receiver keeps the elements and the configuration
+    /// of this queue.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use std::thread;
+    /// # use std::time::Duration;
+    /// # use ttl_queue::TtlQueue;
+    /// let (producer, mut receiver) = TtlQueue::new(Duration::from_secs(60)).into_producer();
+    ///
+    /// let pipeline = thread::spawn(move || {
+    ///     for frame in 0..100 {
+    ///         producer.push_back(frame).unwrap();
+    ///     }
+    /// });
+    ///
+    /// let mut received = 0;
+    /// loop {
+    ///     let closed = receiver.is_closed();
+    ///     while let Some((_instant, frame)) = receiver.pop_front() {
+    ///         assert_eq!(frame, received);
+    ///         received += 1;
+    ///     }
+    ///     if closed {
+    ///         break;
+    ///     }
+    /// }
+    ///
+    /// pipeline.join().unwrap();
+    /// assert_eq!(received, 100);
+    /// ```
+    pub fn into_producer(self) -> (TtlProducer<T>, TtlReceiver<T>) {
+        let (sender, receiver) = self.into_channel();
+        (TtlProducer { sender }, receiver)
+    }
+}
+
+impl<T> TtlProducer<T> {
+    /// Pushes an element to the end of the queue, stamped with the current time.
+    ///
+    /// Returns the element as an error if the [`TtlReceiver`] was dropped.
+    pub fn push_back(&self, element: T) -> Result<(), T> {
+        self.sender.push_back(element)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TickClock;
+
+    #[test]
+    fn elements_are_stamped_by_the_producer() {
+        let clock = TickClock::new();
+        let mut queue = TtlQueue::new(TickClock::ttl(10));
+        queue.set_clock(clock.clone());
+        queue.push_back(0);
+        queue.on_push(|value| (value % 3 != 0).then_some(value));
+
+        let (producer, mut receiver) = queue.into_producer();
+        for value in 1..=6 {
+            clock.tick();
+            producer.push_back(value).unwrap();
+        }
+        assert_eq!(receiver.queue().len(), 1);
+        assert_eq!(receiver.drain_pending(), 4);
+
+        clock.advance(6);
+        assert!(!receiver.is_closed());
+        assert_eq!(receiver.refresh(), 2);
+        let values: Vec<_> = receiver.queue().iter().map(|(_, value)| *value).collect();
+        assert_eq!(values, [4, 5]);
+
+        drop(producer);
+        assert!(receiver.is_closed());
+        let mut queue = receiver.into_queue();
+        assert_eq!(queue.pop_front().map(|(_, value)| value), Some(4));
+    }
+
+    #[test]
+    fn push_fails_without_receiver() {
+        let (producer, receiver) = TtlQueue::new(TickClock::ttl(10)).into_producer();
+        drop(receiver);
+        assert_eq!(producer.push_back(42), Err(42));
+    }
+}
@@ -0,0 +1,306 @@
+use crate::{Clock, Instant, TtlQueue};
+use ::governor::clock::Clock as GovernorClockTrait;
+use ::governor::middleware::NoOpMiddleware;
+use ::governor::nanos::Nanos;
+use ::governor::state::keyed::ShrinkableKeyedStateStore;
+use ::governor::state::{RateLimiter, StateStore};
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::hash::Hash;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+
+/// A [`governor`] clock reading the time from a [`Clock`] of this crate, so that a
+/// rate limiter and a [`TtlQueue`] observe the same time, e.g. a [`TickClock`](crate::TickClock)
+/// in tests.
+///
+/// Instants are measured in nanoseconds since the clock adapter was created.
+#[derive(Clone)]
+pub struct GovernorClock {
+    clock: Arc<dyn Clock>,
+    origin: Instant,
+}
+
+impl GovernorClock {
+    /// Creates a [`governor`] clock reading the time from the specified clock.
+    pub fn new(clock: impl Clock + 'static) -> Self {
+        Self::from_shared(Arc::new(clock))
+    }
+
+    fn from_shared(clock: Arc<dyn Clock>) -> Self {
+        Self {
+            origin: clock.now(),
+            clock,
+        }
+    }
+}
+
+impl GovernorClockTrait for GovernorClock {
+    type Instant = Nanos;
+
+    fn now(&self) -> Nanos {
+        self.clock
+            .now()
+            .saturating_duration_since(self.origin)
+            .into()
+    }
+}
+
+impl Debug for GovernorClock {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GovernorClock")
+            .field("origin", &self.origin)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> TtlQueue<T> {
+    /// Gets a [`governor`] clock sharing the clock of this queue.
+    pub fn governor_clock(&self) -> GovernorClock {
+        GovernorClock::from_shared(self.clock.clone())
+    }
+}
+
+/// A keyed [`governor`] rate limiter keeping its state in a [`TtlStateStore`] and
+/// reading the time from a [`GovernorClock`].
+pub type TtlRateLimiter<K> = RateLimiter<K, TtlStateStore<K>, GovernorClock, NoOpMiddleware<Nanos>>;
+
+/// A keyed [`governor`] state store that forgets the rate limiting state of keys that
+/// were not checked within a TTL, so that the memory of a keyed rate limiter is bounded
+/// by the number of recently active keys instead of all keys ever seen.
+///
+/// The TTL should be at least the time a key needs to replenish its full burst;
+/// otherwise forgetting a key resets its limit early.
+///
+/// ## Example
+///
+/// ```
+/// # use std::num::NonZeroU32;
+/// # use governor::{Quota, RateLimiter};
+/// # use ttl_queue::{GovernorClock, TickClock, TtlRateLimiter, TtlStateStore};
+/// let clock = TickClock::new();
+/// let mut store = TtlStateStore::new(TickClock::ttl(100));
+/// store.set_clock(clock.clone());
+///
+/// let quota = Quota::with_period(TickClock::ttl(10))
+///     .unwrap()
+///     .allow_burst(NonZeroU32::new(2).unwrap());
+/// let limiter: TtlRateLimiter<_> =
+///     RateLimiter::new(quota, store, &GovernorClock::new(clock.clone()));
+///
+/// assert!(limiter.check_key(&"alice").is_ok());
+/// assert!(limiter.check_key(&"alice").is_ok());
+/// assert!(limiter.check_key(&"alice").is_err());
+/// assert!(limiter.check_key(&"bob").is_ok());
+///
+/// clock.advance(10);
+/// assert!(limiter.check_key(&"alice").is_ok());
+/// ```
+pub struct TtlStateStore<K> {
+    inner: Mutex<KeyStates<K>>,
+}
+
+struct KeyStates<K> {
+    /// The rate limiting state of each key, along with the time it was last updated.
+    states: HashMap<K, (Instant, Nanos)>,
+    /// The keys in the order they were updated. Earlier updates of a key are
+    /// superseded by its latest update and dropped by [`compact`](Self::compact).
+    updates: TtlQueue<K>,
+}
+
+impl<K: Hash + Eq> KeyStates<K> {
+    /// Forgets the keys whose last update expired.
+    fn evict(&mut self) {
+        let now = self.updates.now();
+        let ttl = self.updates.ttl();
+        while let Some((instant, _)) = self.updates.front_entry() {
+            if now.saturating_duration_since(*instant) < ttl {
+                break;
+            }
+
+            if let Some((instant, key)) = self.updates.pop_front_entry() {
+                if self
+                    .states
+                    .get(&key)
+                    .is_some_and(|(updated, _)| *updated == instant)
+                {
+                    self.states.remove(&key);
+                }
+            }
+        }
+    }
+
+    /// Records that the key was updated at the specified instant.
+    fn update(&mut self, now: Instant, key: K, state: Nanos)
+    where
+        K: Clone,
+    {
+        let previous = self.states.insert(key.clone(), (now, state));
+        if previous.is_some_and(|(updated, _)| updated == now) {
+            return;
+        }
+
+        self.updates.push_back_entry(now, key);
+        if self.updates.len() > 2 * self.states.len() {
+            self.compact();
+        }
+    }
+
+    /// Drops the superseded updates, so that every key is held once.
+    fn compact(&mut self) {
+        let mut latest = Vec::with_capacity(self.states.len());
+        while let Some((instant, key)) = self.updates.pop_front_entry() {
+            if self
+                .states
+                .get(&key)
+                .is_some_and(|(updated, _)| *updated == instant)
+            {
+                latest.push((instant, key));
+            }
+        }
+        for (instant, key) in latest {
+            self.updates.push_back_entry(instant, key);
+        }
+    }
+}
+
+impl<K: Hash + Eq> TtlStateStore<K> {
+    /// Creates an empty [`TtlStateStore`] forgetting keys not checked within `ttl`.
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            inner: Mutex::new(KeyStates {
+                states: HashMap::new(),
+                updates: TtlQueue::new(ttl),
+            }),
+        }
+    }
+
+    /// Gets the time after which idle keys are forgotten.
+    pub fn ttl(&self) -> Duration {
+        self.lock().updates.ttl()
+    }
+
+    /// Sets the clock used to determine when keys were last checked.
+    ///
+    /// See [`TtlQueue::set_clock`].
+    pub fn set_clock(&mut self, clock: impl Clock + 'static) {
+        self.inner
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+            .updates
+            .set_clock(clock);
+    }
+
+    /// Gets the number of keys with a rate limiting state, forgetting idle keys first.
+    pub fn len(&self) -> usize {
+        let mut inner = self.lock();
+        inner.evict();
+        inner.states.len()
+    }
+
+    /// Returns `true` if no key has a rate limiting state.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn lock(&self) -> MutexGuard<'_, KeyStates<K>> {
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<K: Hash + Eq + Clone> StateStore for TtlStateStore<K> {
+    type Key = K;
+
+    fn measure_and_replace<T, F, E>(&self, key: &K, f: F) -> Result<T, E>
+    where
+        F: Fn(Option<Nanos>) -> Result<(T, Nanos), E>,
+    {
+        let mut inner = self.lock();
+        inner.evict();
+
+        let previous = inner.states.get(key).map(|(_, state)| *state);
+        let (result, state) = f(previous)?;
+
+        let now = inner.updates.now();
+        inner.update(now, key.clone(), state);
+        Ok(result)
+    }
+}
+
+impl<K: Hash + Eq + Clone> ShrinkableKeyedStateStore<K> for TtlStateStore<K> {
+    fn retain_recent(&self, drop_below: Nanos) {
+        self.lock()
+            .states
+            .retain(|_, (_, state)| *state > drop_below);
+    }
+
+    fn len(&self) -> usize {
+        TtlStateStore::len(self)
+    }
+
+    fn is_empty(&self) -> bool {
+        TtlStateStore::is_empty(self)
+    }
+}
+
+impl<K: Hash + Eq> Debug for TtlStateStore<K> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let inner = self.lock();
+        f.debug_struct("TtlStateStore")
+            .field("ttl", &inner.updates.ttl())
+            .field("keys", &inner.states.len())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TickClock;
+    use ::governor::Quota;
+    use std::num::NonZeroU32;
+
+    #[test]
+    fn idle_keys_are_forgotten() {
+        let clock = TickClock::new();
+        let mut store = TtlStateStore::new(TickClock::ttl(50));
+        store.set_clock(clock.clone());
+
+        let quota = Quota::with_period(TickClock::ttl(10))
+            .unwrap()
+            .allow_burst(NonZeroU32::new(1).unwrap());
+        let limiter: TtlRateLimiter<_> =
+            RateLimiter::new(quota, store, &GovernorClock::new(clock.clone()));
+
+        assert!(limiter.check_key(&1).is_ok());
+        clock.advance(30);
+        assert!(limiter.check_key(&2).is_ok());
+        assert!(limiter.check_key(&2).is_err());
+        clock.advance(30);
+        assert!(limiter.check_key(&3).is_ok());
+
+        // Key 1 was idle for 60 ticks, key 2 for 30 ticks.
+        assert_eq!(limiter.len(), 2);
+    }
+
+    #[test]
+    fn hot_keys_are_held_once() {
+        let clock = TickClock::new();
+        let mut store = TtlStateStore::new(TickClock::ttl(1000));
+        store.set_clock(clock.clone());
+
+        let quota = Quota::with_period(TickClock::ttl(1)).unwrap();
+        let limiter: TtlRateLimiter<_> =
+            RateLimiter::new(quota, store, &GovernorClock::new(clock.clone()));
+
+        for _ in 0..500 {
+            clock.tick();
+            assert!(limiter.check_key(&"hot").is_ok());
+            assert!(limiter.check_key(&"warm").is_ok());
+        }
+
+        let store = limiter.into_state_store();
+        assert_eq!(store.len(), 2);
+        assert!(store.lock().updates.len() <= 4);
+    }
+}
@@ -0,0 +1,308 @@
+use crate::{Instant, TickClock, TtlQueue};
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// A log of pushed elements, each stamped with its offset from the start of the
+/// recording, oldest first.
+///
+/// Recordings are captured by a [`RecordingTtlQueue`] and fed back by a [`Replayer`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recording<T> {
+    pushes: Vec<(Duration, T)>,
+}
+
+impl<T> Recording<T> {
+    /// Gets the number of recorded pushes.
+    pub fn len(&self) -> usize {
+        self.pushes.len()
+    }
+
+    /// Returns `true` if no pushes were recorded.
+    pub fn is_empty(&self) -> bool {
+        self.pushes.is_empty()
+    }
+
+    /// Gets the offset of the last recorded push, or zero if there is none.
+    pub fn duration(&self) -> Duration {
+        self.pushes
+            .last()
+            .map_or(Duration::ZERO, |(offset, _)| *offset)
+    }
+
+    /// Returns an iterator over the recorded pushes, oldest first.
+    pub fn iter(&self) -> std::slice::Iter<'_, (Duration, T)> {
+        self.pushes.iter()
+    }
+
+    /// Creates a [`Replayer`] feeding the recorded pushes into a queue with the
+    /// specified TTL.
+    pub fn replay(self, ttl: Duration) -> Replayer<T> {
+        Replayer::new(self, ttl)
+    }
+}
+
+impl<T> Default for Recording<T> {
+    fn default() -> Self {
+        Self { pushes: Vec::new() }
+    }
+}
+
+/// Creates a recording from pushes, e.g. loaded from disk. The pushes are ordered
+/// by their offsets, retaining the order of pushes with equal offsets.
+impl<T> From<Vec<(Duration, T)>> for Recording<T> {
+    fn from(mut pushes: Vec<(Duration, T)>) -> Self {
+        pushes.sort_by_key(|(offset, _)| *offset);
+        Self { pushes }
+    }
+}
+
+/// Converts the recording into its pushes, oldest first, e.g. to store them.
+impl<T> From<Recording<T>> for Vec<(Duration, T)> {
+    fn from(recording: Recording<T>) -> Self {
+        recording.pushes
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Recording<T> {
+    type Item = &'a (Duration, T);
+    type IntoIter = std::slice::Iter<'a, (Duration, T)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// A [`TtlQueue`] recording every push along with its time, so that TTL-dependent
+/// logic can later be re-run deterministically by a [`Replayer`].
+///
+/// ## Example
+///
+/// ```
+/// # use std::time::Duration;
+/// # use ttl_queue::RecordingTtlQueue;
+/// let mut queue = RecordingTtlQueue::new(Duration::from_secs(60));
+/// queue.push_back("login");
+/// queue.push_back("logout");
+///
+/// let recording = queue.into_recording();
+/// assert_eq!(recording.len(), 2);
+///
+/// let mut replayer = recording.replay(Duration::from_secs(60));
+/// assert_eq!(replayer.run(), 2);
+/// assert_eq!(replayer.queue().len(), 2);
+/// ```
+#[derive(Debug)]
+pub struct RecordingTtlQueue<T> {
+    queue: TtlQueue<T>,
+    started: Instant,
+    recording: Recording<T>,
+}
+
+impl<T: Clone> RecordingTtlQueue<T> {
+    /// Creates an empty [`RecordingTtlQueue`] and starts the recording.
+    pub fn new(ttl: Duration) -> Self {
+        let queue = TtlQueue::new(ttl);
+        Self {
+            started: queue.now(),
+            queue,
+            recording: Recording::default(),
+        }
+    }
+
+    /// Gets the underlying queue.
+    pub fn queue(&self) -> &TtlQueue<T> {
+        &self.queue
+    }
+
+    /// Gets the number of elements, including potentially expired elements.
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Returns `true` if the queue is definitely empty or `false` if it is possibly empty.
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// Pushes an element to the end of the queue and records the push.
+    pub fn push_back(&mut self, element: T) {
+        let offset = self.queue.now().saturating_duration_since(self.started);
+        self.recording.pushes.push((offset, element.clone()));
+        self.queue.push_back(element);
+    }
+
+    /// Removes the element from the front of the queue and returns it, as well as the
+    /// time instant at which it was added.
+    pub fn pop_front(&mut self) -> Option<(Instant, T)> {
+        self.queue.pop_front()
+    }
+
+    /// Refreshes the queue and returns the number of currently contained elements.
+    pub fn refresh(&mut self) -> usize {
+        self.queue.refresh()
+    }
+
+    /// Gets the pushes recorded so far.
+    pub fn recording(&self) -> &Recording<T> {
+        &self.recording
+    }
+
+    /// Stops the recording and returns the recorded pushes.
+    pub fn into_recording(self) -> Recording<T> {
+        self.recording
+    }
+}
+
+/// Feeds a [`Recording`] into a [`TtlQueue`] driven by a [`TickClock`], reproducing
+/// the recorded timing exactly regardless of how fast the replay runs.
+///
+/// The clock only advances as the replay progresses; one tick corresponds to one
+/// nanosecond of the recording. [Listeners](TtlQueue::on_expired) or a
+/// [refresh policy](TtlQueue::set_refresh_policy) can be configured through
+/// [`queue_mut`](Self::queue_mut) before replaying.
+///
+/// ## Example
+///
+/// ```
+/// # use std::time::Duration;
+/// # use ttl_queue::Recording;
+/// let recording = Recording::from(vec![
+///     (Duration::from_secs(0), "a"),
+///     (Duration::from_secs(20), "b"),
+///     (Duration::from_secs(50), "c"),
+/// ]);
+///
+/// let mut replayer = recording.replay(Duration::from_secs(30));
+/// assert_eq!(replayer.advance(Duration::from_secs(25)), 2);
+/// assert_eq!(replayer.queue_mut().refresh(), 2);
+///
+/// assert_eq!(replayer.step(), Some(Duration::from_secs(50)));
+/// assert_eq!(replayer.queue_mut().refresh(), 1);
+/// assert!(replayer.is_finished());
+/// ```
+#[derive(Debug)]
+pub struct Replayer<T> {
+    queue: TtlQueue<T>,
+    clock: TickClock,
+    pushes: VecDeque<(Duration, T)>,
+}
+
+impl<T> Replayer<T> {
+    /// Creates a replayer feeding the recording into a queue with the specified TTL.
+    ///
+    /// The replay starts at the beginning of the recording, without any pushes replayed.
+    pub fn new(recording: Recording<T>, ttl: Duration) -> Self {
+        let clock = TickClock::new();
+        let mut queue = TtlQueue::new(ttl);
+        queue.set_clock(clock.clone());
+        Self {
+            queue,
+            clock,
+            pushes: recording.pushes.into(),
+        }
+    }
+
+    /// Gets the queue the recording is replayed into.
+    pub fn queue(&self) -> &TtlQueue<T> {
+        &self.queue
+    }
+
+    /// Gets the queue the recording is replayed into mutably.
+    pub fn queue_mut(&mut self) -> &mut TtlQueue<T> {
+        &mut self.queue
+    }
+
+    /// Gets the clock driving the queue.
+    pub fn clock(&self) -> &TickClock {
+        &self.clock
+    }
+
+    /// Gets how far the replay has progressed into the recording.
+    pub fn elapsed(&self) -> Duration {
+        TickClock::ttl(self.clock.ticks())
+    }
+
+    /// Gets the number of pushes not replayed yet.
+    pub fn remaining(&self) -> usize {
+        self.pushes.len()
+    }
+
+    /// Returns `true` if all pushes were replayed.
+    pub fn is_finished(&self) -> bool {
+        self.pushes.is_empty()
+    }
+
+    /// Advances the clock to the next push and replays it.
+    ///
+    /// Returns the offset of the push, or `None` if the replay is finished.
+    pub fn step(&mut self) -> Option<Duration> {
+        let (offset, element) = self.pushes.pop_front()?;
+        self.advance_clock_to(offset);
+        self.queue.push_back(element);
+        Some(offset)
+    }
+
+    /// Advances the clock by the specified duration, replaying the pushes recorded
+    /// within it, and returns the number of replayed pushes.
+    pub fn advance(&mut self, duration: Duration) -> usize {
+        let until = self.elapsed().saturating_add(duration);
+        let mut count = 0;
+        while self
+            .pushes
+            .front()
+            .is_some_and(|(offset, _)| *offset <= until)
+        {
+            self.step();
+            count += 1;
+        }
+        self.advance_clock_to(until);
+        count
+    }
+
+    /// Replays all remaining pushes and returns their number.
+    pub fn run(&mut self) -> usize {
+        let mut count = 0;
+        while self.step().is_some() {
+            count += 1;
+        }
+        count
+    }
+
+    fn advance_clock_to(&self, offset: Duration) {
+        let target = u64::try_from(offset.as_nanos()).unwrap_or(u64::MAX);
+        let ticks = self.clock.ticks();
+        if target > ticks {
+            self.clock.advance(target - ticks);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replay_reproduces_expiry() {
+        let recording = Recording::from(vec![
+            (Duration::from_millis(30), 3),
+            (Duration::from_millis(10), 1),
+            (Duration::from_millis(20), 2),
+        ]);
+        let mut replayer = Replayer::new(recording.clone(), Duration::from_millis(15));
+
+        let expired = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = expired.clone();
+        replayer
+            .queue_mut()
+            .on_expired(move |_, value| sink.lock().unwrap().push(*value));
+
+        assert_eq!(replayer.advance(Duration::from_millis(20)), 2);
+        assert_eq!(replayer.elapsed(), Duration::from_millis(20));
+        assert_eq!(replayer.queue_mut().refresh(), 2);
+
+        assert_eq!(replayer.advance(Duration::from_millis(30)), 1);
+        assert_eq!(replayer.queue_mut().refresh(), 0);
+        assert_eq!(*expired.lock().unwrap(), [1, 2, 3]);
+        assert_eq!(recording.duration(), Duration::from_millis(30));
+    }
+}
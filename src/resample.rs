@@ -0,0 +1,118 @@
+use crate::{Instant, TtlQueue};
+use std::time::Duration;
+
+/// Determines how [`TtlQueue::resample`] derives values between samples.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Interpolation {
+    /// Uses the value of the sample closest in time, preferring the earlier one on ties.
+    Nearest,
+    /// Interpolates linearly between the samples before and after.
+    #[default]
+    Linear,
+}
+
+impl<T: Copy + Into<f64>> TtlQueue<T> {
+    /// Resamples the unexpired elements at regular time steps, e.g. to feed irregularly
+    /// timed samples into a fixed-rate filter.
+    ///
+    /// The first step is at the timestamp of the oldest element and the steps continue
+    /// every `interval` up to the timestamp of the newest element.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `interval` is zero.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use ttl_queue::{Instant, Interpolation, TtlQueue};
+    /// let now = Instant::now();
+    /// let samples = vec![
+    ///     (now, 0.0),
+    ///     (now + Duration::from_secs(4), 4.0),
+    ///     (now + Duration::from_secs(5), 6.0),
+    /// ];
+    /// let mut queue: TtlQueue<f64> = TtlQueue::try_from(samples).unwrap();
+    /// queue.set_ttl(Duration::from_secs(3600));
+    ///
+    /// let values = |interpolation| -> Vec<f64> {
+    ///     queue
+    ///         .resample(Duration::from_secs(1), interpolation)
+    ///         .into_iter()
+    ///         .map(|(_, value)| value)
+    ///         .collect()
+    /// };
+    ///
+    /// assert_eq!(values(Interpolation::Linear), [0.0, 1.0, 2.0, 3.0, 4.0, 6.0]);
+    /// assert_eq!(values(Interpolation::Nearest), [0.0, 0.0, 0.0, 4.0, 4.0, 6.0]);
+    /// ```
+    pub fn resample(
+        &self,
+        interval: Duration,
+        interpolation: Interpolation,
+    ) -> Vec<(Instant, f64)> {
+        assert!(!interval.is_zero(), "The interval must not be zero");
+        let samples: Vec<(Instant, f64)> = self
+            .iter_valid()
+            .map(|(instant, value)| (*instant, (*value).into()))
+            .collect();
+
+        let (Some(first), Some(last)) = (samples.first(), samples.last()) else {
+            return Vec::new();
+        };
+
+        let mut resampled = Vec::new();
+        let mut index = 0;
+        let mut step = Some(first.0);
+        while let Some(now) = step.filter(|now| *now <= last.0) {
+            while samples
+                .get(index + 1)
+                .is_some_and(|(instant, _)| *instant <= now)
+            {
+                index += 1;
+            }
+
+            let (before, value) = samples[index];
+            let value = match samples.get(index + 1) {
+                Some((after, next)) if now > before => {
+                    let offset = now.duration_since(before).as_secs_f64();
+                    let span = after.duration_since(before).as_secs_f64();
+                    match interpolation {
+                        Interpolation::Linear => value + (next - value) * offset / span,
+                        Interpolation::Nearest if offset * 2.0 > span => *next,
+                        Interpolation::Nearest => value,
+                    }
+                }
+                _ => value,
+            };
+
+            resampled.push((now, value));
+            step = now.checked_add(interval);
+        }
+        resampled
+    }
+}
+
+#[cfg(all(test, not(feature = "mock_instant")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expired_samples_are_ignored() {
+        let now = Instant::now();
+        let mut queue = TtlQueue::new(Duration::from_secs(10));
+        queue.push_back_entry(now - Duration::from_secs(20), 100u8);
+        queue.push_back_entry(now - Duration::from_secs(2), 10);
+        queue.push_back_entry(now, 20);
+
+        let resampled = queue.resample(Duration::from_millis(1500), Interpolation::Linear);
+        assert_eq!(
+            resampled,
+            [
+                (now - Duration::from_secs(2), 10.0),
+                (now - Duration::from_millis(500), 17.5)
+            ]
+        );
+    }
+}
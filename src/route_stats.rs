@@ -0,0 +1,437 @@
+use crate::{Clock, Instant, SystemClock, TtlQueue};
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::future::Future;
+use std::hash::Hash;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// Rolling-window request counts and latencies per route, shared between a
+/// [`RequestWindowLayer`] and the code reporting the statistics.
+///
+/// Recording a request removes the expired requests of its route, so the memory held
+/// per route is bounded by the requests within the window. Routes that no longer
+/// receive requests are only removed by [`refresh`](Self::refresh).
+///
+/// Cloning the statistics is cheap; all clones refer to the same windows.
+pub struct RouteStats<K> {
+    inner: Arc<Mutex<RouteWindows<K>>>,
+}
+
+struct RouteWindows<K> {
+    ttl: Duration,
+    clock: Arc<dyn Clock>,
+    /// The latencies of the completed requests of each route.
+    routes: HashMap<K, TtlQueue<Duration>>,
+}
+
+impl<K: Hash + Eq> RouteStats<K> {
+    /// Creates empty statistics covering the requests completed within `ttl`.
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(RouteWindows {
+                ttl,
+                clock: Arc::new(SystemClock),
+                routes: HashMap::new(),
+            })),
+        }
+    }
+
+    /// Gets the duration covered by the windows.
+    pub fn ttl(&self) -> Duration {
+        self.lock().ttl
+    }
+
+    /// Sets the clock used to measure latencies and expire requests.
+    ///
+    /// See [`TtlQueue::set_clock`].
+    pub fn set_clock(&self, clock: impl Clock + 'static) {
+        let mut inner = self.lock();
+        let clock: Arc<dyn Clock> = Arc::new(clock);
+        for queue in inner.routes.values_mut() {
+            queue.set_shared_clock(clock.clone());
+        }
+        inner.clock = clock;
+    }
+
+    /// Gets the number of requests to the route completed within the window.
+    pub fn request_count<Q>(&self, route: &Q) -> usize
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.lock().routes.get(route).map_or(0, TtlQueue::valid_len)
+    }
+
+    /// Gets the latencies of the requests to the route completed within the window,
+    /// oldest first.
+    pub fn latencies<Q>(&self, route: &Q) -> Vec<Duration>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.lock()
+            .routes
+            .get(route)
+            .map_or_else(Vec::new, |queue| {
+                queue.iter_valid().map(|(_, latency)| *latency).collect()
+            })
+    }
+
+    /// Gets the mean latency of the requests to the route completed within the window,
+    /// or `None` if there are none.
+    pub fn mean_latency<Q>(&self, route: &Q) -> Option<Duration>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let latencies = self.latencies(route);
+        let count = u32::try_from(latencies.len())
+            .ok()
+            .filter(|&count| count > 0)?;
+        Some(latencies.iter().sum::<Duration>() / count)
+    }
+
+    /// Gets the maximum latency of the requests to the route completed within the
+    /// window, or `None` if there are none.
+    pub fn max_latency<Q>(&self, route: &Q) -> Option<Duration>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.latencies(route).into_iter().max()
+    }
+
+    /// Gets the routes with requests completed within the window.
+    pub fn routes(&self) -> Vec<K>
+    where
+        K: Clone,
+    {
+        self.lock()
+            .routes
+            .iter()
+            .filter(|(_, queue)| queue.valid_len() > 0)
+            .map(|(route, _)| route.clone())
+            .collect()
+    }
+
+    /// Removes the expired requests as well as routes without remaining requests, and
+    /// returns the total number of remaining requests.
+    pub fn refresh(&self) -> usize {
+        let mut inner = self.lock();
+        let now = inner.clock.now();
+        let mut count = 0;
+        inner.routes.retain(|_, queue| {
+            let remaining = queue.refresh_at(now);
+            count += remaining;
+            remaining > 0
+        });
+        count
+    }
+
+    /// Records a request to the route that started at the specified instant, removing
+    /// the expired requests of the route.
+    fn record(&self, route: K, started: Instant) {
+        let mut inner = self.lock();
+        let now = inner.clock.now();
+        let RouteWindows { ttl, clock, routes } = &mut *inner;
+        let queue = routes.entry(route).or_insert_with(|| {
+            let mut queue = TtlQueue::new(*ttl);
+            queue.set_shared_clock(clock.clone());
+            queue
+        });
+        queue.refresh_at(now);
+        queue.push_back_entry(now, now.saturating_duration_since(started));
+    }
+}
+
+impl<K> RouteStats<K> {
+    fn now(&self) -> Instant {
+        self.lock().clock.now()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, RouteWindows<K>> {
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<K> Clone for RouteStats<K> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<K> Debug for RouteStats<K> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let inner = self.lock();
+        f.debug_struct("RouteStats")
+            .field("ttl", &inner.ttl)
+            .field("routes", &inner.routes.len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// A [`Layer`] recording the count and latency of requests per route into [`RouteStats`],
+/// e.g. to add rolling-window statistics to `axum` or `hyper` services.
+///
+/// The route of a request is determined by a closure, e.g. from the method and path
+/// of an HTTP request. Requests are recorded when their response future completes,
+/// regardless of whether it failed.
+///
+/// ## Example
+///
+/// ```
+/// # use std::convert::Infallible;
+/// # use std::future::{ready, Ready};
+/// # use std::task::{Context, Poll};
+/// # use std::time::Duration;
+/// # use tower_layer::Layer;
+/// # use tower_service::Service;
+/// # use ttl_queue::{RequestWindowLayer, RouteStats};
+/// struct Echo;
+///
+/// impl Service<String> for Echo {
+///     type Response = String;
+///     type Error = Infallible;
+///     type Future = Ready<Result<String, Infallible>>;
+///
+///     fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+///         Poll::Ready(Ok(()))
+///     }
+///
+///     fn call(&mut self, request: String) -> Self::Future {
+///         ready(Ok(request))
+///     }
+/// }
+///
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// let stats = RouteStats::new(Duration::from_secs(60));
+/// let layer = RequestWindowLayer::new(stats.clone(), |path: &String| path.clone());
+/// let mut service = layer.layer(Echo);
+///
+/// service.call("/health".to_string()).await.unwrap();
+/// service.call("/health".to_string()).await.unwrap();
+/// service.call("/users".to_string()).await.unwrap();
+///
+/// assert_eq!(stats.request_count("/health"), 2);
+/// assert_eq!(stats.request_count("/users"), 1);
+/// # }
+/// ```
+pub struct RequestWindowLayer<K, F> {
+    stats: RouteStats<K>,
+    route: F,
+}
+
+impl<K, F> RequestWindowLayer<K, F> {
+    /// Creates a layer recording requests into the specified statistics, keyed by the
+    /// route determined by the closure.
+    pub fn new(stats: RouteStats<K>, route: F) -> Self {
+        Self { stats, route }
+    }
+
+    /// Gets the statistics the requests are recorded into.
+    pub fn stats(&self) -> &RouteStats<K> {
+        &self.stats
+    }
+}
+
+impl<S, K, F: Clone> Layer<S> for RequestWindowLayer<K, F> {
+    type Service = RequestWindowService<S, K, F>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RequestWindowService {
+            inner,
+            stats: self.stats.clone(),
+            route: self.route.clone(),
+        }
+    }
+}
+
+impl<K, F: Clone> Clone for RequestWindowLayer<K, F> {
+    fn clone(&self) -> Self {
+        Self {
+            stats: self.stats.clone(),
+            route: self.route.clone(),
+        }
+    }
+}
+
+impl<K, F> Debug for RequestWindowLayer<K, F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RequestWindowLayer")
+            .field("stats", &self.stats)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A [`Service`] recording the requests to the wrapped service into [`RouteStats`].
+///
+/// See [`RequestWindowLayer`].
+pub struct RequestWindowService<S, K, F> {
+    inner: S,
+    stats: RouteStats<K>,
+    route: F,
+}
+
+impl<S, K, F, Request> Service<Request> for RequestWindowService<S, K, F>
+where
+    S: Service<Request>,
+    K: Hash + Eq,
+    F: Fn(&Request) -> K,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = RecordingFuture<S::Future, K>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        let route = (self.route)(&request);
+        let started = self.stats.now();
+        RecordingFuture {
+            inner: Box::pin(self.inner.call(request)),
+            pending: Some((self.stats.clone(), route, started)),
+        }
+    }
+}
+
+impl<S: Clone, K, F: Clone> Clone for RequestWindowService<S, K, F> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            stats: self.stats.clone(),
+            route: self.route.clone(),
+        }
+    }
+}
+
+impl<S: Debug, K, F> Debug for RequestWindowService<S, K, F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RequestWindowService")
+            .field("inner", &self.inner)
+            .field("stats", &self.stats)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The response future of a [`RequestWindowService`], recording the request once it
+/// completes.
+pub struct RecordingFuture<Fut, K> {
+    inner: Pin<Box<Fut>>,
+    pending: Option<(RouteStats<K>, K, Instant)>,
+}
+
+// The pending record is never pinned.
+impl<Fut, K> Unpin for RecordingFuture<Fut, K> {}
+
+impl<Fut, K> Future for RecordingFuture<Fut, K>
+where
+    Fut: Future,
+    K: Hash + Eq,
+{
+    type Output = Fut::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let output = std::task::ready!(self.inner.as_mut().poll(cx));
+        if let Some((stats, route, started)) = self.pending.take() {
+            stats.record(route, started);
+        }
+        Poll::Ready(output)
+    }
+}
+
+impl<Fut, K> Debug for RecordingFuture<Fut, K> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RecordingFuture")
+            .field("completed", &self.pending.is_none())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TickClock;
+    use std::future::poll_fn;
+
+    /// A service whose responses take a number of ticks, given by the request.
+    struct Delayed(TickClock);
+
+    impl Service<u64> for Delayed {
+        type Response = ();
+        type Error = ();
+        type Future = Pin<Box<dyn Future<Output = Result<(), ()>>>>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), ()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, ticks: u64) -> Self::Future {
+            let clock = self.0.clone();
+            Box::pin(async move {
+                clock.advance(ticks);
+                if ticks > 5 {
+                    Err(())
+                } else {
+                    Ok(())
+                }
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn latencies_are_recorded_per_route() {
+        let clock = TickClock::new();
+        let stats = RouteStats::new(TickClock::ttl(20));
+        stats.set_clock(clock.clone());
+
+        let layer = RequestWindowLayer::new(stats.clone(), |ticks: &u64| *ticks % 2 == 0);
+        let mut service = layer.layer(Delayed(clock.clone()));
+        poll_fn(|cx| service.poll_ready(cx)).await.unwrap();
+
+        for ticks in [2, 4, 3] {
+            service.call(ticks).await.unwrap();
+        }
+        assert!(service.call(6).await.is_err());
+
+        assert_eq!(stats.request_count(&true), 3);
+        assert_eq!(
+            stats.latencies(&true),
+            [TickClock::ttl(2), TickClock::ttl(4), TickClock::ttl(6)]
+        );
+        assert_eq!(stats.mean_latency(&true), Some(TickClock::ttl(4)));
+        assert_eq!(stats.max_latency(&false), Some(TickClock::ttl(3)));
+
+        // The requests of the even route completed at ticks 2, 6 and 15, the request
+        // of the odd route at tick 9.
+        clock.advance(14);
+        assert_eq!(stats.refresh(), 1);
+        assert_eq!(stats.routes(), [true]);
+    }
+
+    #[test]
+    fn recording_expires_requests_of_the_route() {
+        let clock = TickClock::new();
+        let stats = RouteStats::new(TickClock::ttl(10));
+        stats.set_clock(clock.clone());
+
+        for _ in 0..100 {
+            let started = clock.now();
+            clock.tick();
+            stats.record("/health", started);
+        }
+
+        assert_eq!(stats.lock().routes["/health"].len(), 10);
+        assert_eq!(stats.request_count("/health"), 10);
+    }
+}
@@ -0,0 +1,149 @@
+use crate::TtlQueue;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::thread;
+use std::time::Duration;
+
+/// A thread-safe [`TtlQueue`] that is split across multiple independently locked shards.
+///
+/// Elements are assigned to a shard based on the pushing thread (or an explicit key),
+/// so that many producer threads do not contend on a single lock. Counts are
+/// aggregated across all shards on read.
+///
+/// ## Example
+///
+/// ```
+/// # use std::sync::Arc;
+/// # use std::thread;
+/// # use std::time::Duration;
+/// # use ttl_queue::ShardedTtlQueue;
+/// let queue = Arc::new(ShardedTtlQueue::new(Duration::from_secs(60), 4));
+///
+/// let producers: Vec<_> = (0..8)
+///     .map(|_| {
+///         let queue = queue.clone();
+///         thread::spawn(move || queue.push_back(()))
+///     })
+///     .collect();
+///
+/// for producer in producers {
+///     producer.join().unwrap();
+/// }
+///
+/// assert_eq!(queue.refresh(), 8);
+/// ```
+#[derive(Debug)]
+pub struct ShardedTtlQueue<T> {
+    shards: Box<[Mutex<TtlQueue<T>>]>,
+}
+
+impl<T> ShardedTtlQueue<T> {
+    /// Creates an empty [`ShardedTtlQueue`] with the specified number of shards.
+    ///
+    /// At least one shard is always created.
+    pub fn new(ttl: Duration, shards: usize) -> Self {
+        let shards = (0..shards.max(1))
+            .map(|_| Mutex::new(TtlQueue::new(ttl)))
+            .collect();
+        Self { shards }
+    }
+
+    /// Gets the number of shards.
+    pub fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// Pushes an element to the end of the shard assigned to the current thread.
+    pub fn push_back(&self, element: T) {
+        self.push_back_keyed(&thread::current().id(), element)
+    }
+
+    /// Pushes an element to the end of the shard assigned to the specified key.
+    pub fn push_back_keyed<K: Hash + ?Sized>(&self, key: &K, element: T) {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        let index = (hasher.finish() % self.shards.len() as u64) as usize;
+        self.lock(index).push_back(element);
+    }
+
+    /// Refreshes all shards and returns the total number of currently contained elements.
+    pub fn refresh(&self) -> usize {
+        (0..self.shards.len())
+            .map(|index| self.lock(index).refresh())
+            .sum()
+    }
+
+    /// Gets the total number of elements in all shards, including potentially expired elements.
+    ///
+    /// In order to obtain an accurate count, use [`refresh`](Self::refresh) instead.
+    pub fn len(&self) -> usize {
+        (0..self.shards.len())
+            .map(|index| self.lock(index).len())
+            .sum()
+    }
+
+    /// Returns `true` if all shards are definitely empty or `false` if they are
+    /// possibly empty.
+    pub fn is_empty(&self) -> bool {
+        (0..self.shards.len()).all(|index| self.lock(index).is_empty())
+    }
+
+    /// Consumes the sharded queue and returns the individual shards.
+    pub fn into_shards(self) -> Vec<TtlQueue<T>> {
+        self.shards
+            .into_vec()
+            .into_iter()
+            .map(|shard| shard.into_inner().unwrap_or_else(PoisonError::into_inner))
+            .collect()
+    }
+
+    fn lock(&self, index: usize) -> MutexGuard<'_, TtlQueue<T>> {
+        self.shards[index]
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn concurrent_pushes_are_counted() {
+        let queue = Arc::new(ShardedTtlQueue::new(Duration::MAX, 4));
+        assert_eq!(queue.shard_count(), 4);
+        assert!(queue.is_empty());
+
+        let producers: Vec<_> = (0..8)
+            .map(|_| {
+                let queue = queue.clone();
+                thread::spawn(move || {
+                    for i in 0..100 {
+                        queue.push_back(i);
+                    }
+                })
+            })
+            .collect();
+
+        for producer in producers {
+            producer.join().unwrap();
+        }
+
+        assert_eq!(queue.len(), 800);
+        assert_eq!(queue.refresh(), 800);
+    }
+
+    #[test]
+    fn keyed_pushes_share_a_shard() {
+        let queue = ShardedTtlQueue::new(Duration::MAX, 8);
+        for i in 0..10 {
+            queue.push_back_keyed("user", i);
+        }
+
+        let shards = queue.into_shards();
+        assert_eq!(shards.len(), 8);
+        assert_eq!(shards.iter().filter(|shard| !shard.is_empty()).count(), 1);
+    }
+}
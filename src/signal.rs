@@ -0,0 +1,226 @@
+use crate::derived::DerivedState;
+use crate::{Clock, Instant, TtlQueue};
+use std::time::Duration;
+
+/// A [`TtlQueue`] of numeric samples with incrementally maintained statistics.
+///
+/// The mean, [variance](Self::variance) and [RMS](Self::rms) of the live window are
+/// updated with Welford-style updates whenever a sample is pushed or expires, so
+/// reading them is O(1) regardless of the number of samples, e.g. for audio or
+/// sensor buffers. The same applies to the least-squares [trend](Self::trend) of the
+/// samples over time.
+///
+/// ## Example
+///
+/// ```
+/// # use std::time::Duration;
+/// # use ttl_queue::SignalWindow;
+/// let mut window = SignalWindow::new(Duration::from_secs(1));
+/// for sample in [1.0f32, -1.0, 1.0, -1.0] {
+///     window.push_back(sample);
+/// }
+///
+/// assert_eq!(window.refresh(), 4);
+/// assert_eq!(window.mean(), Some(0.0));
+/// assert_eq!(window.rms(), Some(1.0));
+/// assert_eq!(window.stddev(), Some(1.0));
+/// ```
+#[derive(Debug)]
+pub struct SignalWindow<T> {
+    queue: TtlQueue<T>,
+    /// The instant relative to which sample times are measured while the window is not empty.
+    origin: Option<Instant>,
+    mean: f64,
+    /// The sum of squared differences from the mean.
+    m2: f64,
+    /// The mean sample time in seconds since the origin.
+    mean_time: f64,
+    /// The sum of squared differences of the sample times from their mean.
+    m2_time: f64,
+    /// The sum of products of the differences of sample times and values from their means.
+    co_moment: f64,
+}
+
+impl<T: Copy + Into<f64>> SignalWindow<T> {
+    /// Creates an empty [`SignalWindow`].
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            queue: TtlQueue::new(ttl),
+            origin: None,
+            mean: 0.0,
+            m2: 0.0,
+            mean_time: 0.0,
+            m2_time: 0.0,
+            co_moment: 0.0,
+        }
+    }
+
+    /// Gets the samples of the window.
+    pub fn queue(&self) -> &TtlQueue<T> {
+        &self.queue
+    }
+
+    /// Gets the number of samples, including potentially expired samples.
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Returns `true` if the window is definitely empty or `false` if it is possibly empty.
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// Pushes a sample to the end of the window.
+    pub fn push_back(&mut self, sample: T) {
+        self.push_back_at(self.queue.now(), sample)
+    }
+
+    /// Pushes a sample stamped with the specified instant to the end of the window.
+    ///
+    /// The instant must not be earlier than that of the previously pushed sample.
+    pub fn push_back_at(&mut self, instant: Instant, sample: T) {
+        self.queue.push_back_entry(instant, sample);
+
+        let count = self.queue.len() as f64;
+        let (t, x) = (self.time_of(instant), sample.into());
+        let (delta_t, delta) = (t - self.mean_time, x - self.mean);
+        self.mean_time += delta_t / count;
+        self.mean += delta / count;
+        self.m2 += delta * (x - self.mean);
+        self.m2_time += delta_t * (t - self.mean_time);
+        self.co_moment += delta_t * (x - self.mean);
+    }
+
+    /// Removes the sample from the front of the window and returns it, as well as the
+    /// time instant at which it was added.
+    pub fn pop_front(&mut self) -> Option<(Instant, T)> {
+        let (instant, sample) = self.queue.pop_front_entry()?;
+
+        let count = self.queue.len();
+        if count == 0 {
+            self.origin = None;
+            self.mean = 0.0;
+            self.m2 = 0.0;
+            self.mean_time = 0.0;
+            self.m2_time = 0.0;
+            self.co_moment = 0.0;
+        } else {
+            let (t, x) = (self.time_of(instant), sample.into());
+            let (delta_t, delta) = (t - self.mean_time, x - self.mean);
+            self.mean_time -= delta_t / count as f64;
+            self.mean -= delta / count as f64;
+            self.m2 = (self.m2 - delta * (x - self.mean)).max(0.0);
+            self.m2_time = (self.m2_time - delta_t * (t - self.mean_time)).max(0.0);
+            self.co_moment -= delta_t * (x - self.mean);
+        }
+        Some((instant, sample))
+    }
+
+    /// Gets the time of the sample added at the specified instant in seconds since the
+    /// origin, which is set to that instant if the window is empty.
+    fn time_of(&mut self, instant: Instant) -> f64 {
+        let origin = *self.origin.get_or_insert(instant);
+        instant.saturating_duration_since(origin).as_secs_f64()
+    }
+
+    /// Sets the clock used to timestamp new samples and to determine expiry.
+    ///
+    /// See [`TtlQueue::set_clock`].
+    pub fn set_clock(&mut self, clock: impl Clock + 'static) {
+        self.queue.set_clock(clock);
+    }
+
+    /// Refreshes the window and returns the number of currently contained samples.
+    pub fn refresh(&mut self) -> usize {
+        self.expire()
+    }
+
+    /// Refreshes the window as of the specified instant and returns the number of
+    /// currently contained samples.
+    pub fn refresh_at(&mut self, now: Instant) -> usize {
+        self.expire_at(now)
+    }
+
+    /// Gets the mean of the samples, or `None` if the window is empty.
+    pub fn mean(&self) -> Option<f64> {
+        (!self.is_empty()).then_some(self.mean)
+    }
+
+    /// Gets the (population) variance of the samples, or `None` if the window is empty.
+    pub fn variance(&self) -> Option<f64> {
+        (!self.is_empty()).then(|| self.m2 / self.len() as f64)
+    }
+
+    /// Gets the (population) standard deviation of the samples, or `None` if the
+    /// window is empty.
+    pub fn stddev(&self) -> Option<f64> {
+        self.variance().map(f64::sqrt)
+    }
+
+    /// Gets the least-squares slope of the samples over time in units per second, or `None`
+    /// if the window holds fewer than two samples with distinct timestamps.
+    ///
+    /// A positive trend indicates that the signal is rising over the window, a negative
+    /// trend that it is falling.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use ttl_queue::{Instant, SignalWindow};
+    /// let mut temperature = SignalWindow::new(Duration::from_secs(60));
+    /// let now = Instant::now();
+    /// temperature.push_back_at(now, 20.0);
+    /// temperature.push_back_at(now + Duration::from_secs(10), 21.0);
+    /// temperature.push_back_at(now + Duration::from_secs(20), 22.0);
+    ///
+    /// assert!((temperature.trend().unwrap() - 0.1).abs() < 1e-9);
+    /// ```
+    pub fn trend(&self) -> Option<f64> {
+        (self.len() >= 2 && self.m2_time > 0.0).then(|| self.co_moment / self.m2_time)
+    }
+
+    /// Gets the root mean square of the samples, or `None` if the window is empty.
+    pub fn rms(&self) -> Option<f64> {
+        self.variance()
+            .map(|variance| (variance + self.mean * self.mean).sqrt())
+    }
+}
+
+impl<T: Copy + Into<f64>> DerivedState for SignalWindow<T> {
+    type Element = T;
+
+    fn queue(&self) -> &TtlQueue<T> {
+        &self.queue
+    }
+
+    fn remove_front(&mut self) {
+        self.pop_front();
+    }
+}
+
+#[cfg(all(test, not(feature = "mock_instant")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn statistics_follow_expiry() {
+        let mut window = SignalWindow::new(Duration::from_secs(10));
+        let now = Instant::now();
+        window.push_back_at(now - Duration::from_secs(20), 100i32);
+        for (i, sample) in [2, 4, 4, 4, 5, 5, 7, 9].into_iter().enumerate() {
+            window.push_back_at(now + Duration::from_secs(i as u64), sample);
+        }
+
+        assert_eq!(window.refresh_at(now), 8);
+        assert!((window.mean().unwrap() - 5.0).abs() < 1e-9);
+        assert!((window.variance().unwrap() - 4.0).abs() < 1e-9);
+        assert!((window.rms().unwrap() - 29f64.sqrt()).abs() < 1e-9);
+
+        assert!((window.trend().unwrap() - 34.0 / 42.0).abs() < 1e-9);
+
+        assert_eq!(window.refresh_at(now + Duration::from_secs(30)), 0);
+        assert_eq!(window.trend(), None);
+        assert_eq!(window.stddev(), None);
+    }
+}
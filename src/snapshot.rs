@@ -0,0 +1,530 @@
+use crate::{Instant, TtlQueue};
+use std::any::Any;
+use std::fmt::{Debug, Formatter};
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+
+#[cfg(feature = "arc-swap")]
+use arc_swap::ArcSwap;
+
+/// The maximum number of segments shared by frozen snapshots before
+/// [`TtlQueue::freeze`] copies the elements into a single segment again.
+const MAX_SEGMENTS: usize = 16;
+
+/// An immutable copy of the contents of a [`TtlQueue`] at a given point in time.
+///
+/// The elements are held in reference-counted segments that are shared with other
+/// snapshots, so cloning a snapshot is cheap and it can be sent to other threads
+/// whenever the elements can.
+pub struct TtlSnapshot<T> {
+    ttl: Duration,
+    taken_at: Instant,
+    epoch: u64,
+    segments: Vec<Arc<Vec<(Instant, T)>>>,
+    /// The number of leading entries of the first segment not contained in the snapshot.
+    skip: usize,
+    len: usize,
+}
+
+impl<T> TtlSnapshot<T> {
+    /// Gets the TTL of the queue the snapshot was taken from.
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Gets the time instant at which the snapshot was taken.
+    pub fn taken_at(&self) -> Instant {
+        self.taken_at
+    }
+
+    /// Gets the epoch of the snapshot.
+    ///
+    /// Snapshots [frozen](TtlQueue::freeze) from the same queue with the same epoch
+    /// contain the same elements; the epoch increases whenever the contents differ
+    /// from the previous snapshot. Snapshots taken with [`of`](Self::of) have epoch zero.
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// Gets the number of elements in the snapshot.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the snapshot contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns an iterator to the data, oldest element first.
+    pub fn iter(&self) -> SnapshotIter<'_, T> {
+        let mut segments = self.segments.iter();
+        let current = segments
+            .next()
+            .map_or([].iter(), |segment| segment[self.skip..].iter());
+        SnapshotIter {
+            segments,
+            current,
+            remaining: self.len,
+        }
+    }
+
+    /// Returns an iterator over the contiguous segments holding the elements, oldest
+    /// element first.
+    pub fn segments(&self) -> impl Iterator<Item = &[(Instant, T)]> {
+        self.segments.iter().enumerate().map(|(index, segment)| {
+            if index == 0 {
+                &segment[self.skip..]
+            } else {
+                segment
+            }
+        })
+    }
+}
+
+impl<T: Clone> TtlSnapshot<T> {
+    /// Takes a snapshot of the current contents of the specified queue.
+    ///
+    /// This copies all elements; [`TtlQueue::freeze`] only copies the elements pushed
+    /// since the previous snapshot.
+    pub fn of(queue: &TtlQueue<T>) -> Self {
+        let segment: Vec<(Instant, T)> = queue.iter().cloned().collect();
+        Self {
+            ttl: queue.ttl,
+            taken_at: queue.now(),
+            epoch: 0,
+            len: segment.len(),
+            segments: vec![Arc::new(segment)],
+            skip: 0,
+        }
+    }
+}
+
+impl<T> Clone for TtlSnapshot<T> {
+    fn clone(&self) -> Self {
+        Self {
+            ttl: self.ttl,
+            taken_at: self.taken_at,
+            epoch: self.epoch,
+            segments: self.segments.clone(),
+            skip: self.skip,
+            len: self.len,
+        }
+    }
+}
+
+impl<T: Debug> Debug for TtlSnapshot<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TtlSnapshot")
+            .field("ttl", &self.ttl)
+            .field("taken_at", &self.taken_at)
+            .field("epoch", &self.epoch)
+            .field("entries", &self.iter().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a TtlSnapshot<T> {
+    type Item = &'a (Instant, T);
+    type IntoIter = SnapshotIter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// An iterator over the elements of a [`TtlSnapshot`], oldest element first.
+///
+/// See [`TtlSnapshot::iter`].
+#[derive(Debug, Clone)]
+pub struct SnapshotIter<'a, T> {
+    segments: std::slice::Iter<'a, Arc<Vec<(Instant, T)>>>,
+    current: std::slice::Iter<'a, (Instant, T)>,
+    remaining: usize,
+}
+
+impl<'a, T> Iterator for SnapshotIter<'a, T> {
+    type Item = &'a (Instant, T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        loop {
+            if let Some(entry) = self.current.next() {
+                self.remaining -= 1;
+                return Some(entry);
+            }
+            self.current = self.segments.next()?.iter();
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T> ExactSizeIterator for SnapshotIter<'_, T> {}
+
+/// The segments of the snapshots returned by [`TtlQueue::freeze`], covering the oldest
+/// elements of the queue.
+///
+/// The queue only refers to the segments weakly, so they are released along with the
+/// last snapshot holding them. The segments are type-erased so that they do not affect
+/// whether the queue is `Send` or `Sync`; [`freeze`](TtlQueue::freeze) requires the
+/// elements to be both.
+#[derive(Default)]
+pub(crate) struct FrozenSegments {
+    /// The number of entries of each segment, along with the segment.
+    segments: Vec<(usize, Weak<dyn Any + Send + Sync>)>,
+    /// The number of leading entries of the first segment no longer in the queue.
+    skip: usize,
+    /// The number of leading elements of the queue covered by the segments.
+    len: usize,
+    epoch: u64,
+    /// Whether the covered elements changed since the last snapshot.
+    changed: bool,
+}
+
+impl FrozenSegments {
+    /// Gets the number of leading elements of the queue covered by the segments.
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Accounts for the removal of the specified number of elements from the front
+    /// of the queue, releasing segments that are no longer needed.
+    pub(crate) fn consume_front(&mut self, count: usize) {
+        let count = count.min(self.len);
+        if count == 0 {
+            return;
+        }
+
+        self.len -= count;
+        self.skip += count;
+        self.changed = true;
+        while let Some((first_len, _)) = self.segments.first() {
+            if self.skip < *first_len {
+                break;
+            }
+            self.skip -= first_len;
+            self.segments.remove(0);
+        }
+    }
+
+    /// Discards the segments if they cover the element at the specified position,
+    /// e.g. because it is modified in place.
+    pub(crate) fn modified(&mut self, index: usize) {
+        if index < self.len {
+            self.release(0);
+            self.changed = true;
+        }
+    }
+
+    /// Stops covering the elements of the segments from the specified one onwards.
+    ///
+    /// This does not change the contents of the queue, so the epoch is retained.
+    fn release(&mut self, first: usize) {
+        self.segments.truncate(first);
+        if self.segments.is_empty() {
+            self.skip = 0;
+        }
+        self.len = self
+            .segments
+            .iter()
+            .map(|(segment_len, _)| segment_len)
+            .sum::<usize>()
+            - self.skip;
+    }
+
+    /// Upgrades the segments that are still held by snapshots, oldest first, and
+    /// releases the first segment held by none of them along with all later ones.
+    fn upgrade<T: Send + Sync + 'static>(&mut self) -> Vec<Arc<Vec<(Instant, T)>>> {
+        let upgraded: Vec<_> = self
+            .segments
+            .iter()
+            .map_while(|(_, segment)| segment.upgrade())
+            .map(|segment| {
+                segment
+                    .downcast()
+                    .expect("Frozen segments hold the elements of the queue")
+            })
+            .collect();
+
+        if upgraded.len() < self.segments.len() {
+            self.release(upgraded.len());
+        }
+        upgraded
+    }
+}
+
+impl<T: Clone + Send + Sync + 'static> TtlQueue<T> {
+    /// Takes a cheap, immutable snapshot of the current contents, e.g. to analyze them
+    /// on other threads while this queue keeps receiving elements.
+    ///
+    /// Elements already contained in a snapshot that is still alive are shared with it
+    /// rather than copied; only elements pushed since are cloned into a fresh segment.
+    /// Removing elements from the front does not require copying either, but modifying
+    /// frozen elements in place, e.g. through a [cursor](Self::cursor_front_mut) or by
+    /// [resuming](Self::resume) expiry, causes the next snapshot to copy all elements.
+    /// The queue does not keep the copies itself: once the last snapshot is dropped,
+    /// so are the copied elements, and the next snapshot copies all elements again.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use std::thread;
+    /// # use std::time::Duration;
+    /// # use ttl_queue::TtlQueue;
+    /// let mut queue = TtlQueue::new(Duration::from_secs(60));
+    /// queue.push_back(1);
+    /// queue.push_back(2);
+    ///
+    /// let snapshot = queue.freeze();
+    /// let analytics = thread::spawn(move || snapshot.iter().map(|(_, value)| value).sum::<i32>());
+    ///
+    /// queue.push_back(3);
+    /// assert_eq!(analytics.join().unwrap(), 3);
+    ///
+    /// let snapshot = queue.freeze();
+    /// assert_eq!(snapshot.len(), 3);
+    /// assert_eq!(snapshot.epoch(), queue.freeze().epoch());
+    /// ```
+    pub fn freeze(&mut self) -> TtlSnapshot<T> {
+        let covered = self.frozen.len;
+        let mut segments = self.frozen.upgrade();
+        if self.len() > self.frozen.len {
+            if segments.len() >= MAX_SEGMENTS {
+                self.frozen.release(0);
+                segments.clear();
+            }
+
+            let segment: Vec<(Instant, T)> = self.iter().skip(self.frozen.len).cloned().collect();
+            let segment = Arc::new(segment);
+            let shared: Arc<dyn Any + Send + Sync> = segment.clone();
+            self.frozen.len += segment.len();
+            self.frozen
+                .segments
+                .push((segment.len(), Arc::downgrade(&shared)));
+            self.frozen.changed |= self.frozen.len > covered;
+            segments.push(segment);
+        }
+
+        if self.frozen.changed {
+            self.frozen.epoch += 1;
+            self.frozen.changed = false;
+        }
+
+        TtlSnapshot {
+            ttl: self.ttl,
+            taken_at: self.now(),
+            epoch: self.frozen.epoch,
+            segments,
+            skip: self.frozen.skip,
+            len: self.frozen.len,
+        }
+    }
+}
+
+/// A [`TtlQueue`] with a single writer that publishes immutable [`TtlSnapshot`]s
+/// on every [`refresh`](Self::refresh), which any number of [`SnapshotReader`]s
+/// can load wait-free.
+///
+/// ## Example
+///
+/// ```
+/// # use std::time::Duration;
+/// # use ttl_queue::SnapshotTtlQueue;
+/// let mut queue = SnapshotTtlQueue::new(Duration::from_secs(60));
+/// let reader = queue.reader();
+///
+/// queue.push_back(42);
+/// assert!(reader.load().is_empty());
+///
+/// queue.refresh();
+/// assert_eq!(reader.load().len(), 1);
+/// ```
+#[cfg(feature = "arc-swap")]
+#[derive(Debug)]
+pub struct SnapshotTtlQueue<T> {
+    queue: TtlQueue<T>,
+    published: Arc<ArcSwap<TtlSnapshot<T>>>,
+}
+
+/// A cloneable handle to load the latest snapshot published by a [`SnapshotTtlQueue`].
+#[cfg(feature = "arc-swap")]
+#[derive(Debug)]
+pub struct SnapshotReader<T> {
+    published: Arc<ArcSwap<TtlSnapshot<T>>>,
+}
+
+#[cfg(feature = "arc-swap")]
+impl<T: Clone + Send + Sync + 'static> SnapshotTtlQueue<T> {
+    /// Creates an empty [`SnapshotTtlQueue`] and publishes an empty snapshot.
+    pub fn new(ttl: Duration) -> Self {
+        let mut queue = TtlQueue::new(ttl);
+        let published = Arc::new(ArcSwap::from_pointee(queue.freeze()));
+        Self { queue, published }
+    }
+
+    /// Pushes an element to the end of the queue. The element becomes visible to
+    /// readers after the next [`refresh`](Self::refresh).
+    pub fn push_back(&mut self, element: T) {
+        self.queue.push_back(element)
+    }
+
+    /// Refreshes the queue, publishes a new snapshot and returns the number of
+    /// currently contained elements.
+    pub fn refresh(&mut self) -> usize {
+        let count = self.queue.refresh();
+        self.publish();
+        count
+    }
+
+    /// Publishes a snapshot of the current contents without refreshing the queue.
+    ///
+    /// The snapshot is [frozen](TtlQueue::freeze), so elements contained in the previously
+    /// published snapshot are shared with it rather than copied.
+    pub fn publish(&mut self) {
+        self.published.store(Arc::new(self.queue.freeze()));
+    }
+
+    /// Creates a new reader for the published snapshots.
+    pub fn reader(&self) -> SnapshotReader<T> {
+        SnapshotReader {
+            published: self.published.clone(),
+        }
+    }
+
+    /// Gets the underlying queue.
+    pub fn queue(&self) -> &TtlQueue<T> {
+        &self.queue
+    }
+
+    /// Gets the underlying queue mutably. Changes become visible to readers after
+    /// the next [`refresh`](Self::refresh) or [`publish`](Self::publish).
+    pub fn queue_mut(&mut self) -> &mut TtlQueue<T> {
+        &mut self.queue
+    }
+}
+
+#[cfg(feature = "arc-swap")]
+impl<T> SnapshotReader<T> {
+    /// Loads the most recently published snapshot.
+    pub fn load(&self) -> Arc<TtlSnapshot<T>> {
+        self.published.load_full()
+    }
+}
+
+#[cfg(feature = "arc-swap")]
+impl<T> Clone for SnapshotReader<T> {
+    fn clone(&self) -> Self {
+        Self {
+            published: self.published.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshot_copies_contents() {
+        let mut queue = TtlQueue::new(Duration::MAX);
+        queue.push_back(1);
+        queue.push_back(2);
+
+        let snapshot = TtlSnapshot::of(&queue);
+        queue.push_back(3);
+
+        assert_eq!(snapshot.len(), 2);
+        let values: Vec<_> = snapshot.iter().map(|(_, value)| *value).collect();
+        assert_eq!(values, [1, 2]);
+    }
+
+    #[test]
+    fn frozen_snapshots_share_segments() {
+        let mut queue = TtlQueue::new(Duration::MAX);
+        for i in 0..4 {
+            queue.push_back(i);
+        }
+        let first = queue.freeze();
+
+        queue.pop_front();
+        queue.push_back(4);
+        let second = queue.freeze();
+        assert_eq!(second.segments().count(), 2);
+        assert!(Arc::ptr_eq(&first.segments[0], &second.segments[0]));
+        assert!(second.epoch() > first.epoch());
+
+        let values: Vec<_> = second.iter().map(|(_, value)| *value).collect();
+        assert_eq!(values, [1, 2, 3, 4]);
+        assert_eq!(first.len(), 4);
+
+        queue.pop_front_if(|_, value| *value == 1);
+        queue.cursor_front_mut().remove_current();
+        let third = queue.freeze();
+        assert_eq!(third.segments().count(), 1);
+        let values: Vec<_> = third.iter().map(|(_, value)| *value).collect();
+        assert_eq!(values, [3, 4]);
+    }
+
+    #[test]
+    fn frozen_segments_are_released_with_the_snapshots() {
+        let mut queue = TtlQueue::new(Duration::MAX);
+        for i in 0..4 {
+            queue.push_back(Arc::new(i));
+        }
+        let first = queue.freeze();
+        let tracked = first.segments[0].clone();
+        queue.push_back(Arc::new(4));
+        let second = queue.freeze();
+        assert_eq!(Arc::strong_count(&tracked), 3);
+
+        drop(first);
+        drop(second);
+        assert_eq!(Arc::strong_count(&tracked), 1);
+        let value = tracked[0].1.clone();
+        drop(tracked);
+        assert_eq!(Arc::strong_count(&value), 2);
+
+        let epoch = queue.freeze().epoch();
+        let third = queue.freeze();
+        assert_eq!(third.epoch(), epoch);
+        assert_eq!(third.segments().count(), 1);
+        let values: Vec<_> = third.iter().map(|(_, value)| **value).collect();
+        assert_eq!(values, [0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    #[cfg(feature = "arc-swap")]
+    fn readers_see_published_snapshots() {
+        let mut queue = SnapshotTtlQueue::new(Duration::MAX);
+        let reader = queue.reader();
+        let other = reader.clone();
+
+        queue.push_back(1);
+        let before = reader.load();
+        queue.refresh();
+
+        assert!(before.is_empty());
+        assert_eq!(other.load().len(), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "arc-swap")]
+    fn published_snapshots_share_segments() {
+        let mut queue = SnapshotTtlQueue::new(Duration::MAX);
+        let reader = queue.reader();
+        queue.push_back(1);
+        queue.refresh();
+        let first = reader.load();
+
+        queue.push_back(2);
+        queue.refresh();
+        let second = reader.load();
+        assert_eq!(second.segments().count(), 2);
+        assert!(Arc::ptr_eq(&first.segments[0], &second.segments[0]));
+    }
+}
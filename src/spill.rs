@@ -0,0 +1,370 @@
+use crate::{Instant, TtlQueue};
+use std::fmt::{Debug, Formatter};
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// The default number of payloads spilled together in one segment file.
+const DEFAULT_SEGMENT_LEN: usize = 1024;
+
+/// Distinguishes the segment files of queues within the same process.
+static NEXT_QUEUE_ID: AtomicU64 = AtomicU64::new(0);
+
+/// A TTL queue of byte payloads that keeps the newest segment in memory and spills
+/// older, unexpired segments to files, e.g. for hour-long windows of large events that
+/// would otherwise exhaust the memory.
+///
+/// Each segment is written to its own file once it is full; only the timestamps and
+/// offsets of spilled payloads stay in memory. Segment files are deleted as soon as all
+/// of their payloads expired or were removed, and when the queue is dropped.
+///
+/// ## Example
+///
+/// ```
+/// # use std::time::Duration;
+/// # use ttl_queue::SpillingTtlQueue;
+/// let dir = std::env::temp_dir();
+/// let mut queue = SpillingTtlQueue::with_dir(Duration::from_secs(3600), 100, dir).unwrap();
+/// for i in 0..250 {
+///     queue.push_back(format!("event {i}")).unwrap();
+/// }
+///
+/// assert_eq!(queue.spilled_segments(), 2);
+/// assert_eq!(queue.refresh(), 250);
+///
+/// let (_instant, first) = queue.pop_front().unwrap().unwrap();
+/// assert_eq!(first, b"event 0");
+/// ```
+pub struct SpillingTtlQueue {
+    /// The spilled segments, each stamped with the timestamp of its newest payload.
+    segments: TtlQueue<SpilledSegment>,
+    /// The number of leading payloads of the front segment that were removed.
+    skip: usize,
+    /// The payloads not spilled yet.
+    tail: Vec<(Instant, Vec<u8>)>,
+    segment_len: usize,
+    len: usize,
+    dir: PathBuf,
+    queue_id: u64,
+    next_segment_id: u64,
+}
+
+/// A segment of payloads stored in a file; the file is deleted when the segment is dropped.
+struct SpilledSegment {
+    path: PathBuf,
+    instants: Vec<Instant>,
+    /// The end offset of each payload within the file.
+    ends: Vec<u64>,
+}
+
+impl SpilledSegment {
+    /// Writes the entries to a new file, which is deleted again if writing fails.
+    fn write(path: PathBuf, entries: &[(Instant, Vec<u8>)]) -> io::Result<Self> {
+        let mut file = File::create(&path)?;
+        let mut segment = Self {
+            path,
+            instants: Vec::with_capacity(entries.len()),
+            ends: Vec::with_capacity(entries.len()),
+        };
+
+        let mut end = 0;
+        for (instant, payload) in entries {
+            file.write_all(payload)?;
+            end += payload.len() as u64;
+            segment.instants.push(*instant);
+            segment.ends.push(end);
+        }
+        Ok(segment)
+    }
+
+    /// Reads the payloads starting at the specified index.
+    fn read_from(&self, index: usize) -> io::Result<Vec<(Instant, Vec<u8>)>> {
+        let mut file = File::open(&self.path)?;
+        let mut start = self.start_of(index);
+        file.seek(SeekFrom::Start(start))?;
+
+        let mut entries = Vec::with_capacity(self.len() - index);
+        for (instant, &end) in self.instants.iter().zip(&self.ends).skip(index) {
+            let mut payload = vec![0; (end - start) as usize];
+            file.read_exact(&mut payload)?;
+            entries.push((*instant, payload));
+            start = end;
+        }
+        Ok(entries)
+    }
+
+    /// Reads the payload at the specified index.
+    fn read_one(&self, index: usize) -> io::Result<(Instant, Vec<u8>)> {
+        let mut file = File::open(&self.path)?;
+        let start = self.start_of(index);
+        file.seek(SeekFrom::Start(start))?;
+
+        let mut payload = vec![0; (self.ends[index] - start) as usize];
+        file.read_exact(&mut payload)?;
+        Ok((self.instants[index], payload))
+    }
+
+    fn start_of(&self, index: usize) -> u64 {
+        index
+            .checked_sub(1)
+            .map_or(0, |previous| self.ends[previous])
+    }
+
+    fn len(&self) -> usize {
+        self.instants.len()
+    }
+}
+
+impl Drop for SpilledSegment {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+impl SpillingTtlQueue {
+    /// Creates an empty [`SpillingTtlQueue`] spilling segments of 1024 payloads to the
+    /// system's temporary directory.
+    pub fn new(ttl: Duration) -> io::Result<Self> {
+        Self::with_dir(ttl, DEFAULT_SEGMENT_LEN, std::env::temp_dir())
+    }
+
+    /// Creates an empty [`SpillingTtlQueue`] spilling segments of `segment_len` payloads
+    /// to files in the specified directory, creating it if necessary.
+    ///
+    /// Larger segments mean fewer files, but more payloads held in memory and spilled
+    /// payloads retained on disk after they expired.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `segment_len` is zero.
+    pub fn with_dir(
+        ttl: Duration,
+        segment_len: usize,
+        dir: impl Into<PathBuf>,
+    ) -> io::Result<Self> {
+        assert!(segment_len > 0, "The segment length must not be zero");
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self {
+            segments: TtlQueue::new(ttl),
+            skip: 0,
+            tail: Vec::with_capacity(segment_len),
+            segment_len,
+            len: 0,
+            dir,
+            queue_id: NEXT_QUEUE_ID.fetch_add(1, Ordering::Relaxed),
+            next_segment_id: 0,
+        })
+    }
+
+    /// Gets the time-to-live of the payloads.
+    pub fn ttl(&self) -> Duration {
+        self.segments.ttl()
+    }
+
+    /// Gets the number of payloads per segment.
+    pub fn segment_len(&self) -> usize {
+        self.segment_len
+    }
+
+    /// Gets the directory segment files are written to.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Gets the number of payloads, including potentially expired payloads.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the queue is definitely empty or `false` if it is possibly empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Gets the number of segments currently spilled to disk.
+    pub fn spilled_segments(&self) -> usize {
+        self.segments.len()
+    }
+
+    /// Pushes a payload to the end of the queue, spilling the in-memory segment to disk
+    /// once it is full.
+    ///
+    /// If the segment cannot be written, the error is returned and the payloads are
+    /// retained in memory until the next push.
+    pub fn push_back(&mut self, payload: impl Into<Vec<u8>>) -> io::Result<()> {
+        self.tail.push((self.segments.now(), payload.into()));
+        self.len += 1;
+        if self.tail.len() >= self.segment_len {
+            self.spill()?;
+        }
+        Ok(())
+    }
+
+    /// Writes the in-memory payloads to a new segment file.
+    fn spill(&mut self) -> io::Result<()> {
+        let path = self.dir.join(format!(
+            "ttl-queue-{}-{}-{}.seg",
+            std::process::id(),
+            self.queue_id,
+            self.next_segment_id
+        ));
+        let segment = SpilledSegment::write(path, &self.tail)?;
+        self.next_segment_id += 1;
+        self.tail.clear();
+
+        let newest = *segment.instants.last().expect("segments are never empty");
+        self.segments.push_back_entry(newest, segment);
+        Ok(())
+    }
+
+    /// Removes the payload from the front of the queue and returns it, as well as the
+    /// time instant at which it was added.
+    ///
+    /// If the payload cannot be read from its segment file, the error is returned and
+    /// the payload is removed regardless.
+    pub fn pop_front(&mut self) -> io::Result<Option<(Instant, Vec<u8>)>> {
+        let Some((_, segment)) = self.segments.front_entry() else {
+            if self.tail.is_empty() {
+                return Ok(None);
+            }
+            self.len -= 1;
+            return Ok(Some(self.tail.remove(0)));
+        };
+
+        let entry = segment.read_one(self.skip);
+        self.remove_front(1);
+        entry.map(Some)
+    }
+
+    /// Refreshes the queue and returns the number of currently contained payloads.
+    ///
+    /// Segment files whose payloads all expired are deleted.
+    pub fn refresh(&mut self) -> usize {
+        let now = self.segments.now();
+        let ttl = self.segments.ttl();
+        let expired = |instant: &Instant| now.saturating_duration_since(*instant) >= ttl;
+
+        while let Some((newest, segment)) = self.segments.front_entry() {
+            let count = if expired(newest) {
+                segment.len() - self.skip
+            } else {
+                segment.instants[self.skip..]
+                    .iter()
+                    .take_while(|instant| expired(instant))
+                    .count()
+            };
+            if count == 0 {
+                return self.len;
+            }
+            self.remove_front(count);
+        }
+
+        let count = self
+            .tail
+            .iter()
+            .take_while(|(instant, _)| expired(instant))
+            .count();
+        self.tail.drain(..count);
+        self.len -= count;
+        self.len
+    }
+
+    /// Removes `count` payloads from the front segment, deleting it if none are left.
+    fn remove_front(&mut self, count: usize) {
+        let Some((_, segment)) = self.segments.front_entry() else {
+            return;
+        };
+        let exhausted = self.skip + count >= segment.len();
+
+        self.len -= count;
+        self.skip += count;
+        if exhausted {
+            self.segments.pop_front_entry();
+            self.skip = 0;
+        }
+    }
+
+    /// Returns an iterator over the payloads, oldest first, as well as the time instants
+    /// at which they were added.
+    ///
+    /// Spilled segments are read one at a time as the iterator advances; if a segment
+    /// cannot be read, the error is yielded in place of its payloads.
+    pub fn iter(&self) -> impl Iterator<Item = io::Result<(Instant, Vec<u8>)>> + '_ {
+        self.segments
+            .iter()
+            .enumerate()
+            .flat_map(move |(index, (_, segment))| {
+                let skip = if index == 0 { self.skip } else { 0 };
+                match segment.read_from(skip) {
+                    Ok(entries) => entries.into_iter().map(Ok).collect(),
+                    Err(error) => vec![Err(error)],
+                }
+            })
+            .chain(self.tail.iter().cloned().map(Ok))
+    }
+}
+
+impl Debug for SpillingTtlQueue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SpillingTtlQueue")
+            .field("ttl", &self.ttl())
+            .field("segment_len", &self.segment_len)
+            .field("len", &self.len)
+            .field("spilled_segments", &self.segments.len())
+            .field("dir", &self.dir)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TickClock;
+
+    #[test]
+    fn expired_segments_are_deleted() {
+        let clock = TickClock::new();
+        let mut queue =
+            SpillingTtlQueue::with_dir(TickClock::ttl(10), 4, std::env::temp_dir()).unwrap();
+        queue.segments.set_clock(clock.clone());
+
+        for i in 0..10u8 {
+            queue.push_back(vec![i; i as usize]).unwrap();
+            clock.tick();
+        }
+        let paths: Vec<PathBuf> = queue
+            .segments
+            .iter()
+            .map(|(_, segment)| segment.path.clone())
+            .collect();
+        assert_eq!(paths.len(), 2);
+        assert!(paths.iter().all(|path| path.exists()));
+
+        clock.advance(1);
+        assert_eq!(queue.refresh(), 8);
+        let lengths: Vec<usize> = queue.iter().map(|entry| entry.unwrap().1.len()).collect();
+        assert_eq!(lengths, [2, 3, 4, 5, 6, 7, 8, 9]);
+
+        assert_eq!(queue.pop_front().unwrap().unwrap().1, vec![2; 2]);
+        assert_eq!(queue.pop_front().unwrap().unwrap().1, vec![3; 3]);
+        assert!(!paths[0].exists());
+
+        clock.advance(7);
+        assert_eq!(queue.refresh(), 1);
+        assert!(!paths[1].exists());
+        assert_eq!(queue.pop_front().unwrap().unwrap().1, vec![9; 9]);
+        assert_eq!(queue.pop_front().unwrap(), None);
+
+        for _ in 0..4 {
+            queue.push_back(vec![0; 4]).unwrap();
+        }
+        let (_, segment) = queue.segments.front_entry().unwrap();
+        let path = segment.path.clone();
+        assert!(path.exists());
+        drop(queue);
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,352 @@
+use crate::{Instant, InterArrivalStats, TtlQueue};
+use parking_lot::{Condvar, Mutex, MutexGuard};
+use std::time::Duration;
+
+/// The shortest time a blocked push waits before checking for expired elements again,
+/// so that it does not spin while the next expiry is due or cannot be predicted.
+const MIN_EXPIRY_WAIT: Duration = Duration::from_millis(1);
+
+/// A thread-safe [`TtlQueue`] guarded by a [`parking_lot::Mutex`].
+///
+/// All operations take `&self`, so the queue can be shared across threads, e.g.
+/// through an [`Arc`](std::sync::Arc). The wrapper mirrors the most common operations
+/// of [`TtlQueue`]; any other operation, as well as multiple operations that need to
+/// happen atomically, can be performed through [`with_lock`](Self::with_lock). Use
+/// [`lock`](Self::lock) to inspect or iterate the contents.
+///
+/// Producers and consumers can block on the queue via [`push_back_blocking`](Self::push_back_blocking)
+/// and [`pop_front_timeout`](Self::pop_front_timeout). Blocked threads are woken by the
+/// operations of this wrapper, including [`with_lock`](Self::with_lock), but not by
+/// changes made through [`lock`](Self::lock).
+///
+/// ## Example
+///
+/// ```
+/// # use std::sync::Arc;
+/// # use std::thread;
+/// # use std::time::Duration;
+/// # use ttl_queue::SyncTtlQueue;
+/// let queue = Arc::new(SyncTtlQueue::new(Duration::from_secs(60)));
+///
+/// let producer = {
+///     let queue = queue.clone();
+///     thread::spawn(move || queue.push_back(42))
+/// };
+///
+/// producer.join().unwrap();
+/// assert_eq!(queue.refresh(), 1);
+/// ```
+#[derive(Debug)]
+pub struct SyncTtlQueue<T> {
+    queue: Mutex<TtlQueue<T>>,
+    /// Signaled whenever elements are pushed or removed.
+    changed: Condvar,
+}
+
+impl<T> SyncTtlQueue<T> {
+    /// Creates an empty [`SyncTtlQueue`] with default capacity.
+    pub fn new(ttl: Duration) -> Self {
+        Self::from(TtlQueue::new(ttl))
+    }
+
+    /// Creates an empty [`SyncTtlQueue`] for at least `capacity` elements.
+    pub fn with_capacity(ttl: Duration, capacity: usize) -> Self {
+        Self::from(TtlQueue::with_capacity(ttl, capacity))
+    }
+
+    /// Creates an empty [`SyncTtlQueue`] holding at most `max_len` elements.
+    /// See [`TtlQueue::with_max_len`].
+    pub fn with_max_len(ttl: Duration, max_len: usize) -> Self {
+        Self::from(TtlQueue::with_max_len(ttl, max_len))
+    }
+
+    /// Locks the queue for exclusive access.
+    ///
+    /// Threads blocked on the queue are not woken by changes made through the guard;
+    /// use [`with_lock`](Self::with_lock) to modify the queue.
+    pub fn lock(&self) -> MutexGuard<'_, TtlQueue<T>> {
+        self.queue.lock()
+    }
+
+    /// Calls the function with exclusive access to the queue and returns its result,
+    /// waking blocked threads afterwards.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use ttl_queue::SyncTtlQueue;
+    /// let queue = SyncTtlQueue::new(Duration::from_secs(60));
+    /// queue.with_lock(|queue| queue.extend([(Duration::ZERO, 1), (Duration::ZERO, 2)]));
+    ///
+    /// let odd = queue.with_lock(|queue| queue.extract_if(|_, value| *value % 2 == 1).count());
+    /// assert_eq!(odd, 1);
+    /// assert_eq!(queue.len(), 1);
+    /// ```
+    pub fn with_lock<R>(&self, f: impl FnOnce(&mut TtlQueue<T>) -> R) -> R {
+        let result = f(&mut self.lock());
+        self.changed.notify_all();
+        result
+    }
+
+    /// Consumes the wrapper and returns the underlying queue.
+    pub fn into_inner(self) -> TtlQueue<T> {
+        self.queue.into_inner()
+    }
+
+    /// Pushes an element to the end of the queue.
+    pub fn push_back(&self, element: T) {
+        self.lock().push_back(element);
+        self.changed.notify_all();
+    }
+
+    /// Pushes an element to the end of the queue and returns the number of items
+    /// currently in the queue. See [`TtlQueue::refresh_and_push_back`].
+    pub fn refresh_and_push_back(&self, element: T) -> usize {
+        let count = self.lock().refresh_and_push_back(element);
+        self.changed.notify_all();
+        count
+    }
+
+    /// Pushes an element to the end of the queue, waiting while the queue holds its
+    /// [maximum number](TtlQueue::with_max_len) of unexpired elements, and returns the
+    /// number of elements afterwards.
+    ///
+    /// Space becomes available when other threads remove elements or when elements
+    /// expire. Without a maximum length, this never blocks.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use std::sync::Arc;
+    /// # use std::thread;
+    /// # use std::time::Duration;
+    /// # use ttl_queue::SyncTtlQueue;
+    /// let queue = Arc::new(SyncTtlQueue::with_max_len(Duration::from_secs(60), 1));
+    /// queue.push_back_blocking("first");
+    ///
+    /// let consumer = {
+    ///     let queue = queue.clone();
+    ///     thread::spawn(move || queue.pop_front_timeout(Duration::from_secs(1)))
+    /// };
+    ///
+    /// assert_eq!(queue.push_back_blocking("second"), 1);
+    /// assert_eq!(consumer.join().unwrap().map(|(_, value)| value), Some("first"));
+    /// ```
+    pub fn push_back_blocking(&self, element: T) -> usize {
+        let mut queue = self.lock();
+        let mut element = element;
+        loop {
+            match queue.try_push_back(element) {
+                Ok(count) => {
+                    drop(queue);
+                    self.changed.notify_all();
+                    return count;
+                }
+                Err(error) => {
+                    element = error.into_inner();
+                    // Without unexpired elements, the queue is full of stale elements
+                    // that are removed once their grace period has passed.
+                    let timeout = queue
+                        .time_until_next_expiry()
+                        .unwrap_or(queue.grace_period())
+                        .max(MIN_EXPIRY_WAIT);
+                    self.changed.wait_for(&mut queue, timeout);
+                }
+            }
+        }
+    }
+
+    /// Gets the element from the front of the queue if it exists, as well as the
+    /// time instant at which it was added.
+    pub fn pop_front(&self) -> Option<(Instant, T)> {
+        let entry = self.lock().pop_front();
+        self.changed.notify_all();
+        entry
+    }
+
+    /// Removes the oldest unexpired element from the queue and returns it, as well as
+    /// the time instant at which it was added, waiting up to `timeout` for an element
+    /// to be pushed if there is none.
+    ///
+    /// Returns `None` if no element became available within the timeout.
+    pub fn pop_front_timeout(&self, timeout: Duration) -> Option<(Instant, T)> {
+        let deadline = std::time::Instant::now() + timeout;
+        let mut queue = self.lock();
+        loop {
+            queue.refresh();
+            if let Some(entry) = queue.pop_front() {
+                drop(queue);
+                self.changed.notify_all();
+                return Some(entry);
+            }
+            if self.changed.wait_until(&mut queue, deadline).timed_out() {
+                return None;
+            }
+        }
+    }
+
+    /// Similar to [`pop_front`](Self::pop_front) but without removing the element.
+    pub fn peek_front(&self) -> Option<(Instant, T)>
+    where
+        T: Clone,
+    {
+        self.lock().peek_front().cloned()
+    }
+
+    /// Gets the number elements currently in the queue, including potentially expired elements.
+    /// See [`TtlQueue::len`].
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Returns `true` if the queue is definitely empty or `false` if the queue is
+    /// possibly empty. See [`TtlQueue::is_empty`].
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Refreshes the queue and returns the number of currently contained elements.
+    pub fn refresh(&self) -> usize {
+        let count = self.lock().refresh();
+        self.changed.notify_all();
+        count
+    }
+
+    /// Sets the grace period. See [`TtlQueue::set_grace_period`].
+    pub fn set_grace_period(&self, grace: Duration) {
+        self.lock().set_grace_period(grace)
+    }
+
+    /// Sets the maximum TTL jitter. See [`TtlQueue::set_ttl_jitter`].
+    pub fn set_ttl_jitter(&self, jitter: Duration) {
+        self.lock().set_ttl_jitter(jitter)
+    }
+
+    /// Gets the number of stale elements. See [`TtlQueue::stale_len`].
+    pub fn stale_len(&self) -> usize {
+        self.lock().stale_len()
+    }
+
+    /// Returns the duration covered by the elements in the queue. See [`TtlQueue::span`].
+    pub fn span(&self) -> Duration {
+        self.lock().span()
+    }
+
+    /// Returns the time until all elements have expired. See [`TtlQueue::time_until_empty`].
+    pub fn time_until_empty(&self) -> Option<Duration> {
+        self.lock().time_until_empty()
+    }
+
+    /// Returns the number of live elements per second. See [`TtlQueue::rate`].
+    pub fn rate(&self) -> f64 {
+        self.lock().rate()
+    }
+
+    /// Returns the smoothed rate. See [`TtlQueue::smoothed_rate`].
+    pub fn smoothed_rate(&self, time_constant: Duration) -> f64 {
+        self.lock().smoothed_rate(time_constant)
+    }
+
+    /// Returns the average duration between two events. See [`TtlQueue::avg_delta`].
+    pub fn avg_delta(&self) -> Duration {
+        self.lock().avg_delta()
+    }
+
+    /// Returns inter-arrival time statistics. See [`TtlQueue::inter_arrival_stats`].
+    pub fn inter_arrival_stats(&self) -> Option<InterArrivalStats> {
+        self.lock().inter_arrival_stats()
+    }
+}
+
+impl<T> From<TtlQueue<T>> for SyncTtlQueue<T> {
+    fn from(queue: TtlQueue<T>) -> Self {
+        Self {
+            queue: Mutex::new(queue),
+            changed: Condvar::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn shared_across_threads() {
+        let queue = Arc::new(SyncTtlQueue::new(Duration::MAX));
+
+        let producers: Vec<_> = (0..4)
+            .map(|_| {
+                let queue = queue.clone();
+                thread::spawn(move || {
+                    for i in 0..100 {
+                        queue.push_back(i);
+                    }
+                })
+            })
+            .collect();
+
+        for producer in producers {
+            producer.join().unwrap();
+        }
+
+        assert_eq!(queue.refresh(), 400);
+        assert_eq!(queue.peek_front().map(|(_, value)| value), Some(0));
+        assert_eq!(queue.lock().iter().count(), 400);
+    }
+
+    #[test]
+    fn blocking_push_waits_for_expiry() {
+        let queue = SyncTtlQueue::with_max_len(Duration::from_millis(20), 1);
+        assert_eq!(queue.pop_front_timeout(Duration::from_millis(5)), None);
+
+        queue.push_back_blocking(1);
+        let start = std::time::Instant::now();
+        assert_eq!(queue.push_back_blocking(2), 1);
+        assert!(start.elapsed() >= Duration::from_millis(15));
+        assert_eq!(
+            queue
+                .pop_front_timeout(Duration::ZERO)
+                .map(|(_, value)| value),
+            Some(2)
+        );
+    }
+
+    #[test]
+    fn changes_through_with_lock_wake_blocked_threads() {
+        let queue = Arc::new(SyncTtlQueue::new(Duration::MAX));
+        let consumer = {
+            let queue = queue.clone();
+            thread::spawn(move || queue.pop_front_timeout(Duration::from_secs(10)))
+        };
+
+        thread::sleep(Duration::from_millis(20));
+        queue.with_lock(|queue| queue.push_back(7));
+        assert_eq!(consumer.join().unwrap().map(|(_, value)| value), Some(7));
+    }
+
+    #[test]
+    fn blocking_push_waits_for_unpredictable_expiry() {
+        // Elements past the TTL that the policy keeps alive have no predictable expiry.
+        let queue = Arc::new(SyncTtlQueue::with_max_len(Duration::ZERO, 1));
+        queue
+            .lock()
+            .set_expiry_policy(|_inserted: Instant, _now: Instant, _value: &i32| false);
+        queue.push_back_blocking(1);
+        assert_eq!(queue.lock().time_until_next_expiry(), Some(Duration::ZERO));
+
+        let consumer = {
+            let queue = queue.clone();
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(20));
+                queue.pop_front()
+            })
+        };
+
+        assert_eq!(queue.push_back_blocking(2), 1);
+        assert_eq!(consumer.join().unwrap().map(|(_, value)| value), Some(1));
+    }
+}
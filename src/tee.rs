@@ -0,0 +1,151 @@
+use crate::{Clock, Instant, SystemClock, TtlQueue};
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// A set of [`TtlQueue`]s with different TTLs that each receive a copy of every
+/// pushed element.
+///
+/// All queues share the same [`Clock`], and [`push_back`](Self::push_back) reads it
+/// only once, so the copies carry identical timestamps, e.g. to maintain 1-minute,
+/// 5-minute and 1-hour windows over the same stream.
+///
+/// ## Example
+///
+/// ```
+/// # use std::time::Duration;
+/// # use ttl_queue::TtlTee;
+/// let mut tee = TtlTee::new([Duration::from_secs(60), Duration::from_secs(3600)]);
+/// tee.push_back("request");
+/// tee.push_back("request");
+///
+/// assert_eq!(tee.refresh(), [2, 2]);
+/// assert_eq!(tee.queue(1).unwrap().ttl(), Duration::from_secs(3600));
+/// ```
+pub struct TtlTee<T> {
+    clock: Arc<dyn Clock>,
+    queues: Vec<TtlQueue<T>>,
+}
+
+impl<T> TtlTee<T> {
+    /// Creates one empty queue per specified TTL, in the given order.
+    pub fn new(ttls: impl IntoIterator<Item = Duration>) -> Self {
+        let clock: Arc<dyn Clock> = Arc::new(SystemClock);
+        let queues = ttls
+            .into_iter()
+            .map(|ttl| {
+                let mut queue = TtlQueue::new(ttl);
+                queue.set_shared_clock(clock.clone());
+                queue
+            })
+            .collect();
+        Self { clock, queues }
+    }
+
+    /// Sets the clock shared by all queues.
+    ///
+    /// Defaults to the [`SystemClock`]. See [`TtlQueue::set_clock`].
+    pub fn set_clock(&mut self, clock: impl Clock + 'static) {
+        self.clock = Arc::new(clock);
+        for queue in &mut self.queues {
+            queue.set_shared_clock(self.clock.clone());
+        }
+    }
+
+    /// Gets the number of queues.
+    pub fn len(&self) -> usize {
+        self.queues.len()
+    }
+
+    /// Returns `true` if there are no queues.
+    pub fn is_empty(&self) -> bool {
+        self.queues.is_empty()
+    }
+
+    /// Gets the queues, in the order of their TTLs passed to [`new`](Self::new).
+    pub fn queues(&self) -> &[TtlQueue<T>] {
+        &self.queues
+    }
+
+    /// Gets the queue at the specified index, if any.
+    pub fn queue(&self, index: usize) -> Option<&TtlQueue<T>> {
+        self.queues.get(index)
+    }
+
+    /// Gets the queue at the specified index mutably, if any.
+    pub fn queue_mut(&mut self, index: usize) -> Option<&mut TtlQueue<T>> {
+        self.queues.get_mut(index)
+    }
+
+    /// Refreshes all queues using a single clock read and returns the number of
+    /// currently contained elements per queue.
+    pub fn refresh(&mut self) -> Vec<usize> {
+        let now = self.clock.now();
+        self.queues
+            .iter_mut()
+            .map(|queue| queue.refresh_at(now))
+            .collect()
+    }
+}
+
+impl<T: Clone> TtlTee<T> {
+    /// Pushes a copy of the element to the end of every queue, stamped with a single
+    /// clock read.
+    ///
+    /// The last queue receives the element itself. Each queue applies its own
+    /// [push hooks](TtlQueue::on_push) and, at its [maximum length](TtlQueue::with_max_len),
+    /// its own overflow policy.
+    pub fn push_back(&mut self, element: T) {
+        let now = self.clock.now();
+        if let Some((last, rest)) = self.queues.split_last_mut() {
+            for queue in rest {
+                Self::push_back_at(queue, now, element.clone());
+            }
+            Self::push_back_at(last, now, element);
+        }
+    }
+
+    fn push_back_at(queue: &mut TtlQueue<T>, now: Instant, element: T) {
+        let Some(element) = queue.apply_push_hooks(element) else {
+            return;
+        };
+        if queue.make_room() {
+            queue.push_back_entry(now, element);
+        }
+    }
+}
+
+impl<T: Debug> Debug for TtlTee<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TtlTee")
+            .field("queues", &self.queues)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TickClock;
+
+    #[test]
+    fn copies_share_timestamps_and_expire_per_queue() {
+        let clock = TickClock::new();
+        let mut tee = TtlTee::new([TickClock::ttl(2), TickClock::ttl(5)]);
+        tee.set_clock(clock.clone());
+
+        tee.push_back(1);
+        clock.tick();
+        tee.push_back(2);
+        assert_eq!(
+            tee.queue(0).unwrap().iter().collect::<Vec<_>>(),
+            tee.queue(1).unwrap().iter().collect::<Vec<_>>()
+        );
+
+        clock.advance(2);
+        assert_eq!(tee.refresh(), [0, 2]);
+        clock.advance(3);
+        assert_eq!(tee.refresh(), [0, 0]);
+        assert_eq!(tee.len(), 2);
+    }
+}
@@ -0,0 +1,156 @@
+use crate::Instant;
+use std::time::Duration;
+
+/// Determines which item of a window is passed by a [`Throttle`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ThrottleEdge {
+    /// The first item of a window is passed immediately, later items are suppressed.
+    #[default]
+    Leading,
+    /// The last item of a window is passed once the window has ended, earlier items
+    /// are suppressed.
+    Trailing,
+}
+
+/// Passes at most one item per window and suppresses the rest.
+///
+/// Depending on the [`ThrottleEdge`], either the first item of a window is passed
+/// right away by [`submit`](Self::submit), or the last item is passed by
+/// [`poll`](Self::poll) once the window has ended. A window starts with the first
+/// item submitted after the previous window ended.
+///
+/// ## Example
+///
+/// ```
+/// # use std::time::Duration;
+/// # use ttl_queue::{Instant, Throttle, ThrottleEdge};
+/// let mut throttle = Throttle::new(Duration::from_secs(1), ThrottleEdge::Leading);
+/// let now = Instant::now();
+///
+/// assert_eq!(throttle.submit_at(now, 1), Some(1));
+/// assert_eq!(throttle.submit_at(now + Duration::from_millis(500), 2), None);
+/// assert_eq!(throttle.submit_at(now + Duration::from_secs(1), 3), Some(3));
+/// assert_eq!(throttle.suppressed(), 1);
+/// ```
+#[derive(Debug)]
+pub struct Throttle<T> {
+    window: Duration,
+    edge: ThrottleEdge,
+    /// The start of the current window, if any.
+    window_start: Option<Instant>,
+    /// The latest item of the current window when throttling on the trailing edge.
+    pending: Option<T>,
+    suppressed: usize,
+}
+
+impl<T> Throttle<T> {
+    /// Creates a throttle passing at most one item per window.
+    pub fn new(window: Duration, edge: ThrottleEdge) -> Self {
+        Self {
+            window,
+            edge,
+            window_start: None,
+            pending: None,
+            suppressed: 0,
+        }
+    }
+
+    /// Gets the duration of a window.
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    /// Gets the edge of the window on which items are passed.
+    pub fn edge(&self) -> ThrottleEdge {
+        self.edge
+    }
+
+    /// Gets the total number of suppressed items.
+    pub fn suppressed(&self) -> usize {
+        self.suppressed
+    }
+
+    /// Submits an item and returns it if it passes the throttle right away.
+    ///
+    /// On the trailing edge, the submitted item is never passed right away; use
+    /// [`poll`](Self::poll) to obtain it once the window has ended. If the previous
+    /// window ended without being polled, its last item is returned instead.
+    pub fn submit(&mut self, item: T) -> Option<T> {
+        self.submit_at(Instant::now(), item)
+    }
+
+    /// Submits an item at the specified instant and returns it if it passes the
+    /// throttle right away.
+    pub fn submit_at(&mut self, now: Instant, item: T) -> Option<T> {
+        let in_window = self.is_in_window(now);
+        match self.edge {
+            ThrottleEdge::Leading if in_window => {
+                self.suppressed += 1;
+                None
+            }
+            ThrottleEdge::Leading => {
+                self.window_start = Some(now);
+                Some(item)
+            }
+            ThrottleEdge::Trailing if in_window => {
+                if self.pending.replace(item).is_some() {
+                    self.suppressed += 1;
+                }
+                None
+            }
+            ThrottleEdge::Trailing => {
+                self.window_start = Some(now);
+                self.pending.replace(item)
+            }
+        }
+    }
+
+    /// Takes the last item of the window once it has ended when throttling on the
+    /// trailing edge.
+    pub fn poll(&mut self) -> Option<T> {
+        self.poll_at(Instant::now())
+    }
+
+    /// Takes the last item of the window if it has ended at the specified instant.
+    pub fn poll_at(&mut self, now: Instant) -> Option<T> {
+        if self.is_in_window(now) {
+            return None;
+        }
+        self.window_start = None;
+        self.pending.take()
+    }
+
+    /// Returns `true` if the current window has not yet ended at the specified instant.
+    fn is_in_window(&self, now: Instant) -> bool {
+        self.window_start
+            .is_some_and(|start| now.saturating_duration_since(start) < self.window)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trailing_edge_passes_last_item() {
+        let mut throttle = Throttle::new(Duration::from_secs(1), ThrottleEdge::Trailing);
+        let now = Instant::now();
+        assert_eq!(throttle.submit_at(now, 1), None);
+        assert_eq!(
+            throttle.submit_at(now + Duration::from_millis(300), 2),
+            None
+        );
+        assert_eq!(
+            throttle.submit_at(now + Duration::from_millis(600), 3),
+            None
+        );
+
+        assert_eq!(throttle.poll_at(now + Duration::from_millis(900)), None);
+        assert_eq!(throttle.poll_at(now + Duration::from_secs(1)), Some(3));
+        assert_eq!(throttle.poll_at(now + Duration::from_secs(5)), None);
+        assert_eq!(throttle.suppressed(), 2);
+
+        assert_eq!(throttle.submit_at(now + Duration::from_secs(6), 4), None);
+        assert_eq!(throttle.submit_at(now + Duration::from_secs(7), 5), Some(4));
+    }
+}
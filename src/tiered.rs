@@ -0,0 +1,160 @@
+use crate::{Instant, TtlQueue};
+use std::fmt::{Debug, Formatter};
+use std::time::Duration;
+
+type Demotion<H, W> = Box<dyn FnMut(H) -> Option<W> + Send + Sync>;
+
+/// A two-tier queue keeping detailed recent elements in a hot window and demoting
+/// them into a warm window before they are dropped.
+///
+/// Elements live in the hot window for its TTL. On a [`refresh`](Self::refresh),
+/// elements that expired from the hot window are passed to the demotion function,
+/// which may transform them into a cheaper summary or return `None` to drop them,
+/// e.g. to keep only every n-th element or to aggregate several of them. Demoted
+/// elements are stamped with the instant at which they left the hot window and
+/// live in the warm window for its TTL.
+///
+/// ## Example
+///
+/// ```
+/// # use std::time::Duration;
+/// # use ttl_queue::{Instant, TieredTtlQueue};
+/// let mut latencies = TieredTtlQueue::new(
+///     Duration::from_secs(60),
+///     Duration::from_secs(3600),
+///     |sample: Vec<u32>| sample.iter().max().copied(),
+/// );
+///
+/// let now = Instant::now();
+/// latencies.push_back_at(now, vec![12, 48, 17]);
+///
+/// latencies.refresh_at(now + Duration::from_secs(10));
+/// assert_eq!((latencies.hot().len(), latencies.warm().len()), (1, 0));
+///
+/// latencies.refresh_at(now + Duration::from_secs(60));
+/// assert_eq!((latencies.hot().len(), latencies.warm().len()), (0, 1));
+/// assert_eq!(latencies.warm().iter().next().map(|(_, max)| *max), Some(48));
+/// ```
+pub struct TieredTtlQueue<H, W> {
+    hot: TtlQueue<H>,
+    warm: TtlQueue<W>,
+    demote: Demotion<H, W>,
+}
+
+impl<H, W> TieredTtlQueue<H, W> {
+    /// Creates an empty tiered queue with the TTLs of the hot and warm windows and
+    /// the function demoting elements from the hot into the warm window.
+    pub fn new<F>(hot_ttl: Duration, warm_ttl: Duration, demote: F) -> Self
+    where
+        F: FnMut(H) -> Option<W> + Send + Sync + 'static,
+    {
+        Self {
+            hot: TtlQueue::new(hot_ttl),
+            warm: TtlQueue::new(warm_ttl),
+            demote: Box::new(demote),
+        }
+    }
+
+    /// Gets the hot window holding the recent elements.
+    pub fn hot(&self) -> &TtlQueue<H> {
+        &self.hot
+    }
+
+    /// Gets the warm window holding the demoted elements.
+    pub fn warm(&self) -> &TtlQueue<W> {
+        &self.warm
+    }
+
+    /// Gets the total number of elements in both windows, including potentially
+    /// expired elements.
+    pub fn len(&self) -> usize {
+        self.hot.len() + self.warm.len()
+    }
+
+    /// Returns `true` if both windows are definitely empty or `false` if they are
+    /// possibly empty.
+    pub fn is_empty(&self) -> bool {
+        self.hot.is_empty() && self.warm.is_empty()
+    }
+
+    /// Pushes an element to the end of the hot window.
+    pub fn push_back(&mut self, element: H) {
+        self.push_back_at(Instant::now(), element)
+    }
+
+    /// Pushes an element stamped with the specified instant to the end of the hot window.
+    ///
+    /// The instant must not be earlier than that of the previously pushed element.
+    pub fn push_back_at(&mut self, instant: Instant, element: H) {
+        self.hot.push_back_entry(instant, element);
+    }
+
+    /// Demotes the elements that expired from the hot window, drops the elements that
+    /// expired from the warm window and returns the number of demoted elements.
+    pub fn refresh(&mut self) -> usize {
+        self.refresh_at(Instant::now())
+    }
+
+    /// Demotes and drops the elements that expired at the specified instant and
+    /// returns the number of demoted elements.
+    pub fn refresh_at(&mut self, now: Instant) -> usize {
+        let hot_ttl = self.hot.ttl();
+        let mut demoted = 0;
+        while let Some((instant, _)) = self.hot.front_entry() {
+            if now.saturating_duration_since(*instant) < hot_ttl {
+                break;
+            }
+
+            let (instant, element) = self.hot.pop_front_entry().expect("front exists");
+            if let Some(summary) = (self.demote)(element) {
+                self.warm.push_back_entry(instant + hot_ttl, summary);
+                demoted += 1;
+            }
+        }
+
+        self.warm.refresh_at(now);
+        demoted
+    }
+}
+
+impl<H: Debug, W: Debug> Debug for TieredTtlQueue<H, W> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TieredTtlQueue")
+            .field("hot", &self.hot)
+            .field("warm", &self.warm)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn demoted_elements_expire_from_warm_window() {
+        let mut every_other = false;
+        let mut queue = TieredTtlQueue::new(Duration::from_secs(10), Duration::from_secs(20), {
+            move |value: u32| {
+                every_other = !every_other;
+                every_other.then_some(value)
+            }
+        });
+
+        let now = Instant::now();
+        for i in 0..4 {
+            queue.push_back_at(now + Duration::from_secs(i), i as u32);
+        }
+
+        assert_eq!(queue.refresh_at(now + Duration::from_secs(13)), 2);
+        assert_eq!(
+            queue.warm().iter().map(|(_, v)| *v).collect::<Vec<_>>(),
+            [0, 2]
+        );
+        assert!(queue.hot().is_empty());
+
+        queue.refresh_at(now + Duration::from_secs(30));
+        assert_eq!(queue.warm().len(), 1);
+        queue.refresh_at(now + Duration::from_secs(32));
+        assert!(queue.is_empty());
+    }
+}
@@ -0,0 +1,173 @@
+use crate::Instant;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// Accumulates items into consecutive, non-overlapping windows of a fixed size
+/// and emits each window as a batch once it is complete.
+///
+/// Where a [`TtlQueue`](crate::TtlQueue) maintains a sliding window, the windows of
+/// a tumbling window are aligned to its creation and every item belongs to exactly
+/// one window. Windows without any items are skipped.
+///
+/// ## Example
+///
+/// ```
+/// # use std::time::Duration;
+/// # use ttl_queue::{Instant, TumblingWindow};
+/// let start = Instant::now();
+/// let mut window = TumblingWindow::starting_at(start, Duration::from_secs(1));
+/// window.push_at(start + Duration::from_millis(100), "a");
+/// window.push_at(start + Duration::from_millis(900), "b");
+/// window.push_at(start + Duration::from_millis(1200), "c");
+///
+/// assert_eq!(window.poll_flush_at(start + Duration::from_millis(999)), None);
+///
+/// let batch = window.poll_flush_at(start + Duration::from_secs(2)).unwrap();
+/// assert_eq!(batch.iter().map(|(_, item)| *item).collect::<Vec<_>>(), ["a", "b"]);
+///
+/// let batch = window.poll_flush_at(start + Duration::from_secs(2)).unwrap();
+/// assert_eq!(batch.len(), 1);
+/// ```
+#[derive(Debug)]
+pub struct TumblingWindow<T> {
+    size: Duration,
+    /// The start of the oldest window that was not yet flushed.
+    window_start: Instant,
+    items: VecDeque<(Instant, T)>,
+}
+
+impl<T> TumblingWindow<T> {
+    /// Creates a tumbling window of the specified size whose first window starts now.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `size` is zero.
+    pub fn new(size: Duration) -> Self {
+        Self::starting_at(Instant::now(), size)
+    }
+
+    /// Creates a tumbling window of the specified size whose first window starts at
+    /// the specified instant.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `size` is zero.
+    pub fn starting_at(start: Instant, size: Duration) -> Self {
+        assert!(!size.is_zero(), "The window size must not be zero");
+        Self {
+            size,
+            window_start: start,
+            items: VecDeque::new(),
+        }
+    }
+
+    /// Gets the size of each window.
+    pub fn size(&self) -> Duration {
+        self.size
+    }
+
+    /// Gets the start of the oldest window that was not yet flushed.
+    pub fn window_start(&self) -> Instant {
+        self.window_start
+    }
+
+    /// Gets the number of items that were not yet flushed.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Returns `true` if there are no items that were not yet flushed.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Adds an item to the current window.
+    pub fn push(&mut self, item: T) {
+        self.push_at(Instant::now(), item)
+    }
+
+    /// Adds an item stamped with the specified instant.
+    ///
+    /// The instant must not be earlier than that of the previously added item.
+    /// Items stamped before the oldest unflushed window are added to that window.
+    pub fn push_at(&mut self, instant: Instant, item: T) {
+        self.items.push_back((instant.max(self.window_start), item));
+    }
+
+    /// Emits the items of the oldest complete window, if any.
+    ///
+    /// If several windows have completed since the last flush, each call emits
+    /// the next one.
+    pub fn poll_flush(&mut self) -> Option<Vec<(Instant, T)>> {
+        self.poll_flush_at(Instant::now())
+    }
+
+    /// Emits the items of the oldest window complete at the specified instant, if any.
+    pub fn poll_flush_at(&mut self, now: Instant) -> Option<Vec<(Instant, T)>> {
+        let (first, _) = self.items.front()?;
+        self.window_start = self.window_containing(*first);
+
+        let window_end = self.window_start + self.size;
+        if now < window_end {
+            return None;
+        }
+
+        let count = self
+            .items
+            .iter()
+            .take_while(|(instant, _)| *instant < window_end)
+            .count();
+        self.window_start = window_end;
+        Some(self.items.drain(..count).collect())
+    }
+
+    /// Waits until the oldest window containing items is complete and emits its items.
+    ///
+    /// If there are no items, the future never completes. It is cancellation safe,
+    /// so it can be used in a `tokio::select!` loop alongside the source of items.
+    #[cfg(feature = "tokio")]
+    pub async fn flushed(&mut self) -> Vec<(Instant, T)> {
+        loop {
+            let Some((first, _)) = self.items.front() else {
+                return std::future::pending().await;
+            };
+
+            let deadline = self.window_containing(*first) + self.size;
+            tokio::time::sleep_until(deadline).await;
+            if let Some(batch) = self.poll_flush() {
+                return batch;
+            }
+        }
+    }
+
+    /// Gets the start of the window containing the specified instant.
+    fn window_containing(&self, instant: Instant) -> Instant {
+        let offset = instant.saturating_duration_since(self.window_start);
+        let windows = offset.as_nanos() / self.size.as_nanos();
+        let windows = u32::try_from(windows).unwrap_or(u32::MAX);
+        self.window_start + self.size.saturating_mul(windows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_windows_are_skipped() {
+        let start = Instant::now();
+        let mut window = TumblingWindow::starting_at(start, Duration::from_secs(1));
+        window.push_at(start + Duration::from_millis(5500), 1);
+        window.push_at(start + Duration::from_millis(5900), 2);
+
+        assert_eq!(window.poll_flush_at(start + Duration::from_secs(5)), None);
+        assert_eq!(window.window_start(), start + Duration::from_secs(5));
+
+        let batch = window
+            .poll_flush_at(start + Duration::from_secs(6))
+            .unwrap();
+        assert_eq!(batch.len(), 2);
+        assert_eq!(window.window_start(), start + Duration::from_secs(6));
+        assert_eq!(window.poll_flush_at(start + Duration::from_secs(60)), None);
+    }
+}
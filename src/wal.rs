@@ -0,0 +1,579 @@
+use std::collections::VecDeque;
+use std::fmt::{Debug, Formatter};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// A record appending a payload: the kind, the timestamp in nanoseconds since the
+/// Unix epoch, the payload length and the payload.
+const PUSH: u8 = 0;
+
+/// A record removing payloads from the front: the kind and the number of payloads.
+const REMOVE: u8 = 1;
+
+/// The size of a push record without its payload.
+const PUSH_HEADER_LEN: u64 = 13;
+
+/// The size of a remove record.
+const REMOVE_LEN: u64 = 5;
+
+/// The number of obsolete log bytes below which the log is never compacted automatically.
+const DEFAULT_MIN_COMPACTION_BYTES: u64 = 64 * 1024;
+
+/// A TTL queue of byte payloads that records every push and removal in an append-only
+/// write-ahead log, so that its exact state can be reconstructed after a restart.
+///
+/// Elements of other types are to be serialized before pushing them, since the log
+/// only stores bytes.
+///
+/// The log is compacted by rewriting only the live payloads once the records of removed
+/// payloads outweigh them, so it does not grow beyond roughly twice the size of the
+/// window. Compaction runs on a background thread and is completed by a later
+/// [`refresh`](Self::refresh), so that pushes are not stalled. Payloads are stamped with the wall-clock time, since
+/// [`Instant`](crate::Instant)s cannot be compared across processes.
+///
+/// Records are written to the file immediately, but not synced; use [`sync`](Self::sync)
+/// to wait until they are persisted.
+///
+/// ## Example
+///
+/// ```
+/// # use std::time::Duration;
+/// # use ttl_queue::WalTtlQueue;
+/// let path = std::env::temp_dir().join("wal-ttl-queue-example.log");
+/// # let _ = std::fs::remove_file(&path);
+/// {
+///     let mut queue = WalTtlQueue::open(&path, Duration::from_secs(60)).unwrap();
+///     queue.push_back(b"first").unwrap();
+///     queue.push_back(b"second").unwrap();
+///     queue.pop_front().unwrap();
+/// }
+///
+/// let reopened = WalTtlQueue::open(&path, Duration::from_secs(60)).unwrap();
+/// assert_eq!(reopened.len(), 1);
+/// let (_time, payload) = reopened.peek_front().unwrap();
+/// assert_eq!(payload, b"second");
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+pub struct WalTtlQueue {
+    path: PathBuf,
+    log: File,
+    entries: VecDeque<(SystemTime, Vec<u8>)>,
+    ttl: Duration,
+    /// The size of the log file.
+    log_bytes: u64,
+    /// The size of the push records of the live payloads.
+    live_bytes: u64,
+    min_compaction_bytes: u64,
+    compaction: Option<Compaction>,
+}
+
+/// A compaction running in the background.
+struct Compaction {
+    /// The size of the log when the live payloads were captured; later records are
+    /// copied to the compacted log when it is completed.
+    log_bytes: u64,
+    /// The size of the compacted log as written in the background.
+    compacted_bytes: u64,
+    temp_path: PathBuf,
+    handle: JoinHandle<io::Result<File>>,
+}
+
+impl WalTtlQueue {
+    /// Opens the queue logged in the specified file, creating the file if it does not exist.
+    ///
+    /// The queue is reconstructed by replaying the log, including payloads that expired
+    /// while the file was closed; call [`refresh`](Self::refresh) to remove them. A
+    /// record left incomplete by a crash is discarded.
+    ///
+    /// ## Errors
+    ///
+    /// Returns an error of kind [`InvalidData`](io::ErrorKind::InvalidData) if the log
+    /// contains an unknown record.
+    pub fn open(path: impl AsRef<Path>, ttl: Duration) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let log = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(&path)?;
+
+        let mut queue = Self {
+            path,
+            log,
+            entries: VecDeque::new(),
+            ttl,
+            log_bytes: 0,
+            live_bytes: 0,
+            min_compaction_bytes: DEFAULT_MIN_COMPACTION_BYTES,
+            compaction: None,
+        };
+        queue.replay()?;
+        Ok(queue)
+    }
+
+    /// Reconstructs the entries from the log and truncates an incomplete last record.
+    fn replay(&mut self) -> io::Result<()> {
+        let file_bytes = self.log.metadata()?.len();
+        let mut reader = BufReader::new(self.log.try_clone()?);
+        let mut valid_bytes = 0;
+        loop {
+            let mut kind = [0];
+            if reader.read(&mut kind)? == 0 {
+                break;
+            }
+
+            match kind[0] {
+                PUSH => {
+                    let mut header = [0; PUSH_HEADER_LEN as usize - 1];
+                    let mut payload = Vec::new();
+                    let complete = read_complete(&mut reader, &mut header)? && {
+                        // A torn record may claim any length, so it is checked against
+                        // the file before allocating the payload.
+                        let len = u32::from_le_bytes(header[8..].try_into().expect("4 bytes"));
+                        let remaining = file_bytes - valid_bytes - PUSH_HEADER_LEN;
+                        u64::from(len) <= remaining && {
+                            payload.resize(len as usize, 0);
+                            read_complete(&mut reader, &mut payload)?
+                        }
+                    };
+                    if !complete {
+                        break;
+                    }
+
+                    let nanos = u64::from_le_bytes(header[..8].try_into().expect("8 bytes"));
+                    valid_bytes += PUSH_HEADER_LEN + payload.len() as u64;
+                    self.live_bytes += PUSH_HEADER_LEN + payload.len() as u64;
+                    self.entries
+                        .push_back((UNIX_EPOCH + Duration::from_nanos(nanos), payload));
+                }
+                REMOVE => {
+                    let mut count = [0; REMOVE_LEN as usize - 1];
+                    if !read_complete(&mut reader, &mut count)? {
+                        break;
+                    }
+
+                    valid_bytes += REMOVE_LEN;
+                    for _ in 0..u32::from_le_bytes(count) {
+                        self.remove_entry();
+                    }
+                }
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "unknown write-ahead log record",
+                    ))
+                }
+            }
+        }
+
+        self.log.set_len(valid_bytes)?;
+        self.log_bytes = valid_bytes;
+        Ok(())
+    }
+
+    /// Gets the path of the log file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Gets the time-to-live of the payloads.
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Gets the number of payloads, including potentially expired payloads.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if the queue is definitely empty or `false` if it is possibly empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Gets the size of the log file in bytes.
+    pub fn log_bytes(&self) -> u64 {
+        self.log_bytes
+    }
+
+    /// Sets the number of bytes of obsolete records below which the log is not compacted
+    /// automatically. Defaults to 64 KiB.
+    pub fn set_min_compaction_bytes(&mut self, bytes: u64) {
+        self.min_compaction_bytes = bytes;
+    }
+
+    /// Pushes a payload stamped with the current wall-clock time to the end of the queue.
+    pub fn push_back(&mut self, payload: impl Into<Vec<u8>>) -> io::Result<()> {
+        self.push_back_at(SystemTime::now(), payload)
+    }
+
+    /// Pushes a payload stamped with the specified wall-clock time to the end of the queue.
+    ///
+    /// The time must not be earlier than that of the previously pushed payload.
+    ///
+    /// ## Errors
+    ///
+    /// Returns an error if the log cannot be written, in which case the queue is left
+    /// unchanged, or of kind [`InvalidInput`](io::ErrorKind::InvalidInput) if the
+    /// payload exceeds 4 GiB or the time cannot be represented.
+    pub fn push_back_at(
+        &mut self,
+        time: SystemTime,
+        payload: impl Into<Vec<u8>>,
+    ) -> io::Result<()> {
+        let payload = payload.into();
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidInput, message);
+        let len = u32::try_from(payload.len()).map_err(|_| invalid("payload too large"))?;
+        let nanos = time
+            .duration_since(UNIX_EPOCH)
+            .ok()
+            .and_then(|since| u64::try_from(since.as_nanos()).ok())
+            .ok_or_else(|| invalid("time not representable"))?;
+
+        let mut record = Vec::with_capacity(PUSH_HEADER_LEN as usize + payload.len());
+        record.push(PUSH);
+        record.extend_from_slice(&nanos.to_le_bytes());
+        record.extend_from_slice(&len.to_le_bytes());
+        record.extend_from_slice(&payload);
+        self.append(&record)?;
+
+        self.live_bytes += record.len() as u64;
+        self.entries.push_back((time, payload));
+        Ok(())
+    }
+
+    /// Gets the payload at the front of the queue, as well as the wall-clock time at
+    /// which it was added.
+    pub fn peek_front(&self) -> Option<(SystemTime, &[u8])> {
+        self.entries
+            .front()
+            .map(|(time, payload)| (*time, payload.as_slice()))
+    }
+
+    /// Removes the payload from the front of the queue and returns it, as well as the
+    /// wall-clock time at which it was added.
+    ///
+    /// ## Errors
+    ///
+    /// Returns an error if the log cannot be written, in which case the queue is left
+    /// unchanged.
+    pub fn pop_front(&mut self) -> io::Result<Option<(SystemTime, Vec<u8>)>> {
+        if self.entries.is_empty() {
+            return Ok(None);
+        }
+        self.log_removal(1)?;
+        Ok(self.remove_entry())
+    }
+
+    /// Refreshes the queue and returns the number of currently contained payloads.
+    pub fn refresh(&mut self) -> io::Result<usize> {
+        self.refresh_at(SystemTime::now())
+    }
+
+    /// Refreshes the queue as of the specified wall-clock time and returns the number
+    /// of currently contained payloads.
+    ///
+    /// Completes a finished background compaction, and starts one if the records of
+    /// removed payloads outweigh the live ones.
+    ///
+    /// Payloads stamped later than `now`, e.g. after the system time was set back, are
+    /// not considered expired.
+    ///
+    /// ## Errors
+    ///
+    /// Returns an error if the log cannot be written, in which case the queue is left
+    /// unchanged, or if a background compaction failed, in which case the log is left
+    /// uncompacted.
+    pub fn refresh_at(&mut self, now: SystemTime) -> io::Result<usize> {
+        let count = self
+            .entries
+            .iter()
+            .take_while(|(time, _)| now.duration_since(*time).is_ok_and(|age| age >= self.ttl))
+            .count();
+
+        let mut remaining = count;
+        while remaining > 0 {
+            let chunk = remaining.min(u32::MAX as usize);
+            self.log_removal(chunk as u32)?;
+            for _ in 0..chunk {
+                self.remove_entry();
+            }
+            remaining -= chunk;
+        }
+
+        if self
+            .compaction
+            .as_ref()
+            .is_some_and(|compaction| compaction.handle.is_finished())
+        {
+            self.wait_for_compaction()?;
+        }
+
+        let obsolete_bytes = self.log_bytes - self.live_bytes;
+        if self.compaction.is_none()
+            && obsolete_bytes >= self.min_compaction_bytes.max(self.live_bytes)
+        {
+            self.start_compaction()?;
+        }
+        Ok(self.len())
+    }
+
+    fn log_removal(&mut self, count: u32) -> io::Result<()> {
+        let mut record = [0; REMOVE_LEN as usize];
+        record[0] = REMOVE;
+        record[1..].copy_from_slice(&count.to_le_bytes());
+        self.append(&record)
+    }
+
+    /// Appends a record to the log, cutting off whatever part of it was written if
+    /// writing fails, so that no torn record is followed by later ones.
+    fn append(&mut self, record: &[u8]) -> io::Result<()> {
+        if let Err(error) = self.log.write_all(record) {
+            let _ = self.log.set_len(self.log_bytes);
+            return Err(error);
+        }
+        self.log_bytes += record.len() as u64;
+        Ok(())
+    }
+
+    fn remove_entry(&mut self) -> Option<(SystemTime, Vec<u8>)> {
+        let entry = self.entries.pop_front()?;
+        self.live_bytes -= PUSH_HEADER_LEN + entry.1.len() as u64;
+        Some(entry)
+    }
+
+    /// Rewrites the log to contain only the push records of the live payloads and
+    /// waits until it is done.
+    ///
+    /// The compacted log is written to a temporary file next to the log, which then
+    /// atomically replaces it, so that a crash never loses the state.
+    pub fn compact(&mut self) -> io::Result<()> {
+        self.wait_for_compaction()?;
+        self.start_compaction()?;
+        self.wait_for_compaction()
+    }
+
+    /// Waits until a background compaction is done and replaces the log with the
+    /// compacted one.
+    ///
+    /// ## Errors
+    ///
+    /// Returns an error if the compaction failed, in which case the log is left
+    /// uncompacted.
+    pub fn wait_for_compaction(&mut self) -> io::Result<()> {
+        let Some(compaction) = self.compaction.take() else {
+            return Ok(());
+        };
+        let temp_path = compaction.temp_path.clone();
+        let result = self.complete_compaction(compaction);
+        if result.is_err() {
+            let _ = fs::remove_file(temp_path);
+        }
+        result
+    }
+
+    /// Writes the live payloads to a temporary file on a background thread.
+    fn start_compaction(&mut self) -> io::Result<()> {
+        let mut temp_path = self.path.clone().into_os_string();
+        temp_path.push(".compact");
+        let temp_path = PathBuf::from(temp_path);
+
+        let file = File::create(&temp_path)?;
+        let entries: Vec<_> = self.entries.iter().cloned().collect();
+        let handle = thread::spawn(move || {
+            let mut writer = BufWriter::new(file);
+            for (time, payload) in &entries {
+                let nanos = time
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |since| since.as_nanos() as u64);
+                writer.write_all(&[PUSH])?;
+                writer.write_all(&nanos.to_le_bytes())?;
+                writer.write_all(&(payload.len() as u32).to_le_bytes())?;
+                writer.write_all(payload)?;
+            }
+            writer.into_inner().map_err(io::IntoInnerError::into_error)
+        });
+
+        self.compaction = Some(Compaction {
+            log_bytes: self.log_bytes,
+            compacted_bytes: self.live_bytes,
+            temp_path,
+            handle,
+        });
+        Ok(())
+    }
+
+    /// Copies the records logged since the compaction started to the compacted log,
+    /// which then replaces the log.
+    fn complete_compaction(&mut self, compaction: Compaction) -> io::Result<()> {
+        let mut compacted = compaction.handle.join().map_err(|_| {
+            io::Error::new(io::ErrorKind::Other, "write-ahead log compaction panicked")
+        })??;
+
+        let mut tail = self.log.try_clone()?;
+        tail.seek(SeekFrom::Start(compaction.log_bytes))?;
+        let tail_bytes = io::copy(&mut tail, &mut compacted)?;
+        compacted.sync_all()?;
+        fs::rename(&compaction.temp_path, &self.path)?;
+
+        self.log = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .open(&self.path)?;
+        self.log_bytes = compaction.compacted_bytes + tail_bytes;
+        Ok(())
+    }
+
+    /// Returns an iterator over the payloads, oldest first, as well as the wall-clock
+    /// times at which they were added.
+    pub fn iter(&self) -> impl Iterator<Item = (SystemTime, &[u8])> + '_ {
+        self.entries
+            .iter()
+            .map(|(time, payload)| (*time, payload.as_slice()))
+    }
+
+    /// Waits until all records are persisted.
+    pub fn sync(&self) -> io::Result<()> {
+        self.log.sync_data()
+    }
+}
+
+/// Fills the buffer from the reader and returns `false` if the reader ended before.
+fn read_complete(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<bool> {
+    match reader.read_exact(buf) {
+        Ok(()) => Ok(true),
+        Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => Ok(false),
+        Err(error) => Err(error),
+    }
+}
+
+impl Drop for WalTtlQueue {
+    fn drop(&mut self) {
+        let _ = self.wait_for_compaction();
+    }
+}
+
+impl Debug for WalTtlQueue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WalTtlQueue")
+            .field("path", &self.path)
+            .field("ttl", &self.ttl)
+            .field("len", &self.entries.len())
+            .field("log_bytes", &self.log_bytes)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replay_and_compaction_preserve_state() {
+        let path = std::env::temp_dir().join(format!("ttl-queue-wal-{}.log", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let start = UNIX_EPOCH + Duration::from_secs(1_000_000);
+        let secs = |secs: u64| start + Duration::from_secs(secs);
+
+        {
+            let mut queue = WalTtlQueue::open(&path, Duration::from_secs(10)).unwrap();
+            queue.set_min_compaction_bytes(0);
+            for i in 0..6 {
+                queue.push_back_at(secs(i), vec![i as u8; 8]).unwrap();
+            }
+            assert_eq!(queue.refresh_at(secs(11)).unwrap(), 4);
+            assert_eq!(queue.pop_front().unwrap().unwrap().0, secs(2));
+            assert_eq!(queue.log_bytes(), 6 * 21 + 2 * 5);
+
+            // Removing the majority of the window compacts the log.
+            assert_eq!(queue.refresh_at(secs(14)).unwrap(), 1);
+            queue.wait_for_compaction().unwrap();
+            assert_eq!(queue.log_bytes(), 21);
+            queue.push_back_at(secs(14), b"new".to_vec()).unwrap();
+        }
+
+        // A record cut off by a crash is discarded.
+        let mut log = OpenOptions::new().append(true).open(&path).unwrap();
+        log.write_all(&[PUSH, 1, 2, 3]).unwrap();
+        drop(log);
+
+        let queue = WalTtlQueue::open(&path, Duration::from_secs(10)).unwrap();
+        let entries: Vec<_> = queue
+            .iter()
+            .map(|(time, payload)| (time, payload.to_vec()))
+            .collect();
+        assert_eq!(
+            entries,
+            [(secs(5), vec![5; 8]), (secs(14), b"new".to_vec())]
+        );
+        assert_eq!(queue.log_bytes(), 21 + 16);
+        assert_eq!(fs::metadata(&path).unwrap().len(), 21 + 16);
+
+        drop(queue);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn records_logged_during_compaction_are_kept() {
+        let path =
+            std::env::temp_dir().join(format!("ttl-queue-wal-bg-{}.log", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let start = UNIX_EPOCH + Duration::from_secs(1_000_000);
+        let secs = |secs: u64| start + Duration::from_secs(secs);
+
+        {
+            let mut queue = WalTtlQueue::open(&path, Duration::from_secs(10)).unwrap();
+            queue.set_min_compaction_bytes(0);
+            for i in 0..4 {
+                queue.push_back_at(secs(i), vec![i as u8; 8]).unwrap();
+            }
+            assert_eq!(queue.refresh_at(secs(12)).unwrap(), 1);
+
+            // The compaction runs while further records are logged.
+            queue.push_back_at(secs(13), b"late".to_vec()).unwrap();
+            queue.pop_front().unwrap();
+            queue.wait_for_compaction().unwrap();
+            assert_eq!(queue.log_bytes(), 21 + 17 + 5);
+            assert_eq!(fs::metadata(&path).unwrap().len(), 21 + 17 + 5);
+        }
+
+        let queue = WalTtlQueue::open(&path, Duration::from_secs(10)).unwrap();
+        let entries: Vec<_> = queue
+            .iter()
+            .map(|(time, payload)| (time, payload.to_vec()))
+            .collect();
+        assert_eq!(entries, [(secs(13), b"late".to_vec())]);
+
+        drop(queue);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn torn_record_with_oversized_length_is_discarded() {
+        let path =
+            std::env::temp_dir().join(format!("ttl-queue-wal-torn-{}.log", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        {
+            let mut queue = WalTtlQueue::open(&path, Duration::from_secs(10)).unwrap();
+            queue.push_back(b"kept".to_vec()).unwrap();
+        }
+
+        // A header claiming a payload of 4 GiB without the payload behind it.
+        let mut log = OpenOptions::new().append(true).open(&path).unwrap();
+        log.write_all(&[PUSH, 0, 0, 0, 0, 0, 0, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF])
+            .unwrap();
+        drop(log);
+
+        let queue = WalTtlQueue::open(&path, Duration::from_secs(10)).unwrap();
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue.peek_front().unwrap().1, b"kept");
+        assert_eq!(fs::metadata(&path).unwrap().len(), 17);
+
+        drop(queue);
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,237 @@
+use crate::Instant;
+use std::fmt::{Debug, Formatter};
+use std::time::Duration;
+
+/// The number of slots per wheel level, as a power of two.
+const SLOT_BITS: u32 = 6;
+
+/// The number of slots per wheel level.
+const SLOTS: usize = 1 << SLOT_BITS;
+
+/// The number of wheel levels. Each level covers [`SLOTS`] times the range of the
+/// level below; entries beyond the top level are kept in an overflow list.
+const LEVELS: usize = 4;
+
+/// A hierarchical timing wheel for elements with individual time-to-live values.
+///
+/// Where a [`TtlQueue`](crate::TtlQueue) expires elements in insertion order under a
+/// single TTL, a timing wheel places each element into a slot according to its own
+/// deadline. Short deadlines land in the finest level, long ones in coarser levels
+/// from which they cascade down as time advances. Inserting an element is O(1) and
+/// every element is moved at most once per level, making expiry O(1) amortized
+/// regardless of how widely the TTLs are spread.
+///
+/// Deadlines are rounded up to the configured tick resolution, so elements expire
+/// at most one tick late and never early.
+///
+/// ## Example
+///
+/// ```
+/// # use std::time::Duration;
+/// # use ttl_queue::{Instant, TimingWheel};
+/// let mut wheel = TimingWheel::new(Duration::from_millis(1));
+/// let now = Instant::now();
+/// wheel.insert_at(now, Duration::from_millis(5), "short");
+/// wheel.insert_at(now, Duration::from_secs(3600), "long");
+///
+/// let expired = wheel.expire_at(now + Duration::from_millis(10));
+/// assert_eq!(expired.len(), 1);
+/// assert_eq!(expired[0].1, "short");
+/// assert_eq!(wheel.len(), 1);
+/// ```
+pub struct TimingWheel<T> {
+    /// The reference point of tick zero.
+    start: Instant,
+    /// The duration of a single tick.
+    tick: Duration,
+    /// The last tick that was processed.
+    current: u64,
+    /// The wheel levels, each consisting of [`SLOTS`] slots.
+    levels: Vec<Vec<Vec<WheelEntry<T>>>>,
+    /// Entries whose deadline lies beyond the range of the top level.
+    overflow: Vec<WheelEntry<T>>,
+    len: usize,
+}
+
+/// An element stored in a [`TimingWheel`].
+struct WheelEntry<T> {
+    /// The tick at which the element expires.
+    deadline: u64,
+    /// The instant at which the element was inserted.
+    instant: Instant,
+    value: T,
+}
+
+impl<T> TimingWheel<T> {
+    /// Creates an empty timing wheel with the specified tick resolution.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `tick` is zero.
+    pub fn new(tick: Duration) -> Self {
+        assert!(!tick.is_zero(), "The tick duration must not be zero");
+        Self {
+            start: Instant::now(),
+            tick,
+            current: 0,
+            levels: (0..LEVELS)
+                .map(|_| (0..SLOTS).map(|_| Vec::new()).collect())
+                .collect(),
+            overflow: Vec::new(),
+            len: 0,
+        }
+    }
+
+    /// Gets the tick resolution of the wheel.
+    pub fn tick(&self) -> Duration {
+        self.tick
+    }
+
+    /// Gets the number of elements in the wheel.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the wheel contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Inserts an element that expires after the specified time-to-live.
+    pub fn insert(&mut self, ttl: Duration, value: T) {
+        self.insert_at(Instant::now(), ttl, value)
+    }
+
+    /// Inserts an element stamped with the specified instant that expires after the
+    /// specified time-to-live.
+    pub fn insert_at(&mut self, instant: Instant, ttl: Duration, value: T) {
+        let elapsed = instant.saturating_duration_since(self.start);
+        let deadline = self.ticks_ceil(elapsed.saturating_add(ttl));
+        self.len += 1;
+        self.place(WheelEntry {
+            // Deadlines that already passed expire on the next processed tick.
+            deadline: deadline.max(self.current + 1),
+            instant,
+            value,
+        });
+    }
+
+    /// Removes and returns all elements whose time-to-live has elapsed.
+    pub fn expire(&mut self) -> Vec<(Instant, T)> {
+        self.expire_at(Instant::now())
+    }
+
+    /// Removes and returns all elements whose time-to-live has elapsed at the
+    /// specified instant.
+    ///
+    /// Elements are returned ordered by tick; elements expiring within the same
+    /// tick are returned in no particular order.
+    pub fn expire_at(&mut self, now: Instant) -> Vec<(Instant, T)> {
+        let target = self.ticks_floor(now.saturating_duration_since(self.start));
+        let mut expired = Vec::new();
+
+        while self.current < target {
+            if self.is_empty() {
+                self.current = target;
+                break;
+            }
+
+            self.current += 1;
+            self.cascade();
+
+            let slot = &mut self.levels[0][Self::slot_index(self.current, 0)];
+            self.len -= slot.len();
+            expired.extend(slot.drain(..).map(|entry| (entry.instant, entry.value)));
+        }
+
+        expired
+    }
+
+    /// Moves the entries due in the current tick from the coarser levels down.
+    fn cascade(&mut self) {
+        for level in (1..LEVELS).rev() {
+            let shift = SLOT_BITS * level as u32;
+            if self.current & ((1 << shift) - 1) != 0 {
+                continue;
+            }
+
+            if level == LEVELS - 1 {
+                for entry in std::mem::take(&mut self.overflow) {
+                    self.place(entry);
+                }
+            }
+
+            let slot = Self::slot_index(self.current, level);
+            for entry in std::mem::take(&mut self.levels[level][slot]) {
+                self.place(entry);
+            }
+        }
+    }
+
+    /// Places an entry into the level matching the distance to its deadline.
+    fn place(&mut self, entry: WheelEntry<T>) {
+        let delta = entry.deadline.saturating_sub(self.current);
+        let level = (0..LEVELS).find(|&level| delta < 1 << (SLOT_BITS * (level as u32 + 1)));
+
+        match level {
+            Some(level) => {
+                let slot = Self::slot_index(entry.deadline, level);
+                self.levels[level][slot].push(entry);
+            }
+            None => self.overflow.push(entry),
+        }
+    }
+
+    fn slot_index(tick: u64, level: usize) -> usize {
+        ((tick >> (SLOT_BITS * level as u32)) as usize) & (SLOTS - 1)
+    }
+
+    fn ticks_floor(&self, duration: Duration) -> u64 {
+        (duration.as_nanos() / self.tick.as_nanos()) as u64
+    }
+
+    fn ticks_ceil(&self, duration: Duration) -> u64 {
+        duration.as_nanos().div_ceil(self.tick.as_nanos()) as u64
+    }
+}
+
+impl<T> Debug for TimingWheel<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TimingWheel")
+            .field("tick", &self.tick)
+            .field("current", &self.current)
+            .field("len", &self.len)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expires_mixed_ttls_in_order() {
+        let mut wheel = TimingWheel::new(Duration::from_millis(1));
+        let now = wheel.start;
+        let ttls = [
+            Duration::from_millis(3),
+            Duration::from_millis(70),
+            Duration::from_secs(5),
+            Duration::from_secs(3600),
+            Duration::from_secs(5 * 3600),
+        ];
+        for (index, ttl) in ttls.iter().enumerate().rev() {
+            wheel.insert_at(now, *ttl, index);
+        }
+
+        for (index, ttl) in ttls.iter().enumerate() {
+            assert!(wheel
+                .expire_at(now + *ttl - Duration::from_millis(1))
+                .is_empty());
+            let expired = wheel.expire_at(now + *ttl);
+            assert_eq!(expired.len(), 1, "element {index}");
+            assert_eq!(expired[0].1, index);
+            assert_eq!(wheel.len(), ttls.len() - index - 1);
+        }
+    }
+}
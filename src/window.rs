@@ -0,0 +1,686 @@
+use crate::{Instant, TtlQueue};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::ops::Range;
+use std::time::Duration;
+
+/// A sub-window of a [`TtlQueue`] covering the elements within a time range.
+///
+/// See [`TtlQueue::hopping_windows`] and [`TtlQueue::chunk_by_duration`].
+#[derive(Debug)]
+pub struct HoppingWindow<'a, T> {
+    queue: &'a TtlQueue<T>,
+    start: Instant,
+    end: Instant,
+    range: Range<usize>,
+}
+
+impl<'a, T> HoppingWindow<'a, T> {
+    /// Gets the inclusive start of the window.
+    pub fn start(&self) -> Instant {
+        self.start
+    }
+
+    /// Gets the exclusive end of the window.
+    pub fn end(&self) -> Instant {
+        self.end
+    }
+
+    /// Gets the number of elements in the window.
+    pub fn len(&self) -> usize {
+        self.range.len()
+    }
+
+    /// Returns `true` if the window contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.range.is_empty()
+    }
+
+    /// Returns an iterator over the elements in the window, oldest first.
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = &'a (Instant, T)> {
+        let queue = self.queue;
+        self.range
+            .clone()
+            .map(move |index| queue.get(index).expect("windows are within the queue"))
+    }
+}
+
+/// A run of consecutive elements of a [`TtlQueue`].
+///
+/// See [`TtlQueue::windows`] and [`TtlQueue::chunks`].
+#[derive(Debug)]
+pub struct CountWindow<'a, T> {
+    queue: &'a TtlQueue<T>,
+    range: Range<usize>,
+}
+
+impl<'a, T> CountWindow<'a, T> {
+    /// Gets the number of elements in the window.
+    pub fn len(&self) -> usize {
+        self.range.len()
+    }
+
+    /// Returns `true` if the window contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.range.is_empty()
+    }
+
+    /// Gets the oldest element of the window.
+    pub fn first(&self) -> Option<&'a (Instant, T)> {
+        self.queue.get(self.range.start)
+    }
+
+    /// Gets the newest element of the window.
+    pub fn last(&self) -> Option<&'a (Instant, T)> {
+        self.queue.get(self.range.end.checked_sub(1)?)
+    }
+
+    /// Gets the duration between the oldest and the newest element of the window.
+    pub fn span(&self) -> Duration {
+        match (self.first(), self.last()) {
+            (Some((first, _)), Some((last, _))) => last.saturating_duration_since(*first),
+            _ => Duration::ZERO,
+        }
+    }
+
+    /// Returns an iterator over the elements in the window, oldest first.
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = &'a (Instant, T)> {
+        let queue = self.queue;
+        self.range
+            .clone()
+            .map(move |index| queue.get(index).expect("windows are within the queue"))
+    }
+}
+
+/// A group of elements without idle gaps larger than a given duration.
+///
+/// See [`TtlQueue::sessions`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Session {
+    /// The timestamp of the first element of the session.
+    pub start: Instant,
+    /// The timestamp of the last element of the session.
+    pub end: Instant,
+    /// The number of elements in the session.
+    pub count: usize,
+}
+
+impl Session {
+    /// Gets the duration between the first and the last element of the session.
+    pub fn span(&self) -> Duration {
+        self.end.saturating_duration_since(self.start)
+    }
+}
+
+impl<T> TtlQueue<T> {
+    /// Groups the unexpired elements into sessions separated by idle gaps larger
+    /// than `max_gap`, oldest session first.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use ttl_queue::{Instant, TtlQueue};
+    /// let now = Instant::now();
+    /// let clicks: Vec<_> = [0, 10, 25, 300, 320]
+    ///     .into_iter()
+    ///     .map(|secs| (now + Duration::from_secs(secs), ()))
+    ///     .collect();
+    /// let mut queue: TtlQueue<()> = TtlQueue::try_from(clicks).unwrap();
+    /// queue.set_ttl(Duration::from_secs(3600));
+    ///
+    /// let sessions = queue.sessions(Duration::from_secs(60));
+    /// assert_eq!(sessions.len(), 2);
+    /// assert_eq!(sessions[0].count, 3);
+    /// assert_eq!(sessions[0].span(), Duration::from_secs(25));
+    /// assert_eq!(sessions[1].count, 2);
+    /// ```
+    pub fn sessions(&self, max_gap: Duration) -> Vec<Session> {
+        let mut sessions: Vec<Session> = Vec::new();
+        for (instant, _) in self.iter_valid() {
+            match sessions.last_mut() {
+                Some(session) if instant.saturating_duration_since(session.end) <= max_gap => {
+                    session.end = *instant;
+                    session.count += 1;
+                }
+                _ => sessions.push(Session {
+                    start: *instant,
+                    end: *instant,
+                    count: 1,
+                }),
+            }
+        }
+        sessions
+    }
+
+    /// Returns the ranges of sequence numbers missing between the smallest and the
+    /// largest sequence number of the unexpired elements, in ascending order.
+    ///
+    /// Sequence numbers are extracted from each element by `extract_seq` and may arrive
+    /// out of order or duplicated. The total number of missing sequence numbers over the
+    /// window is the basis of a packet loss measurement.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use ttl_queue::TtlQueue;
+    /// let mut packets = TtlQueue::new(Duration::from_secs(10));
+    /// for seq in [1u64, 2, 5, 4, 9, 9] {
+    ///     packets.push_back(seq);
+    /// }
+    ///
+    /// let gaps = packets.gaps(|seq| *seq);
+    /// assert_eq!(gaps, [3..4, 6..9]);
+    ///
+    /// let lost: u64 = gaps.iter().map(|gap| gap.end - gap.start).sum();
+    /// assert_eq!(lost, 4);
+    /// ```
+    pub fn gaps<F>(&self, mut extract_seq: F) -> Vec<Range<u64>>
+    where
+        F: FnMut(&T) -> u64,
+    {
+        let mut sequence: Vec<u64> = self
+            .iter_valid()
+            .map(|(_, element)| extract_seq(element))
+            .collect();
+        sequence.sort_unstable();
+        sequence.dedup();
+
+        sequence
+            .windows(2)
+            .filter(|pair| pair[1] - pair[0] > 1)
+            .map(|pair| pair[0] + 1..pair[1])
+            .collect()
+    }
+
+    /// Counts the unexpired elements per key extracted by `extract_key`.
+    ///
+    /// This operation is O(N). To query the breakdown repeatedly, a
+    /// [`KeyedCountWindow`](crate::KeyedCountWindow) maintains the counts per key
+    /// incrementally instead.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use ttl_queue::TtlQueue;
+    /// let mut responses = TtlQueue::new(Duration::from_secs(60));
+    /// for status in [200, 404, 200, 503, 200] {
+    ///     responses.push_back(status);
+    /// }
+    ///
+    /// let by_class = responses.counts_by_key(|status| status / 100);
+    /// assert_eq!(by_class[&2], 3);
+    /// assert_eq!(by_class[&4], 1);
+    /// assert_eq!(by_class[&5], 1);
+    /// ```
+    pub fn counts_by_key<K, F>(&self, mut extract_key: F) -> HashMap<K, usize>
+    where
+        K: Hash + Eq,
+        F: FnMut(&T) -> K,
+    {
+        let mut counts = HashMap::new();
+        for (_, element) in self.iter_valid() {
+            *counts.entry(extract_key(element)).or_default() += 1;
+        }
+        counts
+    }
+
+    /// Returns an iterator over all overlapping windows of `n` consecutive unexpired
+    /// elements, oldest first, like [`slice::windows`].
+    ///
+    /// If there are fewer than `n` unexpired elements, no window is returned. The expired
+    /// prefix is skipped as by [`iter_valid`](Self::iter_valid); with
+    /// [TTL jitter](Self::set_ttl_jitter), elements that expired out of order are included
+    /// until the queue is [refreshed](Self::refresh).
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `n` is zero.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use ttl_queue::TtlQueue;
+    /// let mut samples = TtlQueue::new(Duration::from_secs(60));
+    /// for sample in [1.0, 2.0, 6.0, 3.0] {
+    ///     samples.push_back(sample);
+    /// }
+    ///
+    /// let moving_average: Vec<f64> = samples
+    ///     .windows(2)
+    ///     .map(|window| window.iter().map(|(_, v)| v).sum::<f64>() / 2.0)
+    ///     .collect();
+    /// assert_eq!(moving_average, [1.5, 4.0, 4.5]);
+    /// ```
+    pub fn windows(&self, n: usize) -> impl ExactSizeIterator<Item = CountWindow<'_, T>> {
+        assert_ne!(n, 0, "The window size must not be zero");
+        let start = self.advance_expired_hint(self.now());
+        let count = (self.len() - start + 1).saturating_sub(n);
+        (0..count).map(move |offset| CountWindow {
+            queue: self,
+            range: start + offset..start + offset + n,
+        })
+    }
+
+    /// Returns an iterator over non-overlapping chunks of `n` consecutive unexpired
+    /// elements, oldest first, like [`slice::chunks`]. The last chunk holds the newest
+    /// elements and may be shorter.
+    ///
+    /// See [`windows`](Self::windows) for how expired elements are skipped.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `n` is zero.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use ttl_queue::TtlQueue;
+    /// let mut events = TtlQueue::new(Duration::from_secs(60));
+    /// for event in 0..5 {
+    ///     events.push_back(event);
+    /// }
+    ///
+    /// let sizes: Vec<usize> = events.chunks(2).map(|chunk| chunk.len()).collect();
+    /// assert_eq!(sizes, [2, 2, 1]);
+    /// ```
+    pub fn chunks(&self, n: usize) -> impl ExactSizeIterator<Item = CountWindow<'_, T>> {
+        assert_ne!(n, 0, "The chunk size must not be zero");
+        let start = self.advance_expired_hint(self.now());
+        let end = self.len();
+        (0..(end - start).div_ceil(n)).map(move |index| CountWindow {
+            queue: self,
+            range: start + index * n..end.min(start + (index + 1) * n),
+        })
+    }
+
+    /// Returns an iterator over overlapping sub-windows of the queued elements.
+    ///
+    /// The first window starts at the timestamp of the oldest unexpired element and
+    /// covers `size`; each following window starts `hop` later, until a window starts
+    /// after the newest element. Windows may be empty if the elements are sparse. See
+    /// [`windows`](Self::windows) for how expired elements are skipped.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `hop` is zero.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use ttl_queue::{Instant, TtlQueue};
+    /// let now = Instant::now();
+    /// let samples: Vec<_> = (0..6u64)
+    ///     .map(|i| (now + Duration::from_secs(i), i as f64))
+    ///     .collect();
+    /// let queue = TtlQueue::try_from(samples).unwrap();
+    ///
+    /// let averages: Vec<f64> = queue
+    ///     .hopping_windows(Duration::from_secs(4), Duration::from_secs(2))
+    ///     .map(|window| window.iter().map(|(_, v)| v).sum::<f64>() / window.len() as f64)
+    ///     .collect();
+    ///
+    /// assert_eq!(averages, [1.5, 3.5, 4.5]);
+    /// ```
+    pub fn hopping_windows(
+        &self,
+        size: Duration,
+        hop: Duration,
+    ) -> impl Iterator<Item = HoppingWindow<'_, T>> {
+        assert!(!hop.is_zero(), "The hop must not be zero");
+        let expired = self.advance_expired_hint(self.now());
+        let bounds = self
+            .get(expired)
+            .zip(self.back_entry())
+            .map(|((first, _), (last, _))| (*first, *last));
+
+        let mut next_start = bounds.map(|(first, _)| first);
+        std::iter::from_fn(move || {
+            let (_, last) = bounds?;
+            let start = next_start.filter(|start| *start <= last)?;
+            let end = start
+                .checked_add(size)
+                .unwrap_or(last + Duration::from_nanos(1));
+            next_start = start.checked_add(hop);
+
+            let range = self.position_of(start).max(expired)..self.position_of(end).max(expired);
+            Some(HoppingWindow {
+                queue: self,
+                start,
+                end,
+                range,
+            })
+        })
+    }
+
+    /// Divides the TTL window ending now into consecutive buckets of the specified
+    /// interval and returns an iterator over them, oldest first, e.g. to chart the
+    /// activity per 100 ms of the last 5 seconds.
+    ///
+    /// The last bucket ends at the current time and includes elements added at it.
+    /// Buckets are returned back to the one holding the oldest unexpired element, but
+    /// no further than `ceil(ttl / interval)` buckets, including empty ones in between;
+    /// an empty queue yields no buckets. Since the buckets are aligned to the current
+    /// time, their positions are comparable across calls when counted from the newest.
+    /// Bucket starts that reach back further than the clock can represent are clamped
+    /// to the oldest representable instant. Expired elements are never included.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `interval` is zero.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use ttl_queue::{TickClock, TtlQueue};
+    /// let clock = TickClock::new();
+    /// let mut requests = TtlQueue::new(TickClock::ttl(30));
+    /// requests.set_clock(clock.clone());
+    ///
+    /// for delay in [5, 3, 1, 12, 9] {
+    ///     clock.advance(delay);
+    ///     requests.push_back(());
+    /// }
+    ///
+    /// let counts: Vec<usize> = requests
+    ///     .chunk_by_duration(TickClock::ttl(10))
+    ///     .map(|bucket| bucket.len())
+    ///     .collect();
+    /// assert_eq!(counts, [3, 0, 2]);
+    /// ```
+    pub fn chunk_by_duration(
+        &self,
+        interval: Duration,
+    ) -> impl Iterator<Item = HoppingWindow<'_, T>> {
+        assert!(!interval.is_zero(), "The interval must not be zero");
+        let now = self.now();
+        let expired = self.advance_expired_hint(now);
+        let covered = self
+            .iter_valid_at(expired, now)
+            .next()
+            .map_or(0, |(oldest, _)| {
+                now.saturating_duration_since(*oldest).as_nanos() / interval.as_nanos() + 1
+            });
+        let count = self
+            .ttl()
+            .as_nanos()
+            .div_ceil(interval.as_nanos())
+            .min(covered);
+        let count = u32::try_from(count).unwrap_or(u32::MAX);
+
+        (0..count).map(move |index| {
+            let remaining = count - index;
+            let start = saturating_sub(now, interval.saturating_mul(remaining));
+            let end = saturating_sub(now, interval.saturating_mul(remaining - 1));
+            let last = if remaining == 1 {
+                self.len()
+            } else {
+                self.position_of(end)
+            };
+
+            HoppingWindow {
+                queue: self,
+                start,
+                end,
+                range: self.position_of(start).max(expired)..last.max(expired),
+            }
+        })
+    }
+
+    /// Renders the number of unexpired elements per bucket as a unicode sparkline,
+    /// oldest bucket first, e.g. to visualize the recent activity in logs or CLI tools.
+    ///
+    /// The TTL window ending now is divided into `buckets` equally long buckets. Each
+    /// bucket is drawn as one of the block characters `▁` to `█`, scaled relative to the
+    /// busiest bucket; empty buckets are drawn as `▁`.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `buckets` is zero.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use ttl_queue::{TickClock, TtlQueue};
+    /// let clock = TickClock::new();
+    /// let mut requests = TtlQueue::new(TickClock::ttl(40));
+    /// requests.set_clock(clock.clone());
+    ///
+    /// for delay in [12, 0, 10, 0, 0, 0, 10] {
+    ///     clock.advance(delay);
+    ///     requests.push_back(());
+    /// }
+    /// clock.advance(5);
+    ///
+    /// assert_eq!(requests.sparkline(4), "▁▄█▂");
+    /// ```
+    pub fn sparkline(&self, buckets: usize) -> String {
+        const LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+        assert_ne!(buckets, 0, "The number of buckets must not be zero");
+
+        let now = self.now();
+        let ttl = self.ttl().as_nanos();
+        let mut counts = vec![0usize; buckets];
+        for (instant, _) in self.iter_valid() {
+            let age = now.saturating_duration_since(*instant).as_nanos();
+            let from_back = (age * buckets as u128).checked_div(ttl).unwrap_or(0);
+            if let Some(bucket) = (buckets - 1).checked_sub(from_back as usize) {
+                counts[bucket] += 1;
+            }
+        }
+
+        let max = counts.iter().copied().max().unwrap_or(0).max(1);
+        counts
+            .into_iter()
+            .map(|count| LEVELS[count * (LEVELS.len() - 1) / max])
+            .collect()
+    }
+
+    /// Returns the number of leading elements stamped before the specified instant.
+    fn position_of(&self, instant: Instant) -> usize {
+        self.binary_search_by(|(entry, _)| {
+            if *entry < instant {
+                Ordering::Less
+            } else {
+                Ordering::Greater
+            }
+        })
+        .unwrap_or_else(|index| index)
+    }
+}
+
+/// Subtracts the duration from the instant, saturating at the oldest instant the clock
+/// can represent.
+fn saturating_sub(instant: Instant, duration: Duration) -> Instant {
+    if let Some(result) = instant.checked_sub(duration) {
+        return result;
+    }
+
+    // Search for the longest duration that can still be subtracted.
+    let (mut low, mut high) = (Duration::ZERO, duration);
+    while high - low > Duration::from_nanos(1) {
+        let mid = low + (high - low) / 2;
+        if instant.checked_sub(mid).is_some() {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    instant - low
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TickClock;
+
+    /// Creates a queue with a TTL of 10 ticks holding elements pushed at the specified ticks.
+    fn queue_at(ticks: &[u64]) -> (TickClock, TtlQueue<u64>) {
+        let clock = TickClock::new();
+        let mut queue = TtlQueue::new(TickClock::ttl(10));
+        queue.set_clock(clock.clone());
+        for &tick in ticks {
+            clock.advance(tick - clock.ticks());
+            queue.push_back(tick);
+        }
+        (clock, queue)
+    }
+
+    #[test]
+    fn empty_queue_has_no_groups() {
+        let (_clock, queue) = queue_at(&[]);
+        assert!(queue.sessions(TickClock::ttl(1)).is_empty());
+        assert!(queue.gaps(|seq| *seq).is_empty());
+        assert!(queue.counts_by_key(|value| *value).is_empty());
+        assert_eq!(queue.windows(1).len(), 0);
+        assert_eq!(queue.chunks(1).len(), 0);
+        assert_eq!(
+            queue
+                .hopping_windows(TickClock::ttl(2), TickClock::ttl(1))
+                .count(),
+            0
+        );
+        assert_eq!(queue.chunk_by_duration(TickClock::ttl(5)).count(), 0);
+        assert_eq!(queue.sparkline(3), "▁▁▁");
+    }
+
+    #[test]
+    fn single_element_forms_one_group() {
+        let (_clock, queue) = queue_at(&[4]);
+        let sessions = queue.sessions(Duration::ZERO);
+        assert_eq!(sessions.len(), 1);
+        assert_eq!((sessions[0].count, sessions[0].span()), (1, Duration::ZERO));
+        assert!(queue.gaps(|seq| *seq).is_empty());
+        assert_eq!(queue.counts_by_key(|value| *value)[&4], 1);
+        assert_eq!(queue.windows(1).len(), 1);
+        assert_eq!(queue.windows(2).len(), 0);
+        assert_eq!(
+            queue.chunks(3).map(|chunk| chunk.len()).collect::<Vec<_>>(),
+            [1]
+        );
+        assert_eq!(queue.chunk_by_duration(TickClock::ttl(5)).count(), 1);
+        assert_eq!(queue.sparkline(2), "▁█");
+    }
+
+    #[test]
+    fn expired_elements_are_not_grouped() {
+        let (clock, mut queue) = queue_at(&[1, 2, 3]);
+        clock.advance(20);
+        assert!(queue.sessions(TickClock::ttl(1)).is_empty());
+        assert!(queue.gaps(|seq| *seq).is_empty());
+        assert!(queue.counts_by_key(|value| *value).is_empty());
+        assert_eq!(queue.windows(1).len(), 0);
+        assert_eq!(queue.chunks(2).len(), 0);
+        assert_eq!(
+            queue
+                .hopping_windows(TickClock::ttl(2), TickClock::ttl(1))
+                .count(),
+            0
+        );
+        assert_eq!(queue.chunk_by_duration(TickClock::ttl(5)).count(), 0);
+        assert_eq!(queue.sparkline(2), "▁▁");
+        assert_eq!(queue.refresh(), 0);
+    }
+
+    #[test]
+    fn zero_gap_splits_sessions_at_every_tick() {
+        let (_clock, queue) = queue_at(&[1, 1, 2, 4, 4]);
+        let counts: Vec<_> = queue
+            .sessions(Duration::ZERO)
+            .iter()
+            .map(|session| session.count)
+            .collect();
+        assert_eq!(counts, [2, 1, 2]);
+    }
+
+    #[test]
+    fn gaps_ignore_duplicates_and_order() {
+        let (_clock, queue) = queue_at(&[2, 3, 3, 5, 6, 9]);
+        assert_eq!(queue.gaps(|seq| 10 - *seq), [2..4, 6..7]);
+    }
+
+    #[test]
+    #[should_panic(expected = "The window size must not be zero")]
+    fn zero_window_size_panics() {
+        let (_clock, queue) = queue_at(&[1]);
+        let _ = queue.windows(0);
+    }
+
+    #[test]
+    #[should_panic(expected = "The chunk size must not be zero")]
+    fn zero_chunk_size_panics() {
+        let (_clock, queue) = queue_at(&[1]);
+        let _ = queue.chunks(0);
+    }
+
+    #[test]
+    #[should_panic(expected = "The interval must not be zero")]
+    fn zero_interval_panics() {
+        let (_clock, queue) = queue_at(&[1]);
+        let _ = queue.chunk_by_duration(Duration::ZERO);
+    }
+
+    #[test]
+    #[should_panic(expected = "The number of buckets must not be zero")]
+    fn zero_buckets_panic() {
+        let (_clock, queue) = queue_at(&[1]);
+        let _ = queue.sparkline(0);
+    }
+
+    #[test]
+    #[cfg(not(feature = "mock_instant"))]
+    fn chunk_by_duration_with_unbounded_ttl_stops_at_oldest_element() {
+        let now = Instant::now();
+        let queue: TtlQueue<&str> = TtlQueue::try_from(vec![
+            (now - Duration::from_secs(25), "a"),
+            (now - Duration::from_secs(5), "b"),
+        ])
+        .unwrap();
+        assert_eq!(queue.ttl(), Duration::MAX);
+
+        let counts: Vec<usize> = queue
+            .chunk_by_duration(Duration::from_secs(10))
+            .map(|bucket| bucket.len())
+            .collect();
+        assert_eq!(counts, [1, 0, 1]);
+    }
+
+    #[test]
+    #[cfg(not(feature = "mock_instant"))]
+    fn chunk_by_duration_clamps_unrepresentable_starts() {
+        let now = Instant::now();
+        let oldest = saturating_sub(now, Duration::MAX);
+        let queue: TtlQueue<&str> = TtlQueue::try_from(vec![(oldest, "a"), (now, "b")]).unwrap();
+        let interval = now.saturating_duration_since(oldest) / 2 + Duration::from_secs(1);
+
+        let buckets: Vec<_> = queue.chunk_by_duration(interval).collect();
+        assert_eq!(buckets.len(), 2);
+        assert_eq!(buckets[0].start(), oldest);
+        assert_eq!(buckets[0].len(), 1);
+        assert_eq!(buckets[1].len(), 1);
+    }
+
+    #[test]
+    #[cfg(not(feature = "mock_instant"))]
+    fn hopping_windows_skip_expired_elements() {
+        let now = Instant::now();
+        let mut queue: TtlQueue<u64> = (0..6u64)
+            .map(|i| (now - Duration::from_secs(10 - i), i))
+            .collect::<Vec<_>>()
+            .try_into()
+            .unwrap();
+        queue.set_ttl(Duration::from_secs(8));
+
+        let windows: Vec<Vec<u64>> = queue
+            .hopping_windows(Duration::from_secs(2), Duration::from_secs(2))
+            .map(|window| window.iter().map(|(_, value)| *value).collect())
+            .collect();
+        assert_eq!(windows, [vec![3, 4], vec![5]]);
+    }
+}